@@ -1,8 +1,12 @@
 //NOTE this code is copied over from sdk-config-server with small changes for compatiblity
 //TODO refactor, make eval MJOS agnostic
 
+use std::collections::HashMap;
+
 use crate::{utils::core::MapError, Context, MergeStrategy};
+use serde::Serialize;
 use serde_json::{json, Map, Value};
+use superposition_types::semantic_version::resolve_version_comparisons;
 
 pub fn merge(doc: &mut Value, patch: &Value) {
     if !patch.is_object() {
@@ -52,11 +56,10 @@ fn get_overrides(
         }
     };
 
+    let query_data = json!(query_data);
     for context in contexts {
-        // TODO :: Add semantic version comparator in Lib
-        if let Ok(Value::Bool(true)) =
-            jsonlogic::apply(&context.condition, &json!(query_data))
-        {
+        let condition = resolve_version_comparisons(&context.condition, &query_data);
+        if let Ok(Value::Bool(true)) = jsonlogic::apply(&condition, &query_data) {
             for override_key in &context.override_with_keys {
                 if let Some(overriden_value) = overrides.get(override_key) {
                     match merge_strategy {
@@ -153,3 +156,219 @@ pub fn eval_cac_with_reasoning(
     overriden_config.insert("metadata".into(), json!(reasoning));
     Ok(overriden_config)
 }
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KeyOverride {
+    pub context_id: String,
+    pub condition: Value,
+    pub override_id: String,
+    pub value: Value,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KeyResolution {
+    pub winning_context_id: Option<String>,
+    pub contributing_context_ids: Vec<String>,
+    pub value: Value,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KeyTrace {
+    pub default_value: Value,
+    pub overrides: Vec<KeyOverride>,
+    pub merge: KeyResolution,
+    pub replace: KeyResolution,
+}
+
+/*
+  Under REPLACE the last override of a key wins outright. Under MERGE object
+  overrides are merged on top of each other and on the default value, so every
+  override after the last non-object one (which replaces whatever came before it)
+  contributes to the resolved value.
+*/
+fn resolve_key(default_value: &Value, key_overrides: &[KeyOverride]) -> KeyTrace {
+    let replace = match key_overrides.last() {
+        Some(winner) => KeyResolution {
+            winning_context_id: Some(winner.context_id.clone()),
+            contributing_context_ids: vec![winner.context_id.clone()],
+            value: winner.value.clone(),
+        },
+        None => KeyResolution {
+            winning_context_id: None,
+            contributing_context_ids: vec![],
+            value: default_value.clone(),
+        },
+    };
+
+    let mut merged = default_value.clone();
+    let mut contributing_context_ids = vec![];
+    for key_override in key_overrides {
+        if !key_override.value.is_object() {
+            contributing_context_ids.clear();
+        }
+        merge(&mut merged, &key_override.value);
+        contributing_context_ids.push(key_override.context_id.clone());
+    }
+    let merge = KeyResolution {
+        winning_context_id: key_overrides.last().map(|o| o.context_id.clone()),
+        contributing_context_ids,
+        value: merged,
+    };
+
+    KeyTrace {
+        default_value: default_value.clone(),
+        overrides: key_overrides.to_vec(),
+        merge,
+        replace,
+    }
+}
+
+pub fn eval_cac_with_trace(
+    default_config: &Map<String, Value>,
+    contexts: &[Context],
+    overrides: &Map<String, Value>,
+    query_data: &Map<String, Value>,
+) -> Map<String, Value> {
+    let mut key_overrides: HashMap<String, Vec<KeyOverride>> = HashMap::new();
+
+    let query_data = json!(query_data);
+    for context in contexts {
+        let condition = resolve_version_comparisons(&context.condition, &query_data);
+        if !matches!(
+            jsonlogic::apply(&condition, &query_data),
+            Ok(Value::Bool(true))
+        ) {
+            continue;
+        }
+        for override_key in &context.override_with_keys {
+            let Some(Value::Object(overriden_values)) = overrides.get(override_key)
+            else {
+                continue;
+            };
+            for (key, value) in overriden_values {
+                if !default_config.contains_key(key) {
+                    log::error!("CAC: found non-default_config key: {key} in overrides");
+                    continue;
+                }
+                key_overrides
+                    .entry(key.clone())
+                    .or_default()
+                    .push(KeyOverride {
+                        context_id: context.id.clone(),
+                        condition: context.condition.clone(),
+                        override_id: override_key.clone(),
+                        value: value.clone(),
+                    });
+            }
+        }
+    }
+
+    default_config
+        .iter()
+        .map(|(key, default_value)| {
+            let key_trace = resolve_key(
+                default_value,
+                key_overrides
+                    .get(key)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            );
+            (key.clone(), json!(key_trace))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_override(context_id: &str, value: Value) -> KeyOverride {
+        KeyOverride {
+            context_id: context_id.to_string(),
+            condition: json!({}),
+            override_id: format!("{context_id}-override"),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_resolve_key_without_overrides() {
+        let trace = resolve_key(&json!(10), &[]);
+        for resolution in [trace.merge, trace.replace] {
+            assert_eq!(resolution.winning_context_id, None);
+            assert!(resolution.contributing_context_ids.is_empty());
+            assert_eq!(resolution.value, json!(10));
+        }
+    }
+
+    #[test]
+    fn test_resolve_key_merge_and_replace() {
+        let trace = resolve_key(
+            &json!({"a": 1, "b": 1}),
+            &[
+                key_override("c1", json!({"a": 2})),
+                key_override("c2", json!({"b": 2})),
+                key_override("c3", json!({"c": 3})),
+            ],
+        );
+        assert_eq!(trace.replace.winning_context_id.as_deref(), Some("c3"));
+        assert_eq!(trace.replace.contributing_context_ids, vec!["c3"]);
+        assert_eq!(trace.replace.value, json!({"c": 3}));
+        assert_eq!(trace.merge.winning_context_id.as_deref(), Some("c3"));
+        assert_eq!(trace.merge.contributing_context_ids, vec!["c1", "c2", "c3"]);
+        assert_eq!(trace.merge.value, json!({"a": 2, "b": 2, "c": 3}));
+
+        // a non object override replaces everything merged before it
+        let trace = resolve_key(
+            &json!({"a": 1}),
+            &[
+                key_override("c1", json!({"a": 2})),
+                key_override("c2", json!(null)),
+                key_override("c3", json!({"b": 3})),
+            ],
+        );
+        assert_eq!(trace.merge.contributing_context_ids, vec!["c2", "c3"]);
+        assert_eq!(trace.merge.value, json!({"b": 3}));
+    }
+
+    #[test]
+    fn test_eval_cac_with_trace() {
+        let default_config = json!({"key1": 1, "key2": 2})
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        let contexts = vec![
+            Context {
+                id: "c1".to_string(),
+                condition: json!({"==": [{"var": "city"}, "Bangalore"]}),
+                override_with_keys: ["o1".to_string()],
+            },
+            Context {
+                id: "c2".to_string(),
+                condition: json!({"==": [{"var": "city"}, "Delhi"]}),
+                override_with_keys: ["o2".to_string()],
+            },
+        ];
+        let overrides = json!({
+            "o1": {"key1": 10, "unknown": 0},
+            "o2": {"key2": 20},
+        })
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+        let query_data = json!({"city": "Bangalore"})
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+
+        let trace =
+            eval_cac_with_trace(&default_config, &contexts, &overrides, &query_data);
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace["key1"]["replace"]["winning_context_id"], json!("c1"));
+        assert_eq!(trace["key1"]["replace"]["value"], json!(10));
+        assert_eq!(trace["key1"]["overrides"][0]["override_id"], json!("o1"));
+        assert_eq!(trace["key2"]["overrides"], json!([]));
+        assert_eq!(trace["key2"]["merge"]["winning_context_id"], Value::Null);
+        assert_eq!(trace["key2"]["merge"]["value"], json!(2));
+    }
+}
@@ -133,6 +133,16 @@ pub extern "C" fn cac_start_polling_update(tenant: *const c_char) {
     }
 }
 
+#[no_mangle]
+pub extern "C" fn cac_start_streaming_update(tenant: *const c_char) {
+    null_check!(tenant, "NULL pointer provided for tenant", return);
+    unsafe {
+        let client = cac_get_client(tenant);
+        null_check!(client, "CAC client for tenant not found", return);
+        let _handle = CAC_RUNTIME.spawn((*client).clone().run_streaming_updates());
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn cac_free_client(ptr: *mut Arc<Client>) {
     if ptr.is_null() {
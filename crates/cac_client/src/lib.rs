@@ -10,17 +10,27 @@ use std::{
     time::{Duration, UNIX_EPOCH},
 };
 
-use actix_web::{rt::time::interval, web::Data};
+use actix_web::{
+    rt::time::{interval, timeout},
+    web::Data,
+};
 use chrono::{DateTime, Utc};
 use derive_more::{Deref, DerefMut};
 use reqwest::{RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
+use superposition_types::semantic_version::resolve_version_comparisons;
 use tokio::sync::RwLock;
-use utils::core::MapError;
+use utils::{core::MapError, sse::EventParser};
+
+const CONFIG_VERSION_EVENT: &str = "config-version";
+// the server sends a keep-alive comment every 15 seconds
+const STREAM_READ_TIMEOUT: Duration = Duration::from_secs(45);
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Context {
+    #[serde(default)]
+    pub id: String,
     pub condition: Value,
     pub override_with_keys: [String; 1],
 }
@@ -33,7 +43,90 @@ pub struct Config {
     pub default_configs: Map<String, Value>,
 }
 
-#[derive(strum_macros::EnumString)]
+#[derive(Deserialize, Clone, Debug)]
+pub struct KeyDeprecation {
+    pub sunset_at: DateTime<Utc>,
+    pub replacement_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PositionedContext {
+    position: usize,
+    #[serde(flatten)]
+    context: Context,
+}
+
+#[derive(Deserialize)]
+struct ContextsDelta {
+    upserted: Vec<PositionedContext>,
+    removed: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct MapDelta {
+    upserted: Map<String, Value>,
+    removed: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ConfigDelta {
+    to_version: i64,
+    contexts: ContextsDelta,
+    overrides: MapDelta,
+    default_configs: MapDelta,
+}
+
+impl MapDelta {
+    fn apply(self, map: &mut Map<String, Value>) {
+        for key in self.removed {
+            map.remove(&key);
+        }
+        map.extend(self.upserted);
+    }
+}
+
+impl Config {
+    fn apply_delta(&mut self, delta: ConfigDelta) -> Result<(), String> {
+        let ConfigDelta {
+            contexts,
+            overrides,
+            default_configs,
+            ..
+        } = delta;
+        let mut upserted = contexts.upserted;
+        upserted.sort_by_key(|ctx| ctx.position);
+        let replaced_ids = contexts
+            .removed
+            .into_iter()
+            .chain(upserted.iter().map(|ctx| ctx.context.id.clone()))
+            .collect::<HashSet<String>>();
+
+        // validate before mutating, so that a delta that does not match the
+        // config is rejected without leaving the config half updated
+        let retained = self
+            .contexts
+            .iter()
+            .filter(|ctx| !replaced_ids.contains(&ctx.id))
+            .count();
+        let is_misaligned = upserted
+            .iter()
+            .enumerate()
+            .any(|(index, ctx)| ctx.position > retained + index);
+        if is_misaligned {
+            return Err("config delta does not match the current config".to_string());
+        }
+
+        self.contexts.retain(|ctx| !replaced_ids.contains(&ctx.id));
+        for PositionedContext { position, context } in upserted {
+            self.contexts.insert(position, context);
+        }
+        overrides.apply(&mut self.overrides);
+        default_configs.apply(&mut self.default_configs);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, strum_macros::EnumString, strum_macros::Display)]
 #[strum(serialize_all = "snake_case")]
 pub enum MergeStrategy {
     MERGE,
@@ -61,9 +154,16 @@ impl From<String> for MergeStrategy {
 pub struct Client {
     tenant: String,
     reqw: Data<reqwest::RequestBuilder>,
+    stream_reqw: Data<reqwest::RequestBuilder>,
     polling_interval: Duration,
     last_modified: Data<RwLock<DateTime<Utc>>>,
+    etag: Data<RwLock<Option<String>>>,
+    config_version: Data<RwLock<Option<i64>>>,
+    delta_reqw: Data<reqwest::RequestBuilder>,
     config: Data<RwLock<Config>>,
+    deprecated_keys: Data<RwLock<HashMap<String, KeyDeprecation>>>,
+    // deprecated keys already warned about for the current config version
+    warned_deprecated_keys: Data<RwLock<HashSet<String>>>,
 }
 
 fn clone_reqw(reqw: &RequestBuilder) -> Result<RequestBuilder, String> {
@@ -71,6 +171,35 @@ fn clone_reqw(reqw: &RequestBuilder) -> Result<RequestBuilder, String> {
         .ok_or_else(|| "Unable to clone reqw".to_string())
 }
 
+fn get_etag(resp: &Response) -> Option<String> {
+    resp.headers()
+        .get("etag")
+        .and_then(|header_val| header_val.to_str().ok())
+        .map(String::from)
+}
+
+fn get_config_version(resp: &Response) -> Option<i64> {
+    resp.headers()
+        .get("x-config-version")
+        .and_then(|header_val| header_val.to_str().ok())
+        .and_then(|version| version.parse::<i64>().ok())
+}
+
+// keys of the served config that are deprecated, missing when there are none
+fn get_deprecated_keys(resp: &Response) -> HashMap<String, KeyDeprecation> {
+    resp.headers()
+        .get("x-deprecated-keys")
+        .and_then(|header_val| header_val.to_str().ok())
+        .and_then(|header_str| {
+            serde_json::from_str(header_str)
+                .map_err(|e| {
+                    log::error!("Failed to parse deprecated keys: {e}");
+                })
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
 fn get_last_modified(resp: &Response) -> Option<DateTime<Utc>> {
     resp.headers().get("last-modified").and_then(|header_val| {
         let header_str = header_val.to_str().ok()?;
@@ -94,10 +223,20 @@ impl Client {
         let reqw = reqw_client
             .get(cac_endpoint)
             .header("x-tenant", tenant.to_string());
+        let stream_reqw = reqw_client
+            .get(format!("{hostname}/config/stream"))
+            .header("x-tenant", tenant.to_string())
+            .header("Accept", "text/event-stream");
+        let delta_reqw = reqw_client
+            .get(format!("{hostname}/config/delta"))
+            .header("x-tenant", tenant.to_string());
 
         let reqwc = clone_reqw(&reqw)?;
         let resp = reqwc.send().await.map_err_to_string()?;
         let last_modified_at = get_last_modified(&resp);
+        let etag = get_etag(&resp);
+        let config_version = get_config_version(&resp);
+        let deprecated_keys = get_deprecated_keys(&resp);
         if resp.status().is_client_error() {
             return Err("Invalid tenant".to_string());
         }
@@ -106,19 +245,30 @@ impl Client {
         let client = Client {
             tenant,
             reqw: Data::new(reqw),
+            stream_reqw: Data::new(stream_reqw),
             polling_interval,
             last_modified: Data::new(RwLock::new(
                 last_modified_at.unwrap_or(DateTime::<Utc>::from(UNIX_EPOCH)),
             )),
+            etag: Data::new(RwLock::new(etag)),
+            config_version: Data::new(RwLock::new(config_version)),
+            delta_reqw: Data::new(delta_reqw),
             config: Data::new(RwLock::new(config)),
+            deprecated_keys: Data::new(RwLock::new(deprecated_keys)),
+            warned_deprecated_keys: Data::new(RwLock::new(HashSet::new())),
         };
         Ok(client)
     }
 
     async fn fetch(&self) -> Result<reqwest::Response, String> {
-        let last_modified = self.last_modified.read().await;
-        let reqw = clone_reqw(&self.reqw)?
-            .header("If-Modified-Since", last_modified.to_rfc2822());
+        let etag = self.etag.read().await.clone();
+        let reqw = match etag {
+            Some(etag) => clone_reqw(&self.reqw)?.header("If-None-Match", etag),
+            None => {
+                let last_modified = self.last_modified.read().await.to_rfc2822();
+                clone_reqw(&self.reqw)?.header("If-Modified-Since", last_modified)
+            }
+        };
         let resp = reqw.send().await.map_err_to_string()?;
         match resp.status() {
             StatusCode::NOT_MODIFIED => {
@@ -136,15 +286,96 @@ impl Client {
         Ok(resp)
     }
 
+    async fn fetch_delta(
+        &self,
+        since_version: i64,
+    ) -> Result<
+        Option<(ConfigDelta, Option<String>, HashMap<String, KeyDeprecation>)>,
+        String,
+    > {
+        let resp = clone_reqw(&self.delta_reqw)?
+            .query(&[("since_version", since_version)])
+            .send()
+            .await
+            .map_err_to_string()?;
+        match resp.status() {
+            StatusCode::NOT_MODIFIED => Ok(None),
+            StatusCode::OK => {
+                let etag = get_etag(&resp);
+                let deprecated_keys = get_deprecated_keys(&resp);
+                let delta = resp.json::<ConfigDelta>().await.map_err_to_string()?;
+                Ok(Some((delta, etag, deprecated_keys)))
+            }
+            x => Err(format!(
+                "{} CAC: delta fetch failed, status: {}",
+                self.tenant, x
+            )),
+        }
+    }
+
+    /*
+      Applies only the changes since the config version held by the client,
+      falling back to fetching the full config when the delta can't be applied.
+    */
+    async fn update_cac_from_delta(&self) -> Result<String, String> {
+        let mut config_version = self.config_version.write().await;
+        let since_version =
+            config_version.ok_or_else(|| "config version is not known".to_string())?;
+        let Some((delta, fetched_etag, fetched_deprecated_keys)) =
+            self.fetch_delta(since_version).await?
+        else {
+            return Ok(format!(
+                "{} CAC: skipping update, remote not modified",
+                self.tenant
+            ));
+        };
+
+        let mut config = self.config.write().await;
+        let mut etag = self.etag.write().await;
+        let mut deprecated_keys = self.deprecated_keys.write().await;
+        let mut warned_deprecated_keys = self.warned_deprecated_keys.write().await;
+        let to_version = delta.to_version;
+        config.apply_delta(delta)?;
+        *config_version = Some(to_version);
+        *etag = fetched_etag;
+        *deprecated_keys = fetched_deprecated_keys;
+        warned_deprecated_keys.clear();
+        Ok(format!(
+            "{}: CAC updated to version {} successfully",
+            self.tenant, to_version
+        ))
+    }
+
     async fn update_cac(&self) -> Result<String, String> {
+        let has_config_version = self.config_version.read().await.is_some();
+        if has_config_version {
+            match self.update_cac_from_delta().await {
+                Ok(result) => return Ok(result),
+                Err(e) => log::info!(
+                    "{} CAC: unable to apply config delta, fetching full config: {e}",
+                    self.tenant
+                ),
+            }
+        }
+
         let fetched_config = self.fetch().await?;
+        let mut config_version = self.config_version.write().await;
         let mut config = self.config.write().await;
         let mut last_modified = self.last_modified.write().await;
+        let mut etag = self.etag.write().await;
+        let mut deprecated_keys = self.deprecated_keys.write().await;
+        let mut warned_deprecated_keys = self.warned_deprecated_keys.write().await;
         let last_modified_at = get_last_modified(&fetched_config);
+        let fetched_etag = get_etag(&fetched_config);
+        let fetched_version = get_config_version(&fetched_config);
+        *deprecated_keys = get_deprecated_keys(&fetched_config);
+        warned_deprecated_keys.clear();
         *config = fetched_config.json::<Config>().await.map_err_to_string()?;
         if let Some(val) = last_modified_at {
             *last_modified = val;
         }
+        *etag = fetched_etag;
+        *config_version = fetched_version;
         Ok(format!("{}: CAC updated successfully", self.tenant))
     }
 
@@ -157,6 +388,59 @@ impl Client {
         }
     }
 
+    async fn listen_to_config_versions(&self) -> Result<(), String> {
+        let mut resp = clone_reqw(&self.stream_reqw)?
+            .send()
+            .await
+            .map_err_to_string()?;
+        if !resp.status().is_success() {
+            return Err(format!(
+                "{} CAC: subscription failed, status: {}",
+                self.tenant,
+                resp.status()
+            ));
+        }
+
+        let mut parser = EventParser::default();
+        loop {
+            let chunk = timeout(STREAM_READ_TIMEOUT, resp.chunk())
+                .await
+                .map_err(|_| format!("{} CAC: subscription timed out", self.tenant))?
+                .map_err_to_string()?;
+            let Some(chunk) = chunk else {
+                return Ok(());
+            };
+            for event in parser.feed(&chunk) {
+                if event.event.as_deref() == Some(CONFIG_VERSION_EVENT) {
+                    log::info!(
+                        "{} CAC: config version {} published",
+                        self.tenant,
+                        event.data
+                    );
+                    let result = self.update_cac().await.unwrap_or_else(identity);
+                    log::info!("{result}");
+                }
+            }
+        }
+    }
+
+    /// Subscribes to config version events from the server and updates the
+    /// config as soon as a new version is published. Whenever the subscription
+    /// is unavailable, the config is polled every `polling_interval` until the
+    /// subscription can be re-established.
+    pub async fn run_streaming_updates(self: Arc<Self>) {
+        let mut interval = interval(self.polling_interval);
+        loop {
+            interval.tick().await;
+            match self.listen_to_config_versions().await {
+                Ok(()) => log::info!("{} CAC: subscription closed", self.tenant),
+                Err(e) => log::error!("{} CAC: subscription failed: {e}", self.tenant),
+            }
+            let result = self.update_cac().await.unwrap_or_else(identity);
+            log::info!("{result}");
+        }
+    }
+
     pub async fn get_full_config_state_with_filter(
         &self,
         query_data: Option<Map<String, Value>>,
@@ -179,6 +463,37 @@ impl Client {
         Ok(config)
     }
 
+    async fn warn_deprecated_keys(&self, config: &Map<String, Value>) {
+        let deprecated_keys = self.deprecated_keys.read().await;
+        // most lookups have nothing left to warn about, which a read lock settles
+        let has_unwarned_keys = {
+            let warned_deprecated_keys = self.warned_deprecated_keys.read().await;
+            config.keys().any(|key| {
+                deprecated_keys.contains_key(key) && !warned_deprecated_keys.contains(key)
+            })
+        };
+        if !has_unwarned_keys {
+            return;
+        }
+        let mut warned_deprecated_keys = self.warned_deprecated_keys.write().await;
+        for (key, deprecation) in config
+            .keys()
+            .filter_map(|key| deprecated_keys.get_key_value(key))
+            .filter(|(key, _)| warned_deprecated_keys.insert(key.to_string()))
+        {
+            let replacement = deprecation
+                .replacement_key
+                .as_ref()
+                .map(|replacement_key| format!(", use {replacement_key} instead"))
+                .unwrap_or_default();
+            log::warn!(
+                "{} CAC: {key} is deprecated and will be removed on {}{replacement}",
+                self.tenant,
+                deprecation.sunset_at
+            );
+        }
+    }
+
     pub async fn get_last_modified(&self) -> DateTime<Utc> {
         self.last_modified.read().await.clone()
     }
@@ -208,9 +523,28 @@ impl Client {
         if let Some(keys) = filter_keys {
             cac = filter_keys_by_prefix(cac, keys);
         }
+        self.warn_deprecated_keys(&cac).await;
         Ok(cac)
     }
 
+    pub async fn get_resolution_trace(
+        &self,
+        query_data: Map<String, Value>,
+        filter_keys: Option<Vec<String>>,
+    ) -> Result<Map<String, Value>, String> {
+        let cac = self.config.read().await;
+        let mut trace = eval::eval_cac_with_trace(
+            &cac.default_configs,
+            &cac.contexts,
+            &cac.overrides,
+            &query_data,
+        );
+        if let Some(keys) = filter_keys {
+            trace = filter_keys_by_prefix(trace, keys);
+        }
+        Ok(trace)
+    }
+
     pub async fn get_default_config(
         &self,
         filter_keys: Option<Vec<String>>,
@@ -220,6 +554,7 @@ impl Client {
         if let Some(keys) = filter_keys {
             default_configs = filter_keys_by_prefix(default_configs, keys);
         }
+        self.warn_deprecated_keys(&default_configs).await;
         Ok(default_configs)
     }
 }
@@ -260,7 +595,9 @@ pub static CLIENT_FACTORY: Lazy<ClientFactory> =
 
 pub use eval::eval_cac;
 pub use eval::eval_cac_with_reasoning;
+pub use eval::eval_cac_with_trace;
 pub use eval::merge;
+pub use eval::{KeyOverride, KeyResolution, KeyTrace};
 
 pub fn filter_keys_by_prefix(
     keys: Map<String, Value>,
@@ -324,11 +661,14 @@ pub fn filter_config_by_dimensions(
     config: &Config,
     dimension_data: &Map<String, Value>,
 ) -> Config {
+    let dimension_data = json!(dimension_data);
     let filtered_context = config
         .contexts
         .iter()
         .filter_map(|context| {
-            match jsonlogic::partial_apply(&context.condition, &json!(dimension_data)) {
+            let condition =
+                resolve_version_comparisons(&context.condition, &dimension_data);
+            match jsonlogic::partial_apply(&condition, &dimension_data) {
                 Ok(jsonlogic::PartialApplyOutcome::Resolved(Value::Bool(true)))
                 | Ok(jsonlogic::PartialApplyOutcome::Ambiguous) => Some(context.clone()),
                 _ => None,
@@ -355,3 +695,93 @@ pub fn filter_config_by_dimensions(
 
     filtered_config
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(id: &str) -> Value {
+        json!({
+            "id": id,
+            "condition": {"==": [{"var": "city"}, id]},
+            "override_with_keys": [format!("{id}-override")],
+        })
+    }
+
+    fn positioned_context(id: &str, position: usize) -> Value {
+        let mut context = context(id);
+        context["position"] = json!(position);
+        context
+    }
+
+    fn build_config() -> Config {
+        serde_json::from_value(json!({
+            "contexts": [context("c1"), context("c2"), context("c3")],
+            "overrides": {
+                "c1-override": {"key1": 1},
+                "c2-override": {"key1": 2},
+                "c3-override": {"key1": 3},
+            },
+            "default_configs": {"key1": 0, "key2": 0},
+        }))
+        .expect("invalid config")
+    }
+
+    fn build_delta(
+        upserted_contexts: Vec<Value>,
+        removed_contexts: Vec<&str>,
+    ) -> ConfigDelta {
+        serde_json::from_value(json!({
+            "to_version": 2,
+            "contexts": {
+                "upserted": upserted_contexts,
+                "removed": removed_contexts,
+            },
+            "overrides": {
+                "upserted": {"c4-override": {"key1": 4}},
+                "removed": ["c2-override"],
+            },
+            "default_configs": {
+                "upserted": {"key3": 0},
+                "removed": ["key2"],
+            },
+        }))
+        .expect("invalid delta")
+    }
+
+    fn context_ids(config: &Config) -> Vec<&str> {
+        config.contexts.iter().map(|ctx| ctx.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let mut config = build_config();
+        let delta = build_delta(
+            vec![positioned_context("c4", 0), positioned_context("c3", 2)],
+            vec!["c2"],
+        );
+
+        assert_eq!(config.apply_delta(delta), Ok(()));
+        assert_eq!(context_ids(&config), vec!["c4", "c1", "c3"]);
+        assert_eq!(
+            json!(config.overrides),
+            json!({
+                "c1-override": {"key1": 1},
+                "c3-override": {"key1": 3},
+                "c4-override": {"key1": 4},
+            })
+        );
+        assert_eq!(json!(config.default_configs), json!({"key1": 0, "key3": 0}));
+    }
+
+    #[test]
+    fn test_apply_misaligned_delta() {
+        let mut config = build_config();
+        let delta = build_delta(vec![positioned_context("c4", 5)], vec![]);
+
+        assert!(config.apply_delta(delta).is_err());
+        // the config is left untouched
+        assert_eq!(context_ids(&config), vec!["c1", "c2", "c3"]);
+        assert_eq!(json!(config.default_configs), json!({"key1": 0, "key2": 0}));
+    }
+}
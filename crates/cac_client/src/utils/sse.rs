@@ -0,0 +1,96 @@
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Event {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incrementally parses a `text/event-stream` body, collecting events as
+/// the blank line terminating each of them is received.
+#[derive(Default)]
+pub struct EventParser {
+    buffer: Vec<u8>,
+}
+
+impl EventParser {
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buffer
+            .extend(chunk.iter().filter(|byte| **byte != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block = self.buffer.drain(..end + 2).collect::<Vec<u8>>();
+            if let Some(event) = parse_event(&String::from_utf8_lossy(&block)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn parse_event(block: &str) -> Option<Event> {
+    let mut event = None;
+    let mut data = Vec::new();
+    for line in block.lines().filter(|line| !line.starts_with(':')) {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = Some(value.to_string()),
+            "data" => data.push(value),
+            _ => (),
+        }
+    }
+
+    if event.is_none() && data.is_empty() {
+        return None;
+    }
+    Some(Event {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_parser_across_chunks() {
+        let mut parser = EventParser::default();
+        assert_eq!(parser.feed(b"event: config-version\nda"), vec![]);
+        assert_eq!(
+            parser.feed(b"ta: 42\n\nevent: config-version\ndata: 43\n"),
+            vec![Event {
+                event: Some("config-version".to_string()),
+                data: "42".to_string(),
+            }]
+        );
+        assert_eq!(
+            parser.feed(b"\n"),
+            vec![Event {
+                event: Some("config-version".to_string()),
+                data: "43".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_event_parser_fields() {
+        let mut parser = EventParser::default();
+        let events = parser.feed(
+            b": keep-alive\r\n\r\ndata: first\r\ndata:second\r\nid: 1\r\n\r\nevent: ping\n\n",
+        );
+        assert_eq!(
+            events,
+            vec![
+                Event {
+                    event: None,
+                    data: "first\nsecond".to_string(),
+                },
+                Event {
+                    event: Some("ping".to_string()),
+                    data: String::new(),
+                },
+            ]
+        );
+    }
+}
@@ -92,6 +92,22 @@ impl ContextAwareConfig {
         let toml_value =
             toml::from_str(&toml_content).expect("Failed to parse the TOML file");
 
+        ContextAwareConfig::from_toml_value(file, toml_value)
+    }
+
+    pub fn parse_str(toml_content: &str) -> Result<ContextAwareConfig, CACParseError> {
+        let toml_value = toml::from_str(toml_content).map_err(|e| {
+            eprintln!("Failed to parse the TOML content, Error: {}", e);
+            CACParseError
+        })?;
+
+        ContextAwareConfig::from_toml_value("<input>", toml_value)
+    }
+
+    fn from_toml_value(
+        file: &str,
+        toml_value: Value,
+    ) -> Result<ContextAwareConfig, CACParseError> {
         let mut cac: ContextAwareConfig = ContextAwareConfig {
             file: String::from(file),
             dimension_priority: HashMap::new(),
@@ -210,6 +226,61 @@ impl ContextAwareConfig {
         true
     }
 
+    /// Default configs in file order, as `(key, value, schema)`.
+    pub fn default_configs(&self) -> Vec<(String, Value, Value)> {
+        self.toml_value
+            .get("default-config")
+            .and_then(Value::as_table)
+            .map(|table| {
+                table
+                    .iter()
+                    .map(|(key, config)| {
+                        (
+                            key.to_string(),
+                            config.get("value").unwrap().clone(),
+                            config.get("schema").unwrap().clone(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Dimensions in file order, as `(name, priority, schema)`. The priority of
+    /// a dimension is its position in the file, starting from 1.
+    pub fn dimensions(&self) -> Vec<(String, i64, Value)> {
+        self.toml_value
+            .get("dimensions")
+            .and_then(Value::as_table)
+            .map(|table| {
+                table
+                    .iter()
+                    .map(|(name, dimension)| {
+                        (
+                            name.to_string(),
+                            *self.dimension_priority.get(name).unwrap(),
+                            dimension.get("schema").unwrap().clone(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Contexts in file order, with each context expression converted to a
+    /// JSONLogic condition, as `(condition, overrides)`.
+    pub fn contexts(&self) -> Result<Vec<(serde_json::Value, Value)>, CACParseError> {
+        let Some(table) = self.toml_value.get("context").and_then(Value::as_table) else {
+            return Ok(Vec::new());
+        };
+        table
+            .iter()
+            .map(|(context_expression, overrides)| {
+                Ok((to_jsonlogic(context_expression)?, overrides.clone()))
+            })
+            .collect()
+    }
+
     pub fn get_resolved_config(
         &self,
         dimensions: &HashMap<String, Value>,
@@ -308,6 +379,90 @@ fn compute_priority(
     priority
 }
 
+/// Converts a context expression like `$city == 'Delhi' && $hour_of_day >= 18`
+/// into the equivalent JSONLogic condition.
+pub fn to_jsonlogic(
+    context_expression: &str,
+) -> Result<serde_json::Value, CACParseError> {
+    let mut parsed =
+        CACParser::parse(Rule::expression, context_expression).map_err(|e| {
+            eprintln!(
+                "Could not parse expression: {}, Error: {}",
+                context_expression, e
+            );
+            CACParseError
+        })?;
+    parsed
+        .next()
+        .ok_or(CACParseError)
+        .and_then(expression_to_jsonlogic)
+}
+
+fn next_operand(
+    pairs: &mut pest::iterators::Pairs<Rule>,
+) -> Result<serde_json::Value, CACParseError> {
+    pairs
+        .next()
+        .ok_or(CACParseError)
+        .and_then(expression_to_jsonlogic)
+}
+
+fn expression_to_jsonlogic(pair: Pair<Rule>) -> Result<serde_json::Value, CACParseError> {
+    match pair.as_rule() {
+        Rule::expression | Rule::term => next_operand(&mut pair.into_inner()),
+        Rule::logical_or | Rule::logical_and => {
+            let operator = match pair.as_rule() {
+                Rule::logical_or => "or",
+                _ => "and",
+            };
+            let mut operands = pair
+                .into_inner()
+                .map(expression_to_jsonlogic)
+                .collect::<Result<Vec<serde_json::Value>, CACParseError>>()?;
+            match operands.len() {
+                1 => operands.pop().ok_or(CACParseError),
+                _ => Ok(serde_json::json!({ operator: operands })),
+            }
+        }
+        Rule::comparison => {
+            let mut pairs = pair.into_inner();
+            let left = next_operand(&mut pairs)?;
+            let operator = pairs.next().ok_or(CACParseError)?.as_str();
+            let right = next_operand(&mut pairs)?;
+            Ok(serde_json::json!({ operator: [left, right] }))
+        }
+        Rule::dimension => Ok(serde_json::json!({ "var": &pair.as_str()[1..] })),
+        Rule::bool_literal => Ok(serde_json::Value::Bool(pair.as_str() == "true")),
+        Rule::string_literal => {
+            let string_literal = pair.as_str();
+            let len = string_literal.len();
+            Ok(serde_json::Value::String(
+                string_literal[1..(len - 1)].to_string(),
+            ))
+        }
+        Rule::integer => pair
+            .as_str()
+            .parse::<i64>()
+            .map(|integer| serde_json::json!(integer))
+            .map_err(|e| {
+                eprintln!("Could not parse integer: {}, Error: {}", pair.as_str(), e);
+                CACParseError
+            }),
+        Rule::float => pair
+            .as_str()
+            .parse::<f64>()
+            .map(|float| serde_json::json!(float))
+            .map_err(|e| {
+                eprintln!("Could not parse float: {}, Error: {}", pair.as_str(), e);
+                CACParseError
+            }),
+        rule => {
+            eprintln!("Unexpected rule in context expression: {:?}", rule);
+            Err(CACParseError)
+        }
+    }
+}
+
 fn evaluate_context_expression(
     pair: Pair<Rule>,
     dimensions: &HashMap<String, Value>,
@@ -477,3 +632,75 @@ fn evaluate_context_expression(
         _ => panic!("Unexpected rule: {:?}", pair.as_rule()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_jsonlogic() {
+        assert_eq!(
+            to_jsonlogic("$city == 'Delhi'").ok(),
+            Some(json!({"==": [{"var": "city"}, "Delhi"]}))
+        );
+        assert_eq!(
+            to_jsonlogic("$city == 'Delhi' && $hour_of_day >= 18 || $is_holiday == true")
+                .ok(),
+            Some(json!({"or": [
+                {"and": [
+                    {"==": [{"var": "city"}, "Delhi"]},
+                    {">=": [{"var": "hour_of_day"}, 18]},
+                ]},
+                {"==": [{"var": "is_holiday"}, true]},
+            ]}))
+        );
+        assert_eq!(
+            to_jsonlogic("$surge_factor < 1.5").ok(),
+            Some(json!({"<": [{"var": "surge_factor"}, 1.5]}))
+        );
+    }
+
+    #[test]
+    fn test_to_jsonlogic_rejects_invalid_expressions() {
+        assert!(to_jsonlogic("").is_err());
+        assert!(to_jsonlogic("$city = 'Delhi'").is_err());
+        assert!(to_jsonlogic("$city == 'Delhi' &&").is_err());
+        assert!(to_jsonlogic("$hour_of_day >= 99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_contexts() {
+        let cac = ContextAwareConfig::parse_str(
+            r#"
+            [default-config]
+            per_km_rate = { "value" = 20.0, "schema" = { "type" = "number" } }
+
+            [dimensions]
+            city = { schema = { "type" = "string" } }
+
+            [context."$city == 'Delhi'"]
+            per_km_rate = 25.0
+            "#,
+        )
+        .expect("failed to parse cac toml");
+        let contexts = cac.contexts().expect("failed to convert contexts");
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].0, json!({"==": [{"var": "city"}, "Delhi"]}));
+        assert_eq!(contexts[0].1.get("per_km_rate"), Some(&Value::Float(25.0)));
+
+        let cac = ContextAwareConfig::parse_str(
+            r#"
+            [default-config]
+            per_km_rate = { "value" = 20.0, "schema" = { "type" = "number" } }
+
+            [dimensions]
+            city = { schema = { "type" = "string" } }
+
+            [context."$city = 'Delhi'"]
+            per_km_rate = 25.0
+            "#,
+        );
+        assert!(cac.map_or(true, |cac| cac.contexts().is_err()));
+    }
+}
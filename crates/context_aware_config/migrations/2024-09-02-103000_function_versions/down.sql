@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE public.contexts
+drop column function_versions;
+
+ALTER TABLE public.functions
+drop column published_version;
+
+DROP TABLE IF EXISTS public.function_versions;
@@ -0,0 +1,33 @@
+-- Your SQL goes here
+-- Name: function_versions; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.function_versions (
+    function_name text NOT NULL REFERENCES public.functions(function_name) ON DELETE CASCADE,
+    version integer NOT NULL,
+    code text NOT NULL,
+    runtime_version VARCHAR(16) NOT NULL,
+    description text NOT NULL,
+    published_by text NOT NULL,
+    published_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (function_name, version)
+);
+--
+-- Name: function_versions function_versions_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER function_versions_audit AFTER INSERT OR DELETE OR UPDATE ON public.function_versions FOR EACH ROW EXECUTE FUNCTION public.event_logger();
+
+ALTER TABLE public.functions
+add column published_version integer;
+
+ALTER TABLE public.contexts
+add column function_versions json DEFAULT '{}'::json NOT NULL;
+
+-- functions published so far become their first version
+INSERT INTO public.function_versions (function_name, version, code, runtime_version, description, published_by, published_at)
+SELECT function_name, 1, published_code, published_runtime_version, function_description, COALESCE(published_by, draft_edited_by), COALESCE(published_at, CURRENT_TIMESTAMP)
+FROM public.functions
+WHERE published_code IS NOT NULL AND published_runtime_version IS NOT NULL;
+
+UPDATE public.functions
+SET published_version = 1
+WHERE published_code IS NOT NULL AND published_runtime_version IS NOT NULL;
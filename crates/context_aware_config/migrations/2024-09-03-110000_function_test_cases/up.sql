@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE public.functions
+add column test_cases json DEFAULT '[]'::json NOT NULL;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Name: dimension_aliases; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.dimension_aliases (
+    alias character varying NOT NULL PRIMARY KEY,
+    dimension character varying NOT NULL REFERENCES public.dimensions(dimension) ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp without time zone NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: dimension_aliases dimension_aliases_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER dimension_aliases_audit AFTER INSERT OR DELETE OR UPDATE ON public.dimension_aliases FOR EACH ROW EXECUTE FUNCTION public.event_logger();
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Name: default_config_aliases; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.default_config_aliases (
+    alias character varying NOT NULL PRIMARY KEY,
+    key character varying NOT NULL REFERENCES public.default_configs(key) ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp without time zone NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: default_config_aliases default_config_aliases_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER default_config_aliases_audit AFTER INSERT OR DELETE OR UPDATE ON public.default_config_aliases FOR EACH ROW EXECUTE FUNCTION public.event_logger();
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Name: default_config_deprecations; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.default_config_deprecations (
+    key character varying NOT NULL PRIMARY KEY REFERENCES public.default_configs(key) ON UPDATE CASCADE ON DELETE CASCADE,
+    sunset_at timestamp without time zone NOT NULL,
+    replacement_key character varying REFERENCES public.default_configs(key) ON UPDATE CASCADE ON DELETE SET NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: default_config_deprecations default_config_deprecations_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER default_config_deprecations_audit AFTER INSERT OR DELETE OR UPDATE ON public.default_config_deprecations FOR EACH ROW EXECUTE FUNCTION public.event_logger();
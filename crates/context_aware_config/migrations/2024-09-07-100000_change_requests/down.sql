@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS public.change_request_comments;
+DROP TABLE IF EXISTS public.change_requests;
+DROP TYPE IF EXISTS public.change_request_status;
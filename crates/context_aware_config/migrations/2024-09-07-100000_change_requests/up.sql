@@ -0,0 +1,45 @@
+-- Your SQL goes here
+-- Name: change_request_status; Type: TYPE; Schema: public; Owner: -
+--
+CREATE TYPE public.change_request_status AS ENUM (
+    'PENDING',
+    'APPLIED',
+    'REJECTED'
+);
+--
+-- Name: change_requests; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.change_requests (
+    id bigint NOT NULL PRIMARY KEY,
+    status public.change_request_status NOT NULL,
+    change json NOT NULL,
+    diff json NOT NULL,
+    config_tags character varying[],
+    reviewers character varying[] DEFAULT '{}'::character varying[] NOT NULL,
+    author json NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    reviewed_by character varying,
+    reviewed_at timestamp without time zone,
+    config_version bigint
+);
+CREATE INDEX IF NOT EXISTS change_requests_status_index ON public.change_requests(status);
+--
+-- Name: change_requests change_requests_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER change_requests_audit AFTER INSERT OR DELETE OR UPDATE ON public.change_requests FOR EACH ROW EXECUTE FUNCTION public.event_logger();
+--
+-- Name: change_request_comments; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.change_request_comments (
+    id bigint NOT NULL PRIMARY KEY,
+    change_request_id bigint NOT NULL REFERENCES public.change_requests(id) ON DELETE CASCADE,
+    comment text NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS change_request_comments_change_request_id_index ON public.change_request_comments(change_request_id);
+--
+-- Name: change_request_comments change_request_comments_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER change_request_comments_audit AFTER INSERT OR DELETE OR UPDATE ON public.change_request_comments FOR EACH ROW EXECUTE FUNCTION public.event_logger();
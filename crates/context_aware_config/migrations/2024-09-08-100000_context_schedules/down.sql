@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS public.archived_contexts;
+
+DROP INDEX IF EXISTS public.contexts_active_until_index;
+
+ALTER TABLE public.contexts
+drop constraint if exists contexts_active_window_check,
+drop column active_from,
+drop column active_until;
@@ -0,0 +1,33 @@
+-- Your SQL goes here
+ALTER TABLE public.contexts
+add column active_from timestamp with time zone,
+add column active_until timestamp with time zone,
+add constraint contexts_active_window_check CHECK (active_from < active_until);
+--
+-- Name: contexts_active_until_index; Type: INDEX; Schema: public; Owner: -
+--
+CREATE INDEX IF NOT EXISTS contexts_active_until_index ON public.contexts USING btree (active_until) WHERE active_until IS NOT NULL;
+--
+-- Name: archived_contexts; Type: TABLE; Schema: public; Owner: -
+--
+CREATE TABLE public.archived_contexts (
+    id character varying NOT NULL,
+    value json NOT NULL,
+    override_id character varying NOT NULL,
+    created_at timestamp with time zone NOT NULL,
+    created_by character varying NOT NULL,
+    priority integer NOT NULL,
+    override json NOT NULL,
+    last_modified_at timestamp without time zone NOT NULL,
+    last_modified_by character varying(200) NOT NULL,
+    function_versions json NOT NULL,
+    active_from timestamp with time zone,
+    active_until timestamp with time zone,
+    archived_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    archived_by character varying NOT NULL,
+    PRIMARY KEY (id, archived_at)
+);
+--
+-- Name: archived_contexts archived_contexts_audit; Type: TRIGGER; Schema: public; Owner: -
+--
+CREATE TRIGGER archived_contexts_audit AFTER INSERT OR DELETE OR UPDATE ON public.archived_contexts FOR EACH ROW EXECUTE FUNCTION public.event_logger();
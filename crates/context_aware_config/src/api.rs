@@ -1,7 +1,9 @@
 pub mod audit_log;
+pub mod change_requests;
 pub mod config;
 pub mod context;
 pub mod default_config;
 pub mod dimension;
 pub mod functions;
+pub mod tenant;
 pub mod type_templates;
@@ -0,0 +1,9 @@
+mod handlers;
+mod helpers;
+mod types;
+pub use handlers::endpoints;
+pub use helpers::{
+    is_experiment_change, is_experiment_condition, parse_reviewers,
+    reject_unreviewed_change, submit_change_request,
+};
+pub use types::ChangeOperation;
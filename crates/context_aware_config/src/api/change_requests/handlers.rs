@@ -0,0 +1,303 @@
+use actix_web::{
+    get, post,
+    web::{Data, Json, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse, Scope,
+};
+use chrono::Utc;
+use diesel::{
+    r2d2::{ConnectionManager, PooledConnection},
+    Connection, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
+    RunQueryDsl,
+};
+use service_utils::{
+    helpers::{generate_snowflake_id, parse_config_tags},
+    service::types::{
+        AppHeader, AppState, CustomHeaders, DbConnection, InternalRequest, Tenant,
+    },
+};
+use superposition_macros::{bad_argument, not_found};
+use superposition_types::{
+    result as superposition, PaginatedResponse, QueryFilters, TenantConfig, User,
+};
+
+use crate::{
+    api::change_requests::{
+        helpers::{
+            apply_change_request, ensure_can_approve, ensure_can_reject, parse_reviewers,
+            submit_change_request,
+        },
+        types::{
+            ChangeOperation, ChangeRequestFilters, ChangeRequestResponse, CommentReq,
+            ReviewReq,
+        },
+    },
+    db::{
+        models::{ChangeRequest, ChangeRequestComment, ChangeRequestStatus},
+        schema::{change_request_comments, change_requests},
+    },
+    helpers::{add_config_version, experimentation_namespace},
+};
+
+type DBConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+pub fn endpoints() -> Scope {
+    Scope::new("")
+        .service(list_change_requests)
+        .service(submit_change_request_handler)
+        .service(get_change_request)
+        .service(approve_change_request)
+        .service(reject_change_request)
+        .service(add_comment_handler)
+}
+
+#[get("")]
+async fn list_change_requests(
+    db_conn: DbConnection,
+    filters: Query<QueryFilters>,
+    change_request_filters: Query<ChangeRequestFilters>,
+) -> superposition::Result<Json<PaginatedResponse<ChangeRequest>>> {
+    let DbConnection(mut conn) = db_conn;
+
+    let query_builder = |filters: &ChangeRequestFilters| {
+        let mut builder = change_requests::table.into_boxed();
+        if let Some(status) = filters.status {
+            builder = builder.filter(change_requests::status.eq(status));
+        }
+        builder
+    };
+    let total_items: i64 = query_builder(&change_request_filters)
+        .count()
+        .get_result(&mut conn)?;
+
+    let limit = filters.count.unwrap_or(10);
+    let offset = (filters.page.unwrap_or(1) - 1) * limit;
+    let data: Vec<ChangeRequest> = query_builder(&change_request_filters)
+        .order(change_requests::created_at.desc())
+        .limit(limit)
+        .offset(offset)
+        .load(&mut conn)?;
+    let total_pages = (total_items as f64 / limit as f64).ceil() as i64;
+
+    Ok(Json(PaginatedResponse {
+        total_pages,
+        total_items,
+        data,
+    }))
+}
+
+/*
+  Changes are submitted for review through their own endpoints, this one takes
+  the changes superposition makes on behalf of its other services, like the
+  conclusion of an experiment.
+*/
+#[post("")]
+async fn submit_change_request_handler(
+    state: Data<AppState>,
+    http_req: HttpRequest,
+    custom_headers: CustomHeaders,
+    req: Json<ChangeOperation>,
+    mut db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    if http_req.extensions().get::<InternalRequest>().is_none() {
+        return Err(bad_argument!(
+            "Changes are submitted for review through their own endpoints"
+        ));
+    }
+    submit_change_request(
+        &state,
+        &mut db_conn,
+        req.into_inner(),
+        &user,
+        parse_config_tags(custom_headers.config_tags)?,
+        parse_reviewers(custom_headers.reviewers),
+        &tenant_config,
+    )
+}
+
+#[get("/{id}")]
+async fn get_change_request(
+    path: Path<i64>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<ChangeRequestResponse>> {
+    let DbConnection(mut conn) = db_conn;
+    let id = path.into_inner();
+
+    let change_request = change_requests::table
+        .find(id)
+        .get_result::<ChangeRequest>(&mut conn)
+        .optional()?
+        .ok_or_else(|| not_found!("Change request {} doesn't exist", id))?;
+    let comments = change_request_comments::table
+        .filter(change_request_comments::change_request_id.eq(id))
+        .order(change_request_comments::created_at.asc())
+        .load::<ChangeRequestComment>(&mut conn)?;
+
+    Ok(Json(ChangeRequestResponse {
+        change_request,
+        comments,
+    }))
+}
+
+// locks the change request, so that it can't be reviewed twice at the same time
+fn fetch_pending_change_request(
+    id: i64,
+    conn: &mut DBConnection,
+) -> superposition::Result<ChangeRequest> {
+    let change_request = change_requests::table
+        .find(id)
+        .for_update()
+        .get_result::<ChangeRequest>(conn)
+        .optional()?
+        .ok_or_else(|| not_found!("Change request {} doesn't exist", id))?;
+    if change_request.status != ChangeRequestStatus::PENDING {
+        return Err(bad_argument!(
+            "Change request {} is already {:?}",
+            id,
+            change_request.status
+        ));
+    }
+    Ok(change_request)
+}
+
+fn add_comment(
+    state: &Data<AppState>,
+    change_request_id: i64,
+    comment: String,
+    user: &User,
+    conn: &mut DBConnection,
+) -> superposition::Result<ChangeRequestComment> {
+    if comment.trim().is_empty() {
+        return Err(bad_argument!("Comment can't be empty"));
+    }
+    let comment = ChangeRequestComment {
+        id: generate_snowflake_id(state)?,
+        change_request_id,
+        comment,
+        created_by: user.get_email(),
+        created_at: Utc::now().naive_utc(),
+    };
+    diesel::insert_into(change_request_comments::table)
+        .values(&comment)
+        .execute(conn)?;
+    Ok(comment)
+}
+
+#[post("/{id}/approve")]
+async fn approve_change_request(
+    state: Data<AppState>,
+    path: Path<i64>,
+    req: Json<ReviewReq>,
+    db_conn: DbConnection,
+    tenant: Option<Tenant>,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
+    let id = path.into_inner();
+    let ReviewReq { comment } = req.into_inner();
+    let exp_schema = experimentation_namespace(&state, tenant.as_ref())?;
+
+    let (change_request, version_id) = conn
+        .transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let change_request = fetch_pending_change_request(id, transaction_conn)?;
+            ensure_can_approve(&change_request, &user)?;
+
+            apply_change_request(
+                &change_request,
+                &exp_schema,
+                transaction_conn,
+                &tenant_config,
+            )?;
+            let version_id = add_config_version(
+                &state,
+                change_request.config_tags.clone(),
+                transaction_conn,
+            )?;
+
+            if let Some(comment) = comment {
+                add_comment(&state, id, comment, &user, transaction_conn)?;
+            }
+            let change_request = diesel::update(change_requests::table)
+                .filter(change_requests::id.eq(id))
+                .set((
+                    change_requests::status.eq(ChangeRequestStatus::APPLIED),
+                    change_requests::reviewed_by.eq(user.get_email()),
+                    change_requests::reviewed_at.eq(Utc::now().naive_utc()),
+                    change_requests::config_version.eq(version_id),
+                ))
+                .get_result::<ChangeRequest>(transaction_conn)?;
+            Ok((change_request, version_id))
+        })?;
+    log::info!(
+        "change request {id} by {} approved by {}",
+        change_request.created_by,
+        user.get_email()
+    );
+
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            AppHeader::XConfigVersion.to_string(),
+            version_id.to_string(),
+        ))
+        .json(change_request))
+}
+
+#[post("/{id}/reject")]
+async fn reject_change_request(
+    state: Data<AppState>,
+    path: Path<i64>,
+    req: Json<ReviewReq>,
+    db_conn: DbConnection,
+    user: User,
+) -> superposition::Result<Json<ChangeRequest>> {
+    let DbConnection(mut conn) = db_conn;
+    let id = path.into_inner();
+    let ReviewReq { comment } = req.into_inner();
+
+    let change_request =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let change_request = fetch_pending_change_request(id, transaction_conn)?;
+            ensure_can_reject(&change_request, &user)?;
+
+            if let Some(comment) = comment {
+                add_comment(&state, id, comment, &user, transaction_conn)?;
+            }
+            Ok(diesel::update(change_requests::table)
+                .filter(change_requests::id.eq(id))
+                .set((
+                    change_requests::status.eq(ChangeRequestStatus::REJECTED),
+                    change_requests::reviewed_by.eq(user.get_email()),
+                    change_requests::reviewed_at.eq(Utc::now().naive_utc()),
+                ))
+                .get_result::<ChangeRequest>(transaction_conn)?)
+        })?;
+    log::info!("change request {id} rejected by {}", user.get_email());
+
+    Ok(Json(change_request))
+}
+
+#[post("/{id}/comments")]
+async fn add_comment_handler(
+    state: Data<AppState>,
+    path: Path<i64>,
+    req: Json<CommentReq>,
+    db_conn: DbConnection,
+    user: User,
+) -> superposition::Result<Json<ChangeRequestComment>> {
+    let DbConnection(mut conn) = db_conn;
+    let id = path.into_inner();
+
+    let exists = change_requests::table
+        .find(id)
+        .select(change_requests::id)
+        .get_result::<i64>(&mut conn)
+        .optional()?
+        .is_some();
+    if !exists {
+        return Err(not_found!("Change request {} doesn't exist", id));
+    }
+    let comment = add_comment(&state, id, req.into_inner().comment, &user, &mut conn)?;
+    Ok(Json(comment))
+}
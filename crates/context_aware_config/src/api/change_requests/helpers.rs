@@ -0,0 +1,548 @@
+use actix_web::{
+    web::{Data, Json},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use diesel::{
+    r2d2::{ConnectionManager, PooledConnection},
+    sql_types::{BigInt, Text},
+    Connection, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde_json::{from_value, json, Value};
+use service_utils::{
+    helpers::{extract_dimensions, generate_snowflake_id},
+    service::types::{AppState, InternalRequest},
+};
+use superposition_macros::{bad_argument, unexpected_error};
+use superposition_types::{result as superposition, Cac, Condition, TenantConfig, User};
+
+use crate::{
+    api::{
+        change_requests::types::ChangeOperation,
+        config::compute_config_diff,
+        context::{apply_context_actions, override_helper, put, ContextAction},
+        default_config::{delete_default_config, put_default_config},
+    },
+    db::{
+        models::{ChangeRequest, ChangeRequestStatus},
+        schema::{change_requests, contexts},
+    },
+    helpers::generate_cac,
+};
+
+type DBConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+pub fn parse_reviewers(reviewers: Option<String>) -> Vec<String> {
+    reviewers
+        .map(|reviewers| {
+            reviewers
+                .split(',')
+                .map(str::trim)
+                .filter(|reviewer| !reviewer.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn is_experiment_condition(condition: &Condition) -> superposition::Result<bool> {
+    Ok(extract_dimensions(condition)?.contains_key("variantIds"))
+}
+
+fn is_experiment_context(
+    conn: &mut DBConnection,
+    context_id: &str,
+) -> superposition::Result<bool> {
+    let value: Option<Value> = contexts::table
+        .find(context_id)
+        .select(contexts::value)
+        .first(conn)
+        .optional()?;
+    let Some(Value::Object(condition)) = value else {
+        return Ok(false);
+    };
+    let condition = Cac::<Condition>::try_from_db(condition).map_err(|err| {
+        log::error!("failed to decode context {context_id} from db: {err}");
+        unexpected_error!(err)
+    })?;
+    is_experiment_condition(&condition.into_inner())
+}
+
+/*
+  Contexts of experiments, which carry the variantIds dimension, are created,
+  updated and discarded by the experimentation platform as experiments go
+  through their own lifecycle, so these changes aren't held back for review.
+  Anything else it sends is reviewed like the changes of any other client.
+*/
+pub fn is_experiment_change(
+    req: &HttpRequest,
+    actions: &[ContextAction],
+    conn: &mut DBConnection,
+) -> superposition::Result<bool> {
+    if req.extensions().get::<InternalRequest>().is_none() {
+        return Ok(false);
+    }
+    for action in actions {
+        let is_experiment_action = match action {
+            ContextAction::Put(put_req) => {
+                is_experiment_condition(&put_req.context.clone().into_inner())?
+            }
+            ContextAction::Delete(ctx_id) => is_experiment_context(conn, ctx_id)?,
+            ContextAction::Move((ctx_id, move_req)) => {
+                is_experiment_context(conn, ctx_id)?
+                    && is_experiment_condition(&move_req.context.clone().into_inner())?
+            }
+        };
+        if !is_experiment_action {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Rejects changes that can't be submitted as change requests while the tenant requires them.
+pub fn reject_unreviewed_change(
+    tenant_config: &TenantConfig,
+    change: &str,
+) -> superposition::Result<()> {
+    if tenant_config.require_change_requests {
+        return Err(bad_argument!(
+            "{} can't go through a change request and is disabled while change requests are required",
+            change
+        ));
+    }
+    Ok(())
+}
+
+/// Applies `change` on behalf of `author`, expected to run inside a transaction.
+pub fn apply_change(
+    change: ChangeOperation,
+    conn: &mut DBConnection,
+    author: &User,
+    tenant_config: &TenantConfig,
+) -> superposition::Result<()> {
+    match change {
+        ChangeOperation::PutContext(req) => {
+            put(Json(req), conn, true, author, tenant_config)?;
+        }
+        ChangeOperation::UpdateOverride(req) => {
+            override_helper(Json(req), conn, true, author, tenant_config)?;
+        }
+        ChangeOperation::BulkOperations(actions) => {
+            apply_context_actions(actions, conn, author, tenant_config)?;
+        }
+        ChangeOperation::PutDefaultConfig {
+            key,
+            request,
+            force,
+        } => {
+            put_default_config(key, request, force, conn, author)?;
+        }
+        ChangeOperation::DeleteDefaultConfig { key } => {
+            delete_default_config(&key, conn, author)?;
+        }
+        ChangeOperation::ConcludeExperiment { changes, .. } => {
+            for change in changes {
+                apply_change(change, conn, author, tenant_config)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies `change` like [`apply_change`] and returns the diff it makes to the config.
+fn apply_change_with_diff(
+    change: ChangeOperation,
+    conn: &mut DBConnection,
+    author: &User,
+    tenant_config: &TenantConfig,
+) -> superposition::Result<Value> {
+    let before = generate_cac(conn)?;
+    apply_change(change, conn, author, tenant_config)?;
+    let after = generate_cac(conn)?;
+    Ok(json!(compute_config_diff(&before, &after)))
+}
+
+/*
+  The config may have changed since the change request was submitted, in which
+  case applying it now makes a different change than the one that was reviewed.
+*/
+fn ensure_reviewed_diff(
+    change_request: &ChangeRequest,
+    diff: &Value,
+) -> superposition::Result<()> {
+    if change_request.diff != *diff {
+        return Err(bad_argument!(
+            "The config has changed since change request {} was submitted and it no longer makes the reviewed changes, submit it again",
+            change_request.id
+        ));
+    }
+    Ok(())
+}
+
+/// Applies an approved change request, expected to run inside a transaction.
+pub fn apply_change_request(
+    change_request: &ChangeRequest,
+    exp_schema: &str,
+    conn: &mut DBConnection,
+    tenant_config: &TenantConfig,
+) -> superposition::Result<()> {
+    let id = change_request.id;
+    let change =
+        from_value::<ChangeOperation>(change_request.change.clone()).map_err(|err| {
+            log::error!("failed to decode change request {id}: {err}");
+            unexpected_error!("Failed to decode change request {}", id)
+        })?;
+    let author = from_value::<User>(change_request.author.clone()).map_err(|err| {
+        log::error!("failed to decode author of change request {id}: {err}");
+        unexpected_error!("Failed to decode change request {}", id)
+    })?;
+
+    if let ChangeOperation::ConcludeExperiment {
+        experiment_id,
+        chosen_variant,
+        ..
+    } = &change
+    {
+        conclude_experiment(conn, exp_schema, *experiment_id, chosen_variant, &author)?;
+    }
+    let diff = apply_change_with_diff(change, conn, &author, tenant_config)?;
+    ensure_reviewed_diff(change_request, &diff)
+}
+
+fn conclude_experiment(
+    conn: &mut DBConnection,
+    exp_schema: &str,
+    experiment_id: i64,
+    chosen_variant: &str,
+    author: &User,
+) -> superposition::Result<()> {
+    let concluded = diesel::sql_query(format!(
+        r#"UPDATE "{exp_schema}".experiments SET status = 'CONCLUDED', chosen_variant = $1, last_modified = now(), last_modified_by = $2 WHERE id = $3 AND status IN ('CREATED', 'INPROGRESS')"#
+    ))
+    .bind::<Text, _>(chosen_variant)
+    .bind::<Text, _>(author.get_email())
+    .bind::<BigInt, _>(experiment_id)
+    .execute(conn)?;
+    if concluded == 0 {
+        return Err(bad_argument!(
+            "Experiment {} doesn't exist or is already concluded",
+            experiment_id
+        ));
+    }
+    Ok(())
+}
+
+/*
+  Superposition doesn't authenticate users. Callers without the internal token
+  all act as the default user, and the identity sent along with the internal
+  token in x-user is taken as is. Authors can't be told apart from reviewers
+  when both are anonymous, so only identified authors are kept from reviewing
+  their own changes and anonymous callers are never named reviewers.
+*/
+fn is_anonymous(email: &str) -> bool {
+    email == User::default().email
+}
+
+pub fn ensure_can_approve(
+    change_request: &ChangeRequest,
+    user: &User,
+) -> superposition::Result<()> {
+    let email = user.get_email();
+    if !is_anonymous(&email) && change_request.created_by == email {
+        return Err(bad_argument!(
+            "Change request {} has to be approved by someone other than its author",
+            change_request.id
+        ));
+    }
+    if !change_request.reviewers.is_empty() && !change_request.reviewers.contains(&email)
+    {
+        return Err(bad_argument!(
+            "Change request {} can only be approved by {}",
+            change_request.id,
+            change_request.reviewers.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+pub fn ensure_can_reject(
+    change_request: &ChangeRequest,
+    user: &User,
+) -> superposition::Result<()> {
+    let email = user.get_email();
+    // authors can always withdraw their change requests
+    let is_author = change_request.created_by == email;
+    if !is_author
+        && !change_request.reviewers.is_empty()
+        && !change_request.reviewers.contains(&email)
+    {
+        return Err(bad_argument!(
+            "Change request {} can only be rejected by {}",
+            change_request.id,
+            change_request.reviewers.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn new_change_request(
+    id: i64,
+    change: &ChangeOperation,
+    diff: Value,
+    user: &User,
+    tags: Option<Vec<String>>,
+    reviewers: Vec<String>,
+) -> ChangeRequest {
+    // the token of the author is not kept around
+    let author = User {
+        auth_token: String::new(),
+        ..user.clone()
+    };
+    ChangeRequest {
+        id,
+        status: ChangeRequestStatus::PENDING,
+        change: json!(change),
+        diff,
+        config_tags: tags,
+        reviewers: reviewers
+            .into_iter()
+            .filter(|reviewer| *reviewer != user.email && !is_anonymous(reviewer))
+            .collect(),
+        author: json!(author),
+        created_by: user.get_email(),
+        created_at: Utc::now().naive_utc(),
+        reviewed_by: None,
+        reviewed_at: None,
+        config_version: None,
+    }
+}
+
+/*
+  Instead of being applied, the change is stored as a pending change request
+  along with the diff it makes to the config. The diff is computed by applying
+  the change inside a savepoint that is rolled back, which also rejects changes
+  that would fail validation before anyone is asked to review them.
+*/
+pub fn submit_change_request(
+    state: &Data<AppState>,
+    conn: &mut DBConnection,
+    change: ChangeOperation,
+    user: &User,
+    tags: Option<Vec<String>>,
+    reviewers: Vec<String>,
+    tenant_config: &TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    let id = generate_snowflake_id(state)?;
+    let change_request =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            diesel::sql_query("SAVEPOINT change_request_savepoint")
+                .execute(transaction_conn)?;
+            let diff = apply_change_with_diff(
+                change.clone(),
+                transaction_conn,
+                user,
+                tenant_config,
+            )?;
+            diesel::sql_query("ROLLBACK TO change_request_savepoint")
+                .execute(transaction_conn)?;
+
+            let change_request =
+                new_change_request(id, &change, diff, user, tags, reviewers);
+            diesel::insert_into(change_requests::table)
+                .values(&change_request)
+                .execute(transaction_conn)?;
+            Ok(change_request)
+        })?;
+    log::info!(
+        "change request {} submitted by {}",
+        change_request.id,
+        user.get_email()
+    );
+    Ok(HttpResponse::Accepted().json(change_request))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn condition(value: Value) -> Condition {
+        let Value::Object(map) = value else {
+            panic!("condition should be an object");
+        };
+        Cac::<Condition>::try_from(map).unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_is_experiment_condition() {
+        let experiment = condition(json!({
+            "and": [
+                {"==": [{"var": "city"}, "Delhi"]},
+                {"in": ["7-control", {"var": "variantIds"}]}
+            ]
+        }));
+        assert_eq!(is_experiment_condition(&experiment).ok(), Some(true));
+
+        let config = condition(json!({"==": [{"var": "city"}, "Delhi"]}));
+        assert_eq!(is_experiment_condition(&config).ok(), Some(false));
+    }
+
+    fn user(email: &str) -> User {
+        User {
+            email: email.to_string(),
+            ..User::default()
+        }
+    }
+
+    fn change_request(author: &str, reviewers: &[&str]) -> ChangeRequest {
+        new_change_request(
+            1,
+            &ChangeOperation::DeleteDefaultConfig {
+                key: "logo".to_string(),
+            },
+            json!({}),
+            &user(author),
+            None,
+            reviewers.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[test]
+    fn test_new_change_request() {
+        let anonymous = User::default().email;
+        let change_request = change_request(
+            "alice@superposition.io",
+            &["alice@superposition.io", &anonymous, "bob@superposition.io"],
+        );
+        assert_eq!(change_request.status, ChangeRequestStatus::PENDING);
+        assert_eq!(change_request.created_by, "alice@superposition.io");
+        assert_eq!(
+            change_request.reviewers,
+            vec!["bob@superposition.io".to_string()]
+        );
+        assert_eq!(change_request.author["auth_token"], json!(""));
+        assert_eq!(
+            change_request.change,
+            json!({"operation": "DELETE_DEFAULT_CONFIG", "payload": {"key": "logo"}})
+        );
+    }
+
+    #[test]
+    fn test_ensure_can_approve() {
+        let anonymous = User::default();
+        let open = change_request("alice@superposition.io", &[]);
+        assert!(ensure_can_approve(&open, &user("bob@superposition.io")).is_ok());
+        assert!(ensure_can_approve(&open, &anonymous).is_ok());
+        let err = ensure_can_approve(&open, &user("alice@superposition.io"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("someone other than its author"), "{err}");
+
+        let reviewed =
+            change_request("alice@superposition.io", &["bob@superposition.io"]);
+        assert!(ensure_can_approve(&reviewed, &user("bob@superposition.io")).is_ok());
+        let err = ensure_can_approve(&reviewed, &user("carol@superposition.io"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("can only be approved by bob@superposition.io"),
+            "{err}"
+        );
+        assert!(ensure_can_approve(&reviewed, &anonymous).is_err());
+
+        // anonymous callers can't be told apart, so they can review each other
+        let anonymous_change = change_request(&anonymous.email, &[]);
+        assert!(ensure_can_approve(&anonymous_change, &anonymous).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_can_reject() {
+        let reviewed =
+            change_request("alice@superposition.io", &["bob@superposition.io"]);
+        assert!(ensure_can_reject(&reviewed, &user("alice@superposition.io")).is_ok());
+        assert!(ensure_can_reject(&reviewed, &user("bob@superposition.io")).is_ok());
+        let err = ensure_can_reject(&reviewed, &user("carol@superposition.io"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("can only be rejected by bob@superposition.io"),
+            "{err}"
+        );
+
+        let open = change_request("alice@superposition.io", &[]);
+        assert!(ensure_can_reject(&open, &user("carol@superposition.io")).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_reviewed_diff() {
+        let mut change_request = change_request("alice@superposition.io", &[]);
+        change_request.diff = json!({"default_configs": {"logo": {"old": "a.png"}}});
+        assert!(
+            ensure_reviewed_diff(&change_request, &change_request.diff.clone()).is_ok()
+        );
+        let err = ensure_reviewed_diff(
+            &change_request,
+            &json!({"default_configs": {"logo": {"old": "b.png"}}}),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("no longer makes the reviewed changes"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_decode_experiment_conclusion() {
+        // sent by the experimentation platform when concluding an experiment
+        let change = from_value::<ChangeOperation>(json!({
+            "operation": "CONCLUDE_EXPERIMENT",
+            "payload": {
+                "experiment_id": 7,
+                "chosen_variant": "7-test",
+                "changes": [
+                    {
+                        "operation": "PUT_DEFAULT_CONFIG",
+                        "payload": {"key": "logo", "request": {"value": "b.png"}, "force": false}
+                    },
+                    {"operation": "BULK_OPERATIONS", "payload": [{"DELETE": "ctx-1"}]}
+                ]
+            }
+        }))
+        .unwrap();
+        let ChangeOperation::ConcludeExperiment {
+            experiment_id,
+            chosen_variant,
+            changes,
+        } = change
+        else {
+            panic!("expected an experiment conclusion");
+        };
+        assert_eq!(experiment_id, 7);
+        assert_eq!(chosen_variant, "7-test");
+        assert!(matches!(
+            changes.as_slice(),
+            [
+                ChangeOperation::PutDefaultConfig { key, .. },
+                ChangeOperation::BulkOperations(actions),
+            ] if key == "logo" && actions.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_reject_unreviewed_change() {
+        let tenant_config = |require_change_requests| TenantConfig {
+            mandatory_dimensions: Vec::new(),
+            require_change_requests,
+        };
+        assert!(
+            reject_unreviewed_change(&tenant_config(false), "Renaming a key").is_ok()
+        );
+        let err = reject_unreviewed_change(&tenant_config(true), "Renaming a key")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Renaming a key can't go through"), "{err}");
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{
+        context::{ContextAction, PutReq},
+        default_config::CreateReq,
+    },
+    db::models::{ChangeRequest, ChangeRequestComment, ChangeRequestStatus},
+};
+
+/*
+  A mutation waiting for approval, stored with the request it was submitted
+  with so that approving it goes through the same path as the API.
+*/
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(
+    tag = "operation",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+pub enum ChangeOperation {
+    PutContext(PutReq),
+    UpdateOverride(PutReq),
+    BulkOperations(Vec<ContextAction>),
+    PutDefaultConfig {
+        key: String,
+        request: CreateReq,
+        force: bool,
+    },
+    DeleteDefaultConfig {
+        key: String,
+    },
+    // the overrides of the winning variant, the experiment concludes along with them
+    ConcludeExperiment {
+        experiment_id: i64,
+        chosen_variant: String,
+        changes: Vec<ChangeOperation>,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct ChangeRequestFilters {
+    pub status: Option<ChangeRequestStatus>,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewReq {
+    pub comment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CommentReq {
+    pub comment: String,
+}
+
+#[derive(Serialize)]
+pub struct ChangeRequestResponse {
+    #[serde(flatten)]
+    pub change_request: ChangeRequest,
+    pub comments: Vec<ChangeRequestComment>,
+}
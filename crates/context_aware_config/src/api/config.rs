@@ -1,4 +1,7 @@
 mod handlers;
 pub mod types;
 pub use handlers::endpoints;
+#[cfg(test)]
+pub(crate) use helpers::tests::build_config;
+pub use helpers::{compute_config_diff, diff_key_maps};
 mod helpers;
@@ -1,30 +1,60 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, time::Duration};
 
 use super::helpers::{
-    apply_prefix_filter_to_config, filter_config_by_dimensions, get_query_params_map,
+    apply_prefix_filter_to_config, compute_config_delta, compute_config_diff,
+    default_config_rule, filter_config_by_dimensions, filter_config_by_prefix,
+    generate_cac_toml, get_query_params_map, get_representation_etag, merge_config_tags,
+    restored_default_configs, snapshot_override,
 };
-use super::types::{Config, Context};
+use super::types::{
+    Config, ConfigDeltaQuery, ConfigDiff, ConfigDiffQuery, ConfigVersionFilters,
+    ConfigVersionTagsReq, Context, ResolveBatchReq, ResolvedConfigWithTrace,
+    RestoreResponse,
+};
+use crate::api::change_requests::reject_unreviewed_change;
 use crate::api::context::{
     delete_context_api, hash, put, validate_dimensions_and_calculate_priority, PutReq,
 };
-use crate::api::dimension::get_all_dimension_schema_map;
-use crate::db::models::ConfigVersion;
+use crate::api::default_config::{
+    apply_default_config_aliases, get_default_config_aliases, get_deprecated_keys,
+    validate_default_config, validate_key_usages,
+};
+use crate::api::dimension::{
+    apply_dimension_aliases, get_all_dimension_schema_map, get_dimension_aliases,
+};
+use crate::db::models::{self, ConfigVersion};
 use crate::{
-    db::schema::{config_versions::dsl as config_versions, event_log::dsl as event_log},
-    helpers::generate_cac,
+    db::schema::{
+        config_versions::dsl as config_versions, contexts::dsl as contexts,
+        default_configs::dsl as default_configs, dimensions::dsl as dimensions,
+        event_log::dsl as event_log,
+    },
+    helpers::{
+        add_config_version, generate_cac, get_merge_strategy,
+        subscribe_to_config_versions, to_cac_client_config,
+    },
 };
 use actix_http::header::HeaderValue;
-use actix_web::web::{Json, Query};
-use actix_web::{get, put, web, HttpRequest, HttpResponse, HttpResponseBuilder, Scope};
-use cac_client::{eval_cac, eval_cac_with_reasoning, MergeStrategy};
+use actix_web::http::header::{
+    self, ContentEncoding, ETag, EntityTag, Header, IfNoneMatch,
+};
+use actix_web::rt::time::timeout;
+use actix_web::web::{Bytes, Data, Json, Path, Query};
+use actix_web::{
+    get, post, put, web, HttpRequest, HttpResponse, HttpResponseBuilder, Scope,
+};
+use cac_client::{eval_cac, eval_cac_with_reasoning, eval_cac_with_trace};
 use chrono::{DateTime, NaiveDateTime, TimeZone, Timelike, Utc};
 use diesel::{
-    dsl::max,
+    dsl::{max, sql},
     r2d2::{ConnectionManager, PooledConnection},
-    ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+    sql_types::{Bool, Nullable, Text, Timestamp},
+    Connection, ExpressionMethods, OptionalExtension, PgArrayExpressionMethods,
+    PgConnection, QueryDsl, QueryableByName, RunQueryDsl,
 };
+use futures_util::{stream, Stream};
 use serde_json::{json, Map, Value};
-use superposition_macros::{bad_argument, db_error, unexpected_error};
+use superposition_macros::{bad_argument, db_error, not_found, unexpected_error};
 use superposition_types::{
     result as superposition, Cac, Condition, Overrides, PaginatedResponse, QueryFilters,
     TenantConfig, User,
@@ -33,17 +63,31 @@ use superposition_types::{
 use itertools::Itertools;
 use jsonschema::JSONSchema;
 use service_utils::{
-    helpers::extract_dimensions,
-    service::types::{AppHeader, DbConnection},
+    helpers::{extract_dimensions, parse_config_tags, validate_config_tags},
+    service::types::{
+        AppExecutionNamespace, AppHeader, AppState, CustomHeaders, DbConnection,
+    },
 };
+use tokio::sync::watch;
 use uuid::Uuid;
 
+const ROLLBACK_TAG: &str = "rollback";
+const STREAM_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const MAX_RESOLVE_BATCH_SIZE: usize = 1000;
+
 pub fn endpoints() -> Scope {
     Scope::new("")
         .service(get)
         .service(get_resolved_config)
+        .service(get_resolved_config_batch)
+        .service(get_toml_config)
         .service(reduce_config)
         .service(get_config_versions)
+        .service(update_config_version_tags)
+        .service(get_config_diff)
+        .service(get_config_delta)
+        .service(restore_config_version)
+        .service(stream_config_versions)
 }
 
 fn validate_version_in_params(
@@ -62,6 +106,59 @@ fn validate_version_in_params(
         })
 }
 
+fn validate_tag_in_params(
+    query_params_map: &mut Map<String, Value>,
+) -> superposition::Result<Option<String>> {
+    query_params_map
+        .remove("tag")
+        .map(|tag| {
+            let tag = match tag {
+                Value::String(val) => val,
+                val => val.to_string(),
+            };
+            validate_config_tags(vec![tag]).map(|mut tags| tags.remove(0))
+        })
+        .transpose()
+}
+
+fn get_latest_version_with_tag(
+    tag: &str,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<i64> {
+    config_versions::config_versions
+        .select(config_versions::id)
+        .filter(config_versions::tags.contains(vec![tag.to_string()]))
+        .order(config_versions::created_at.desc())
+        .first::<i64>(conn)
+        .map_err(|err| match err {
+            diesel::result::Error::NotFound => {
+                not_found!("No config version found with tag {}", tag)
+            }
+            err => {
+                log::error!("failed to fetch config version with tag {tag}: {err}");
+                db_error!(err)
+            }
+        })
+}
+
+/*
+  A config version can be requested either by its id or by a tag, in which
+  case the newest version carrying the tag is picked.
+*/
+fn get_version_from_params(
+    query_params_map: &mut Map<String, Value>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Option<i64>> {
+    let version = validate_version_in_params(query_params_map)?;
+    match (version, validate_tag_in_params(query_params_map)?) {
+        (Some(_), Some(_)) => {
+            Err(bad_argument!("only one of version and tag can be provided"))
+        }
+        (None, Some(tag)) => get_latest_version_with_tag(&tag, conn).map(Some),
+        (version, None) => Ok(version),
+    }
+}
+
 pub fn add_audit_id_to_header(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     resp_builder: &mut HttpResponseBuilder,
@@ -106,6 +203,37 @@ fn add_config_version_to_header(
     }
 }
 
+/*
+  Deprecated keys present in the response are listed in a header along with
+  their sunset dates and replacements, keeping the response body unchanged.
+*/
+fn add_deprecated_keys_to_header<'a>(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    keys: impl Iterator<Item = &'a String>,
+    resp_builder: &mut HttpResponseBuilder,
+) {
+    let deprecated_keys = match get_deprecated_keys(conn) {
+        Ok(deprecated_keys) => deprecated_keys,
+        Err(e) => {
+            log::error!("failed to fetch deprecated keys: {e}");
+            return;
+        }
+    };
+    let used_keys = keys
+        .filter_map(|key| deprecated_keys.get_key_value(key))
+        .collect::<HashMap<_, _>>();
+    if used_keys.is_empty() {
+        return;
+    }
+    match serde_json::to_string(&used_keys) {
+        Ok(header_value) => {
+            resp_builder
+                .insert_header((AppHeader::XDeprecatedKeys.to_string(), header_value));
+        }
+        Err(e) => log::error!("failed to serialize deprecated keys: {e}"),
+    }
+}
+
 fn get_max_created_at(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
 ) -> Result<NaiveDateTime, diesel::result::Error> {
@@ -116,7 +244,86 @@ fn get_max_created_at(
         .and_then(|res| res.ok_or(diesel::result::Error::NotFound))
 }
 
-fn is_not_modified(max_created_at: Option<NaiveDateTime>, req: &HttpRequest) -> bool {
+#[derive(QueryableByName, Default)]
+struct AliasExpiries {
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    last_expired: Option<NaiveDateTime>,
+    #[diesel(sql_type = Nullable<Timestamp>)]
+    next_expiry: Option<NaiveDateTime>,
+}
+
+/*
+  Dimension and default config aliases expire by wall clock, without an
+  event or a config version. The last expiry that passed counts as a
+  modification of the config and the next one goes into its entity tag.
+*/
+fn get_alias_expiries(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> AliasExpiries {
+    diesel::sql_query(
+        "SELECT max(expires_at) FILTER (WHERE expires_at <= $1) AS last_expired, \
+         min(expires_at) FILTER (WHERE expires_at > $1) AS next_expiry \
+         FROM (SELECT expires_at FROM dimension_aliases \
+         UNION ALL SELECT expires_at FROM default_config_aliases) AS aliases",
+    )
+    .bind::<Timestamp, _>(Utc::now().naive_utc())
+    .get_result::<AliasExpiries>(conn)
+    .map_err(|e| log::error!("failed to fetch alias expiries: {e}"))
+    .unwrap_or_default()
+}
+
+fn add_etag_to_header(etag: &Option<String>, resp_builder: &mut HttpResponseBuilder) {
+    if let Some(etag) = etag {
+        resp_builder.insert_header(ETag(EntityTag::new_strong(etag.to_owned())));
+    }
+}
+
+/*
+  Fetches the id and hash of the requested config version, or of the latest
+  version when none is requested. The config served afterwards is the one of
+  the returned id, so that it corresponds to the same version as the hash.
+*/
+fn get_config_hash(
+    version: Option<i64>,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Option<(i64, String)>> {
+    let mut query = config_versions::config_versions
+        .select((config_versions::id, config_versions::config_hash))
+        .into_boxed();
+    query = match version {
+        Some(val) => query.filter(config_versions::id.eq(val)),
+        None => query.order(config_versions::created_at.desc()),
+    };
+
+    query
+        .first::<(i64, String)>(conn)
+        .optional()
+        .map_err(|err| {
+            log::error!("failed to fetch config hash: {err}");
+            db_error!(err)
+        })
+}
+
+fn is_not_modified(
+    max_created_at: Option<NaiveDateTime>,
+    etag: &Option<String>,
+    req: &HttpRequest,
+) -> bool {
+    if req.headers().contains_key(header::IF_NONE_MATCH) {
+        let Some(etag) = etag else {
+            return false;
+        };
+        let etag = EntityTag::new_strong(etag.to_owned());
+        return match IfNoneMatch::parse(req) {
+            Ok(IfNoneMatch::Any) => true,
+            Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+            Err(err) => {
+                log::error!("failed to parse If-None-Match header: {err}");
+                false
+            }
+        };
+    }
+
     let nanosecond_erasure = |t: NaiveDateTime| t.with_nanosecond(0);
     let last_modified = req
         .headers()
@@ -362,6 +569,8 @@ fn construct_new_payload(
     return Ok(web::Json(PutReq {
         context: context,
         r#override: override_,
+        active_from: None,
+        active_until: None,
     }));
 }
 
@@ -373,7 +582,7 @@ async fn reduce_config_key(
     mut og_contexts: Vec<Context>,
     mut og_overrides: HashMap<String, Overrides>,
     check_key: &str,
-    dimension_schema_map: &HashMap<String, (JSONSchema, i32)>,
+    dimension_schema_map: &HashMap<String, (JSONSchema, i32, bool)>,
     default_config: Map<String, Value>,
     is_approve: bool,
 ) -> superposition::Result<Config> {
@@ -543,23 +752,44 @@ async fn get(
 ) -> superposition::Result<HttpResponse> {
     let DbConnection(mut conn) = db_conn;
 
+    let alias_expiries = get_alias_expiries(&mut conn);
     let max_created_at = get_max_created_at(&mut conn)
         .map_err(|e| log::error!("failed to fetch max timestamp from event_log: {e}"))
-        .ok();
+        .ok()
+        .map(|created_at| {
+            alias_expiries
+                .last_expired
+                .map_or(created_at, |expired_at| created_at.max(expired_at))
+        });
 
     log::info!("Max created at: {max_created_at:?}");
 
-    let is_not_modified = is_not_modified(max_created_at, &req);
-
-    if is_not_modified {
-        return Ok(HttpResponse::NotModified().finish());
+    let mut query_params_map = get_query_params_map(req.query_string())?;
+    let requested_version = get_version_from_params(&mut query_params_map, &mut conn)?;
+    let version_hash = get_config_hash(requested_version, &mut conn)?;
+    let mut config_version = version_hash
+        .as_ref()
+        .map(|(version, _)| *version)
+        .or(requested_version);
+    let config_hash = version_hash.map(|(_, config_hash)| config_hash);
+    let etag = get_representation_etag(
+        &config_hash,
+        "config",
+        &query_params_map,
+        None,
+        alias_expiries.next_expiry,
+    );
+
+    if is_not_modified(max_created_at, &etag, &req) {
+        let mut response = HttpResponse::NotModified();
+        add_etag_to_header(&etag, &mut response);
+        return Ok(response.finish());
     }
 
-    let mut query_params_map = get_query_params_map(req.query_string())?;
-    let mut config_version = validate_version_in_params(&mut query_params_map)?;
     let mut config = generate_config_from_version(&mut config_version, &mut conn)?;
 
     config = apply_prefix_filter_to_config(&mut query_params_map, config)?;
+    apply_dimension_aliases(&get_dimension_aliases(&mut conn)?, &mut query_params_map);
 
     if !query_params_map.is_empty() {
         config = filter_config_by_dimensions(&config, &query_params_map)?
@@ -567,8 +797,14 @@ async fn get(
 
     let mut response = HttpResponse::Ok();
     add_last_modified_to_header(max_created_at, &mut response);
+    add_etag_to_header(&etag, &mut response);
     add_audit_id_to_header(&mut conn, &mut response);
     add_config_version_to_header(&config_version, &mut response);
+    add_deprecated_keys_to_header(
+        &mut conn,
+        config.default_configs.keys(),
+        &mut response,
+    );
     Ok(response.json(config))
 }
 
@@ -580,44 +816,58 @@ async fn get_resolved_config(
     let DbConnection(mut conn) = db_conn;
     let mut query_params_map = get_query_params_map(req.query_string())?;
 
+    let alias_expiries = get_alias_expiries(&mut conn);
     let max_created_at = get_max_created_at(&mut conn)
         .map_err(|e| log::error!("failed to fetch max timestamp from event_log : {e}"))
-        .ok();
-
-    let is_not_modified = is_not_modified(max_created_at, &req);
+        .ok()
+        .map(|created_at| {
+            alias_expiries
+                .last_expired
+                .map_or(created_at, |expired_at| created_at.max(expired_at))
+        });
 
-    if is_not_modified {
-        return Ok(HttpResponse::NotModified().finish());
+    let requested_version = get_version_from_params(&mut query_params_map, &mut conn)?;
+    let version_hash = get_config_hash(requested_version, &mut conn)?;
+    let mut config_version = version_hash
+        .as_ref()
+        .map(|(version, _)| *version)
+        .or(requested_version);
+    let config_hash = version_hash.map(|(_, config_hash)| config_hash);
+    let merge_strategy = get_merge_strategy(&req);
+    let etag = get_representation_etag(
+        &config_hash,
+        "resolve",
+        &query_params_map,
+        Some(&merge_strategy),
+        alias_expiries.next_expiry,
+    );
+
+    if is_not_modified(max_created_at, &etag, &req) {
+        let mut response = HttpResponse::NotModified();
+        add_etag_to_header(&etag, &mut response);
+        return Ok(response.finish());
     }
 
-    let mut config_version = validate_version_in_params(&mut query_params_map)?;
     let mut config = generate_config_from_version(&mut config_version, &mut conn)?;
 
     config = apply_prefix_filter_to_config(&mut query_params_map, config)?;
+    apply_dimension_aliases(&get_dimension_aliases(&mut conn)?, &mut query_params_map);
 
-    let cac_client_contexts = config
-        .contexts
-        .into_iter()
-        .map(|val| cac_client::Context {
-            condition: json!(val.condition),
-            override_with_keys: val.override_with_keys,
-        })
-        .collect::<Vec<_>>();
-
-    let merge_strategy = req
-        .headers()
-        .get("x-merge-strategy")
-        .and_then(|header_value: &HeaderValue| header_value.to_str().ok())
-        .and_then(|val| MergeStrategy::from_str(val).ok())
-        .unwrap_or_default();
-
-    let mut override_map = Map::new();
-    for (key, val) in config.overrides.iter() {
-        override_map.insert(key.to_owned(), json!(val));
-    }
+    let (cac_client_contexts, override_map) = to_cac_client_config(&config);
 
-    let response = if let Some(Value::String(_)) = query_params_map.get("show_reasoning")
-    {
+    let show_reasoning = matches!(
+        query_params_map.get("show_reasoning"),
+        Some(Value::String(_))
+    );
+    let trace = show_reasoning.then(|| {
+        eval_cac_with_trace(
+            &config.default_configs,
+            &cac_client_contexts,
+            &override_map,
+            &query_params_map,
+        )
+    });
+    let mut response = if show_reasoning {
         eval_cac_with_reasoning(
             config.default_configs,
             &cac_client_contexts,
@@ -625,10 +875,6 @@ async fn get_resolved_config(
             &query_params_map,
             merge_strategy,
         )
-        .map_err(|err| {
-            log::error!("failed to eval cac with err: {}", err);
-            unexpected_error!("cac eval failed")
-        })?
     } else {
         eval_cac(
             config.default_configs,
@@ -637,32 +883,144 @@ async fn get_resolved_config(
             &query_params_map,
             merge_strategy,
         )
-        .map_err(|err| {
-            log::error!("failed to eval cac with err: {}", err);
-            unexpected_error!("cac eval failed")
-        })?
-    };
+    }
+    .map_err(|err| {
+        log::error!("failed to eval cac with err: {}", err);
+        unexpected_error!("cac eval failed")
+    })?;
+    let default_config_aliases = get_default_config_aliases(&mut conn)?;
+    apply_default_config_aliases(&default_config_aliases, &mut response);
     let mut resp = HttpResponse::Ok();
     add_last_modified_to_header(max_created_at, &mut resp);
+    add_etag_to_header(&etag, &mut resp);
     add_audit_id_to_header(&mut conn, &mut resp);
     add_config_version_to_header(&config_version, &mut resp);
+    add_deprecated_keys_to_header(&mut conn, response.keys(), &mut resp);
+
+    match trace {
+        Some(mut trace) => {
+            apply_default_config_aliases(&default_config_aliases, &mut trace);
+            Ok(resp.json(ResolvedConfigWithTrace {
+                config: response,
+                trace,
+            }))
+        }
+        None => Ok(resp.json(response)),
+    }
+}
+
+#[post("/resolve/batch")]
+async fn get_resolved_config_batch(
+    req: HttpRequest,
+    body: Json<ResolveBatchReq>,
+    db_conn: DbConnection,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
+    let ResolveBatchReq {
+        mut dimensions,
+        prefix,
+        version,
+    } = body.into_inner();
+
+    if dimensions.len() > MAX_RESOLVE_BATCH_SIZE {
+        return Err(bad_argument!(
+            "at most {} dimension sets can be resolved in one batch",
+            MAX_RESOLVE_BATCH_SIZE
+        ));
+    }
+
+    let dimension_aliases = get_dimension_aliases(&mut conn)?;
+    for query_data in dimensions.iter_mut() {
+        apply_dimension_aliases(&dimension_aliases, query_data);
+    }
+
+    let mut config_version = version;
+    let mut config = generate_config_from_version(&mut config_version, &mut conn)?;
+    if let Some(prefix_list) = prefix.filter(|prefix_list| !prefix_list.is_empty()) {
+        config = filter_config_by_prefix(&config, &prefix_list)?;
+    }
+
+    let (cac_client_contexts, override_map) = to_cac_client_config(&config);
+    let mut response = dimensions
+        .iter()
+        .map(|query_data| {
+            eval_cac(
+                config.default_configs.clone(),
+                &cac_client_contexts,
+                &override_map,
+                query_data,
+                get_merge_strategy(&req),
+            )
+            .map_err(|err| {
+                log::error!("failed to eval cac with err: {}", err);
+                unexpected_error!("cac eval failed")
+            })
+        })
+        .collect::<superposition::Result<Vec<_>>>()?;
+    let key_aliases = get_default_config_aliases(&mut conn)?;
+    for resolved_config in response.iter_mut() {
+        apply_default_config_aliases(&key_aliases, resolved_config);
+    }
 
+    let mut resp = HttpResponse::Ok();
+    add_audit_id_to_header(&mut conn, &mut resp);
+    add_config_version_to_header(&config_version, &mut resp);
+    add_deprecated_keys_to_header(&mut conn, config.default_configs.keys(), &mut resp);
     Ok(resp.json(response))
 }
 
+#[get("/toml")]
+async fn get_toml_config(
+    req: HttpRequest,
+    db_conn: DbConnection,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
+
+    let mut query_params_map = get_query_params_map(req.query_string())?;
+    let mut config_version = get_version_from_params(&mut query_params_map, &mut conn)?;
+    let config = generate_config_from_version(&mut config_version, &mut conn)?;
+
+    // schemas are not versioned, the current ones are exported for any version
+    let dimension_schemas = dimensions::dimensions
+        .select((
+            dimensions::dimension,
+            dimensions::priority,
+            dimensions::schema,
+        ))
+        .order((dimensions::priority.asc(), dimensions::dimension.asc()))
+        .load::<(String, i32, Value)>(&mut conn)?;
+    let default_config_schemas = default_configs::default_configs
+        .select((default_configs::key, default_configs::schema))
+        .load::<(String, Value)>(&mut conn)?
+        .into_iter()
+        .collect::<Map<String, Value>>();
+
+    let cac_toml =
+        generate_cac_toml(&config, &dimension_schemas, &default_config_schemas)?;
+
+    let mut response = HttpResponse::Ok();
+    add_config_version_to_header(&config_version, &mut response);
+    Ok(response.content_type("application/toml").body(cac_toml))
+}
+
 #[get("/versions")]
 async fn get_config_versions(
     db_conn: DbConnection,
     filters: Query<QueryFilters>,
+    version_filters: Query<ConfigVersionFilters>,
 ) -> superposition::Result<Json<PaginatedResponse<ConfigVersion>>> {
     let DbConnection(mut conn) = db_conn;
 
-    let n_version: i64 = config_versions::config_versions
-        .count()
-        .get_result(&mut conn)?;
+    let mut count_query = config_versions::config_versions.into_boxed();
     let mut builder = config_versions::config_versions
         .into_boxed()
         .order(config_versions::created_at.desc());
+    if let Some(tag) = &version_filters.tag {
+        count_query =
+            count_query.filter(config_versions::tags.contains(vec![tag.clone()]));
+        builder = builder.filter(config_versions::tags.contains(vec![tag.clone()]));
+    }
+    let n_version: i64 = count_query.count().get_result(&mut conn)?;
     if let Some(limit) = filters.count {
         builder = builder.limit(limit);
     }
@@ -679,3 +1037,353 @@ async fn get_config_versions(
         data: config_versions,
     }))
 }
+
+#[put("/versions/{version_id}/tags")]
+async fn update_config_version_tags(
+    path: Path<i64>,
+    req: Json<ConfigVersionTagsReq>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<ConfigVersion>> {
+    let DbConnection(mut conn) = db_conn;
+    let version_id = path.into_inner();
+    let ConfigVersionTagsReq { add, remove } = req.into_inner();
+    let add = validate_config_tags(add)?;
+
+    // the row stays locked until the update, concurrent edits apply in turn
+    let config_version =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let existing_tags = config_versions::config_versions
+                .select(config_versions::tags)
+                .filter(config_versions::id.eq(version_id))
+                .for_update()
+                .first::<Option<Vec<String>>>(transaction_conn)
+                .map_err(|err| match err {
+                    diesel::result::Error::NotFound => {
+                        not_found!("Config version {} not found", version_id)
+                    }
+                    err => {
+                        log::error!("failed to fetch config version {version_id}: {err}");
+                        db_error!(err)
+                    }
+                })?;
+
+            let tags = merge_config_tags(existing_tags, add, &remove);
+            Ok(diesel::update(config_versions::config_versions)
+                .filter(config_versions::id.eq(version_id))
+                .set(config_versions::tags.eq(tags))
+                .get_result::<ConfigVersion>(transaction_conn)?)
+        })?;
+
+    Ok(Json(config_version))
+}
+
+#[get("/diff")]
+async fn get_config_diff(
+    db_conn: DbConnection,
+    query: Query<ConfigDiffQuery>,
+) -> superposition::Result<Json<ConfigDiff>> {
+    let DbConnection(mut conn) = db_conn;
+    let ConfigDiffQuery { from, to } = query.into_inner();
+
+    let from_config = generate_config_from_version(&mut Some(from), &mut conn)?;
+    let to_config = generate_config_from_version(&mut Some(to), &mut conn)?;
+
+    Ok(Json(compute_config_diff(&from_config, &to_config)))
+}
+
+#[get("/delta")]
+async fn get_config_delta(
+    db_conn: DbConnection,
+    query: Query<ConfigDeltaQuery>,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
+    let since_version = query.since_version;
+
+    let Some((latest_version, config_hash)) = get_config_hash(None, &mut conn)? else {
+        return Err(not_found!("No config versions found"));
+    };
+    let config_hash = Some(config_hash);
+    // clients apply the delta to the full config, so they are handed its tag
+    let etag = get_representation_etag(
+        &config_hash,
+        "config",
+        &Map::new(),
+        None,
+        get_alias_expiries(&mut conn).next_expiry,
+    );
+    if latest_version == since_version {
+        let mut response = HttpResponse::NotModified();
+        add_etag_to_header(&etag, &mut response);
+        add_config_version_to_header(&Some(latest_version), &mut response);
+        return Ok(response.finish());
+    }
+
+    let since_config = generate_config_from_version(&mut Some(since_version), &mut conn)?;
+    let latest_config =
+        generate_config_from_version(&mut Some(latest_version), &mut conn)?;
+
+    let mut response = HttpResponse::Ok();
+    add_etag_to_header(&etag, &mut response);
+    add_config_version_to_header(&Some(latest_version), &mut response);
+    add_deprecated_keys_to_header(
+        &mut conn,
+        latest_config.default_configs.keys(),
+        &mut response,
+    );
+    Ok(response.json(compute_config_delta(
+        since_version,
+        &since_config,
+        latest_version,
+        &latest_config,
+    )))
+}
+
+// schema and function of a key as they were when the version was created
+fn fetch_default_config_rule(
+    key: &str,
+    version_created_at: NaiveDateTime,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<(Value, Option<String>)> {
+    let new_data = event_log::event_log
+        .select(event_log::new_data)
+        .filter(event_log::table_name.eq("default_configs"))
+        .filter(event_log::action.ne("DELETE"))
+        .filter(event_log::timestamp.le(version_created_at))
+        .filter(sql::<Bool>("new_data ->> 'key' = ").bind::<Text, _>(key))
+        .order_by(event_log::timestamp.desc())
+        .first::<Option<Value>>(conn)
+        .optional()?
+        .flatten();
+
+    new_data
+        .as_ref()
+        .and_then(default_config_rule)
+        .ok_or(bad_argument!(
+        "the schema of default config key {} could not be recovered from the audit log",
+        key
+    ))
+}
+
+/*
+  Restored keys get back the schema and function they had in the version and
+  go through the same checks as a put. Returns the keys whose schema or
+  function changed, their overrides are checked once the contexts are restored.
+*/
+fn restore_default_configs(
+    changes: &ConfigDiff,
+    version_created_at: NaiveDateTime,
+    user: &User,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Vec<models::DefaultConfig>> {
+    let mut rule_changed = Vec::new();
+    for (key, value) in restored_default_configs(changes) {
+        let (schema, function_name) =
+            fetch_default_config_rule(key, version_created_at, conn)?;
+        let current = default_configs::default_configs
+            .filter(default_configs::key.eq(key))
+            .get_result::<models::DefaultConfig>(conn)
+            .optional()?;
+        let default_config = models::DefaultConfig {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            schema,
+            function_name,
+            created_at: current.as_ref().map_or_else(Utc::now, |row| row.created_at),
+            created_by: current
+                .as_ref()
+                .map_or_else(|| user.get_email(), |row| row.created_by.clone()),
+            last_modified_at: Utc::now().naive_utc(),
+            last_modified_by: user.get_email(),
+        };
+        validate_default_config(&default_config, conn)?;
+
+        diesel::insert_into(default_configs::default_configs)
+            .values(&default_config)
+            .on_conflict(default_configs::key)
+            .do_update()
+            .set(&default_config)
+            .execute(conn)?;
+
+        if current.map_or(false, |row| {
+            row.schema != default_config.schema
+                || row.function_name != default_config.function_name
+        }) {
+            rule_changed.push(default_config);
+        }
+    }
+    Ok(rule_changed)
+}
+
+fn restore_contexts(
+    changes: &ConfigDiff,
+    snapshot: &Config,
+    user: &User,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<()> {
+    for removed in changes.contexts.removed.iter() {
+        delete_context_api(removed.id.to_owned(), user.to_owned(), conn)?;
+    }
+
+    for added in changes.contexts.added.iter() {
+        let (override_id, override_) = snapshot_override(snapshot, &added.id)?;
+        let context = models::Context {
+            id: added.id.to_owned(),
+            value: json!(added.condition),
+            override_id,
+            override_,
+            priority: added.priority,
+            created_at: Utc::now(),
+            created_by: user.get_email(),
+            last_modified_at: Utc::now().naive_utc(),
+            last_modified_by: user.get_email(),
+            function_versions: json!({}),
+            active_from: None,
+            active_until: None,
+        };
+        diesel::insert_into(contexts::contexts)
+            .values(&context)
+            .execute(conn)?;
+    }
+
+    for modified in changes.contexts.modified.iter() {
+        let (override_id, override_) = snapshot_override(snapshot, &modified.id)?;
+        diesel::update(contexts::contexts)
+            .filter(contexts::id.eq(&modified.id))
+            .set((
+                contexts::override_.eq(override_),
+                contexts::override_id.eq(override_id),
+                contexts::priority.eq(modified.new_priority),
+                contexts::last_modified_at.eq(Utc::now().naive_utc()),
+                contexts::last_modified_by.eq(user.get_email()),
+            ))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+fn remove_default_configs(
+    changes: &ConfigDiff,
+    user: &User,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<()> {
+    for key in changes.default_configs.removed.keys() {
+        diesel::update(default_configs::default_configs)
+            .filter(default_configs::key.eq(key))
+            .set((
+                default_configs::last_modified_at.eq(Utc::now().naive_utc()),
+                default_configs::last_modified_by.eq(user.get_email()),
+            ))
+            .execute(conn)?;
+        diesel::delete(
+            default_configs::default_configs.filter(default_configs::key.eq(key)),
+        )
+        .execute(conn)?;
+    }
+    Ok(())
+}
+
+#[post("/versions/{version_id}/restore")]
+async fn restore_config_version(
+    state: Data<AppState>,
+    path: Path<i64>,
+    custom_headers: CustomHeaders,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    reject_unreviewed_change(&tenant_config, "Restoring a config version")?;
+    let DbConnection(mut conn) = db_conn;
+    let restored_from = path.into_inner();
+    let mut tags = parse_config_tags(custom_headers.config_tags)?.unwrap_or_default();
+    tags.push(ROLLBACK_TAG.to_string());
+    tags.push(format!("{ROLLBACK_TAG}-from-{restored_from}"));
+
+    let snapshot = generate_config_from_version(&mut Some(restored_from), &mut conn)?;
+    let version_created_at = config_versions::config_versions
+        .select(config_versions::created_at)
+        .filter(config_versions::id.eq(restored_from))
+        .get_result::<NaiveDateTime>(&mut conn)?;
+
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        let current = generate_cac(transaction_conn)?;
+        let changes = compute_config_diff(&current, &snapshot);
+
+        // default configs are added before and removed after the contexts
+        // are rewritten, so overrides never point to a missing key midway
+        let rule_changed = restore_default_configs(
+            &changes,
+            version_created_at,
+            &user,
+            transaction_conn,
+        )?;
+        restore_contexts(&changes, &snapshot, &user, transaction_conn)?;
+        for default_config in rule_changed.iter() {
+            let violations = validate_key_usages(default_config, transaction_conn)?;
+            if !violations.is_empty() {
+                return Err(bad_argument!(
+                    "restoring the schema or function of {} would invalidate {} existing context value(s)",
+                    default_config.key,
+                    violations.len()
+                ));
+            }
+        }
+        remove_default_configs(&changes, &user, transaction_conn)?;
+
+        let version_id = add_config_version(&state, Some(tags), transaction_conn)?;
+        log::info!(
+            "config restored to version {restored_from} by {}, new version {version_id}",
+            user.get_email()
+        );
+
+        let mut http_resp = HttpResponse::Ok();
+        http_resp.insert_header((
+            AppHeader::XConfigVersion.to_string(),
+            version_id.to_string(),
+        ));
+        Ok(http_resp.json(RestoreResponse {
+            version_id,
+            restored_from,
+            changes,
+        }))
+    })
+}
+
+fn config_version_events(
+    receiver: watch::Receiver<Option<i64>>,
+) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+    stream::unfold((receiver, true), |(mut receiver, is_first)| async move {
+        if !is_first {
+            match timeout(STREAM_KEEP_ALIVE_INTERVAL, receiver.changed()).await {
+                Ok(Ok(())) => (),
+                // the watcher has stopped, end the stream so that the client reconnects
+                Ok(Err(_)) => return None,
+                Err(_) => {
+                    let keep_alive = Bytes::from_static(b": keep-alive\n\n");
+                    return Some((Ok(keep_alive), (receiver, false)));
+                }
+            }
+        }
+
+        let event = match *receiver.borrow_and_update() {
+            Some(version) => {
+                Bytes::from(format!("event: config-version\ndata: {version}\n\n"))
+            }
+            None => Bytes::from_static(b": keep-alive\n\n"),
+        };
+        Some((Ok::<_, actix_web::Error>(event), (receiver, false)))
+    })
+}
+
+#[get("/stream")]
+async fn stream_config_versions(
+    state: Data<AppState>,
+    namespace: AppExecutionNamespace,
+) -> superposition::Result<HttpResponse> {
+    let receiver = subscribe_to_config_versions(&state, namespace.0).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        // compressing the response would buffer events
+        .insert_header(ContentEncoding::Identity)
+        .streaming(config_version_events(receiver)))
+}
@@ -1,21 +1,30 @@
-use super::types::{Config, Context};
+use super::types::{
+    Config, ConfigDelta, ConfigDiff, Context, ContextModification, ContextWithOverride,
+    ContextsDelta, ContextsDiff, KeyDiff, MapDelta, PositionedContext, ValueChange,
+};
 use actix_web::web::Query;
+use cac_client::MergeStrategy;
+use chrono::NaiveDateTime;
+use itertools::Itertools;
 use serde_json::{json, Map, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use superposition_macros::{bad_argument, unexpected_error};
-use superposition_types::{result as superposition, Cac, Overrides};
+use superposition_types::{
+    result as superposition, semantic_version::resolve_version_comparisons, Cac,
+    Overrides,
+};
 
 pub fn filter_context(
     contexts: &[Context],
     dimension_data: &Map<String, Value>,
 ) -> Vec<Context> {
+    let dimension_data = json!(dimension_data);
     contexts
         .iter()
         .filter_map(|context| {
-            match jsonlogic::partial_apply(
-                &json!(context.condition),
-                &json!(dimension_data),
-            ) {
+            let condition =
+                resolve_version_comparisons(&json!(context.condition), &dimension_data);
+            match jsonlogic::partial_apply(&condition, &dimension_data) {
                 Ok(jsonlogic::PartialApplyOutcome::Resolved(Value::Bool(true)))
                 | Ok(jsonlogic::PartialApplyOutcome::Ambiguous) => Some(context.clone()),
                 _ => None,
@@ -142,3 +151,884 @@ pub fn get_query_params_map(
 
     Ok(query_params_map)
 }
+
+pub fn diff_key_maps(from: &Map<String, Value>, to: &Map<String, Value>) -> KeyDiff {
+    let mut diff = KeyDiff::default();
+    for (key, from_value) in from {
+        match to.get(key) {
+            None => {
+                diff.removed.insert(key.to_owned(), from_value.to_owned());
+            }
+            Some(to_value) if to_value != from_value => {
+                diff.changed.insert(
+                    key.to_owned(),
+                    ValueChange {
+                        from: from_value.to_owned(),
+                        to: to_value.to_owned(),
+                    },
+                );
+            }
+            Some(_) => (),
+        }
+    }
+    for (key, to_value) in to {
+        if !from.contains_key(key) {
+            diff.added.insert(key.to_owned(), to_value.to_owned());
+        }
+    }
+    diff
+}
+
+fn get_context_override(config: &Config, context: &Context) -> Map<String, Value> {
+    config
+        .overrides
+        .get(&context.override_with_keys[0])
+        .map(|overrides| overrides.to_owned().into())
+        .unwrap_or_default()
+}
+
+fn context_with_override(config: &Config, context: &Context) -> ContextWithOverride {
+    ContextWithOverride {
+        id: context.id.to_owned(),
+        condition: context.condition.to_owned(),
+        priority: context.priority,
+        override_: get_context_override(config, context),
+    }
+}
+
+pub fn compute_config_diff(from: &Config, to: &Config) -> ConfigDiff {
+    let from_contexts: HashMap<&String, &Context> =
+        from.contexts.iter().map(|ctx| (&ctx.id, ctx)).collect();
+    let to_contexts: HashMap<&String, &Context> =
+        to.contexts.iter().map(|ctx| (&ctx.id, ctx)).collect();
+
+    let mut contexts = ContextsDiff::default();
+    for to_ctx in &to.contexts {
+        match from_contexts.get(&to_ctx.id) {
+            None => contexts.added.push(context_with_override(to, to_ctx)),
+            Some(from_ctx) => {
+                let overrides = diff_key_maps(
+                    &get_context_override(from, from_ctx),
+                    &get_context_override(to, to_ctx),
+                );
+                if !overrides.is_empty() || from_ctx.priority != to_ctx.priority {
+                    contexts.modified.push(ContextModification {
+                        id: to_ctx.id.to_owned(),
+                        condition: to_ctx.condition.to_owned(),
+                        old_priority: from_ctx.priority,
+                        new_priority: to_ctx.priority,
+                        overrides,
+                    });
+                }
+            }
+        }
+    }
+    for from_ctx in &from.contexts {
+        if !to_contexts.contains_key(&from_ctx.id) {
+            contexts.removed.push(context_with_override(from, from_ctx));
+        }
+    }
+
+    ConfigDiff {
+        contexts,
+        default_configs: diff_key_maps(&from.default_configs, &to.default_configs),
+    }
+}
+
+/// Tags of a config version after an edit, `None` when no tag is left.
+pub fn merge_config_tags(
+    existing: Option<Vec<String>>,
+    add: Vec<String>,
+    remove: &[String],
+) -> Option<Vec<String>> {
+    let tags = existing
+        .unwrap_or_default()
+        .into_iter()
+        .chain(add)
+        .filter(|tag| !remove.contains(tag))
+        .unique()
+        .collect::<Vec<String>>();
+    (!tags.is_empty()).then_some(tags)
+}
+
+/// Keys a restore writes back with the value they have in the version, the ones
+/// missing or changed since.
+pub fn restored_default_configs(changes: &ConfigDiff) -> Vec<(&String, &Value)> {
+    changes
+        .default_configs
+        .added
+        .iter()
+        .chain(
+            changes
+                .default_configs
+                .changed
+                .iter()
+                .map(|(key, change)| (key, &change.to)),
+        )
+        .collect()
+}
+
+/// Override id and override of a context in a config snapshot.
+pub fn snapshot_override(
+    snapshot: &Config,
+    context_id: &str,
+) -> superposition::Result<(String, Value)> {
+    let context = snapshot
+        .contexts
+        .iter()
+        .find(|context| context.id == context_id)
+        .ok_or(unexpected_error!(
+            "context {} not found in the config snapshot",
+            context_id
+        ))?;
+    Ok((
+        context.override_with_keys[0].to_owned(),
+        Value::Object(get_context_override(snapshot, context)),
+    ))
+}
+
+/// Schema and function of a default config in an audit log row of the key.
+pub fn default_config_rule(new_data: &Value) -> Option<(Value, Option<String>)> {
+    let schema = new_data.get("schema")?.to_owned();
+    let function_name = new_data
+        .get("function_name")
+        .and_then(Value::as_str)
+        .map(String::from);
+    Some((schema, function_name))
+}
+
+fn compute_map_delta(from: &Map<String, Value>, to: &Map<String, Value>) -> MapDelta {
+    MapDelta {
+        upserted: to
+            .iter()
+            .filter(|(key, value)| from.get(*key) != Some(*value))
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        removed: from
+            .keys()
+            .filter(|key| !to.contains_key(*key))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn overrides_to_map(config: &Config) -> Map<String, Value> {
+    config
+        .overrides
+        .iter()
+        .map(|(key, overrides)| (key.to_owned(), json!(overrides)))
+        .collect()
+}
+
+pub fn compute_config_delta(
+    from_version: i64,
+    from: &Config,
+    to_version: i64,
+    to: &Config,
+) -> ConfigDelta {
+    let from_contexts: HashMap<&String, &Context> =
+        from.contexts.iter().map(|ctx| (&ctx.id, ctx)).collect();
+    let to_context_ids = to
+        .contexts
+        .iter()
+        .map(|ctx| &ctx.id)
+        .collect::<HashSet<_>>();
+
+    let upserted = to
+        .contexts
+        .iter()
+        .enumerate()
+        .filter(|(_, ctx)| {
+            from_contexts.get(&ctx.id).map_or(true, |from_ctx| {
+                from_ctx.priority != ctx.priority
+                    || from_ctx.override_with_keys != ctx.override_with_keys
+            })
+        })
+        .map(|(position, ctx)| PositionedContext {
+            position,
+            context: ctx.clone(),
+        })
+        .collect();
+    let removed = from
+        .contexts
+        .iter()
+        .filter(|ctx| !to_context_ids.contains(&ctx.id))
+        .map(|ctx| ctx.id.clone())
+        .collect();
+
+    ConfigDelta {
+        from_version,
+        to_version,
+        contexts: ContextsDelta { upserted, removed },
+        overrides: compute_map_delta(&overrides_to_map(from), &overrides_to_map(to)),
+        default_configs: compute_map_delta(&from.default_configs, &to.default_configs),
+    }
+}
+
+const CAC_TOML_OPERATORS: [&str; 6] = ["==", "!=", "<", "<=", ">", ">="];
+
+fn is_cac_toml_identifier(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+}
+
+fn render_cac_toml_term(
+    term: &Value,
+    dimensions: &HashSet<&str>,
+) -> superposition::Result<String> {
+    match term {
+        Value::Object(var) if var.len() == 1 && var.contains_key("var") => {
+            match var.get("var").and_then(Value::as_str) {
+                Some(dimension) if !dimensions.contains(dimension) => {
+                    Err(bad_argument!("dimension {} does not exist", dimension))
+                }
+                Some(dimension) if is_cac_toml_identifier(dimension) => {
+                    Ok(format!("${dimension}"))
+                }
+                Some(dimension) => Err(bad_argument!(
+                    "dimension {} cannot be expressed in cac toml",
+                    dimension
+                )),
+                None => Err(bad_argument!("invalid variable in condition: {}", term)),
+            }
+        }
+        Value::String(value) if is_cac_toml_identifier(value) => Ok(format!("'{value}'")),
+        Value::Bool(value) => Ok(value.to_string()),
+        Value::Number(value) if value.is_u64() => Ok(value.to_string()),
+        Value::Number(value) => match value.as_f64() {
+            Some(float) if float >= 0.0 => {
+                let float = float.to_string();
+                Ok(if float.contains('.') {
+                    float
+                } else {
+                    format!("{float}.0")
+                })
+            }
+            _ => Err(bad_argument!("{} cannot be expressed in cac toml", term)),
+        },
+        _ => Err(bad_argument!("{} cannot be expressed in cac toml", term)),
+    }
+}
+
+/*
+  cac toml expressions have no parentheses and `&&` binds tighter than `||`, so
+  an `or` can only appear at the top of a condition or inside another `or`.
+*/
+fn render_cac_toml_condition(
+    condition: &Value,
+    dimensions: &HashSet<&str>,
+    inside_and: bool,
+) -> superposition::Result<String> {
+    let (operator, operands) = condition
+        .as_object()
+        .filter(|condition| condition.len() == 1)
+        .and_then(|condition| condition.iter().next())
+        .and_then(|(operator, operands)| {
+            operands.as_array().map(|operands| (operator, operands))
+        })
+        .ok_or_else(|| bad_argument!("invalid condition: {}", condition))?;
+
+    match operator.as_str() {
+        "and" => Ok(operands
+            .iter()
+            .map(|operand| render_cac_toml_condition(operand, dimensions, true))
+            .collect::<superposition::Result<Vec<_>>>()?
+            .join(" && ")),
+        "or" if !inside_and => Ok(operands
+            .iter()
+            .map(|operand| render_cac_toml_condition(operand, dimensions, false))
+            .collect::<superposition::Result<Vec<_>>>()?
+            .join(" || ")),
+        operator if CAC_TOML_OPERATORS.contains(&operator) && operands.len() == 2 => {
+            Ok(format!(
+                "{} {} {}",
+                render_cac_toml_term(&operands[0], dimensions)?,
+                operator,
+                render_cac_toml_term(&operands[1], dimensions)?
+            ))
+        }
+        _ => Err(bad_argument!(
+            "condition {} cannot be expressed in cac toml",
+            condition
+        )),
+    }
+}
+
+fn json_to_toml(value: Value) -> superposition::Result<toml::Value> {
+    toml::Value::try_from(&value).map_err(|err| {
+        bad_argument!("{} cannot be expressed in cac toml: {}", value, err)
+    })
+}
+
+fn collect_condition_dimensions<'a>(
+    condition: &'a Value,
+    dimensions: &mut HashSet<&'a str>,
+) {
+    match condition {
+        Value::Object(map) => {
+            for (operator, operands) in map {
+                match (operator.as_str(), operands) {
+                    ("var", Value::String(dimension)) => {
+                        dimensions.insert(dimension);
+                    }
+                    _ => collect_condition_dimensions(operands, dimensions),
+                }
+            }
+        }
+        Value::Array(operands) => operands
+            .iter()
+            .for_each(|operand| collect_condition_dimensions(operand, dimensions)),
+        _ => (),
+    }
+}
+
+/*
+  cac_toml gives dimensions the priorities 1, 2, 3.. in the order they are
+  written, so when the priorities of the server are not contiguous the contexts
+  can end up ordered differently. The export is refused when that happens.
+*/
+fn check_cac_toml_priorities(
+    config: &Config,
+    dimensions: &[(String, i32, Value)],
+) -> superposition::Result<()> {
+    let positions = dimensions
+        .iter()
+        .enumerate()
+        .map(|(index, (dimension, _, _))| (dimension.as_str(), index as i32 + 1))
+        .collect::<HashMap<_, _>>();
+    let is_contiguous = dimensions.iter().all(|(dimension, priority, _)| {
+        positions.get(dimension.as_str()) == Some(priority)
+    });
+    if is_contiguous {
+        return Ok(());
+    }
+
+    let toml_priorities = config
+        .contexts
+        .iter()
+        .map(|context| {
+            let condition = json!(context.condition);
+            let mut context_dimensions = HashSet::new();
+            collect_condition_dimensions(&condition, &mut context_dimensions);
+            let priority = context_dimensions
+                .iter()
+                .filter_map(|dimension| positions.get(dimension))
+                .sum::<i32>();
+            (context, priority)
+        })
+        .collect::<Vec<_>>();
+    for (context, toml_priority) in toml_priorities.iter() {
+        if let Some((other, _)) =
+            toml_priorities.iter().find(|(other, other_priority)| {
+                context.priority < other.priority && toml_priority >= other_priority
+            })
+        {
+            return Err(bad_argument!(
+                "dimension priorities are not contiguous, context {} would no longer be overridden by context {} in cac toml",
+                context.id,
+                other.id
+            ));
+        }
+    }
+    log::warn!("dimension priorities are not contiguous, exporting them by position");
+    Ok(())
+}
+
+/*
+  Renders the config in the format read by `cac_toml`. Dimensions are written
+  in increasing order of priority since cac_toml derives priorities from their
+  position in the file, the rest is sorted to keep the output stable.
+  Schemas are not part of config versions, the ones passed in are used for
+  every version.
+*/
+pub fn generate_cac_toml(
+    config: &Config,
+    dimensions: &[(String, i32, Value)],
+    schemas: &Map<String, Value>,
+) -> superposition::Result<String> {
+    check_cac_toml_priorities(config, dimensions)?;
+
+    let mut default_config_table = toml::Table::new();
+    let mut keys = config.default_configs.keys().collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        let schema = schemas.get(key).cloned().unwrap_or_else(|| {
+            log::warn!("schema for {key} not found, exporting an empty schema");
+            json!({})
+        });
+        let mut entry = toml::Table::new();
+        entry.insert(
+            "value".to_string(),
+            json_to_toml(config.default_configs[key].clone())?,
+        );
+        entry.insert("schema".to_string(), json_to_toml(schema)?);
+        default_config_table.insert(key.to_owned(), toml::Value::Table(entry));
+    }
+
+    let mut dimension_table = toml::Table::new();
+    for (dimension, _, schema) in dimensions {
+        let mut entry = toml::Table::new();
+        entry.insert("schema".to_string(), json_to_toml(schema.clone())?);
+        dimension_table.insert(dimension.to_owned(), toml::Value::Table(entry));
+    }
+
+    let dimension_names = dimensions
+        .iter()
+        .map(|(dimension, _, _)| dimension.as_str())
+        .collect::<HashSet<_>>();
+    let mut context_table = toml::Table::new();
+    for context in config.contexts.iter() {
+        let expression =
+            render_cac_toml_condition(&json!(context.condition), &dimension_names, false)
+                .map_err(|err| {
+                    bad_argument!(
+                        "context {} cannot be expressed in cac toml: {}",
+                        context.id,
+                        err.message()
+                    )
+                })?;
+        let override_ = config
+            .overrides
+            .get(&context.override_with_keys[0])
+            .ok_or_else(|| {
+                unexpected_error!("override for context {} not found", context.id)
+            })?;
+        context_table.insert(expression, json_to_toml(json!(override_))?);
+    }
+
+    let mut cac_toml = toml::Table::new();
+    cac_toml.insert(
+        "default-config".to_string(),
+        toml::Value::Table(default_config_table),
+    );
+    cac_toml.insert(
+        "dimensions".to_string(),
+        toml::Value::Table(dimension_table),
+    );
+    cac_toml.insert("context".to_string(), toml::Value::Table(context_table));
+
+    toml::to_string(&cac_toml).map_err(|err| {
+        log::error!("failed to serialize cac toml: {err}");
+        unexpected_error!("failed to generate cac toml")
+    })
+}
+
+/*
+  The same config version is served in different representations depending
+  on the endpoint, the query and the merge strategy, each of them gets its own
+  entity tag so that a cached response is never reused for another one.
+  Aliases resolve the query without creating a config version, so the next
+  alias expiry is part of the tag as well, an expiring alias changes it.
+*/
+pub fn get_representation_etag(
+    config_hash: &Option<String>,
+    endpoint: &str,
+    query_params_map: &Map<String, Value>,
+    merge_strategy: Option<&MergeStrategy>,
+    next_alias_expiry: Option<NaiveDateTime>,
+) -> Option<String> {
+    let config_hash = config_hash.as_ref()?;
+    let representation = json!({
+        "endpoint": endpoint,
+        "config_hash": config_hash,
+        "query": query_params_map,
+        "merge_strategy": merge_strategy.map(ToString::to_string),
+        "next_alias_expiry": next_alias_expiry,
+    });
+    Some(blake3::hash(representation.to_string().as_bytes()).to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use superposition_types::Condition;
+
+    /// Builds a config from `(id, condition, priority, override)` contexts.
+    pub(crate) fn build_config(
+        contexts: Vec<(&str, Value, i32, Value)>,
+        default_configs: Value,
+    ) -> Config {
+        let mut config = Config {
+            contexts: Vec::new(),
+            overrides: HashMap::new(),
+            default_configs: default_configs.as_object().cloned().unwrap_or_default(),
+        };
+        for (id, condition, priority, override_) in contexts {
+            let override_id = format!("{id}-override");
+            config.contexts.push(Context {
+                id: id.to_string(),
+                condition: Cac::<Condition>::try_from(
+                    condition.as_object().cloned().unwrap_or_default(),
+                )
+                .expect("invalid condition")
+                .into_inner(),
+                priority,
+                override_with_keys: [override_id.clone()],
+            });
+            config.overrides.insert(
+                override_id,
+                Cac::<Overrides>::try_from(
+                    override_.as_object().cloned().unwrap_or_default(),
+                )
+                .expect("invalid override")
+                .into_inner(),
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn test_compute_config_diff() {
+        let city_condition = json!({"==": [{"var": "city"}, "Bangalore"]});
+        let os_condition = json!({"==": [{"var": "os"}, "android"]});
+        let vehicle_condition = json!({"==": [{"var": "vehicle_type"}, "cab"]});
+
+        let from = build_config(
+            vec![
+                (
+                    "c1",
+                    city_condition.clone(),
+                    1,
+                    json!({"rate": 10, "surge": 1}),
+                ),
+                ("c2", os_condition, 2, json!({"rate": 12})),
+            ],
+            json!({"rate": 5, "surge": 0, "legacy": true}),
+        );
+        let to = build_config(
+            vec![
+                (
+                    "c1",
+                    city_condition,
+                    1,
+                    json!({"rate": 11, "surge": 1, "fee": 2}),
+                ),
+                ("c3", vehicle_condition, 4, json!({"rate": 20})),
+            ],
+            json!({"rate": 5, "surge": 2, "fee": 0}),
+        );
+
+        let diff = compute_config_diff(&from, &to);
+
+        assert_eq!(
+            diff.contexts
+                .added
+                .iter()
+                .map(|ctx| ctx.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c3"]
+        );
+        assert_eq!(
+            diff.contexts
+                .removed
+                .iter()
+                .map(|ctx| ctx.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c2"]
+        );
+        assert_eq!(diff.contexts.modified.len(), 1);
+        let modified = &diff.contexts.modified[0];
+        assert_eq!(modified.id, "c1");
+        assert_eq!(modified.overrides.added.get("fee"), Some(&json!(2)));
+        assert_eq!(
+            modified.overrides.changed.get("rate"),
+            Some(&ValueChange {
+                from: json!(10),
+                to: json!(11)
+            })
+        );
+        assert!(modified.overrides.removed.is_empty());
+
+        assert_eq!(diff.default_configs.added.get("fee"), Some(&json!(0)));
+        assert_eq!(
+            diff.default_configs.removed.get("legacy"),
+            Some(&json!(true))
+        );
+        assert_eq!(
+            diff.default_configs.changed.get("surge"),
+            Some(&ValueChange {
+                from: json!(0),
+                to: json!(2)
+            })
+        );
+        assert!(!diff.default_configs.changed.contains_key("rate"));
+    }
+
+    #[test]
+    fn test_merge_config_tags() {
+        let tags =
+            |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            merge_config_tags(
+                Some(tags(&["stable", "v1"])),
+                tags(&["v2", "stable"]),
+                &tags(&["v1"])
+            ),
+            Some(tags(&["stable", "v2"]))
+        );
+        assert_eq!(
+            merge_config_tags(None, tags(&["v1"]), &[]),
+            Some(tags(&["v1"]))
+        );
+        assert_eq!(
+            merge_config_tags(Some(tags(&["v1"])), Vec::new(), &tags(&["v1"])),
+            None
+        );
+        assert_eq!(merge_config_tags(None, tags(&["v1"]), &tags(&["v1"])), None);
+    }
+
+    #[test]
+    fn test_restore_from_snapshot() {
+        let city_condition = json!({"==": [{"var": "city"}, "Bangalore"]});
+        let os_condition = json!({"==": [{"var": "os"}, "android"]});
+        let current = build_config(
+            vec![("c1", city_condition.clone(), 1, json!({"rate": 11}))],
+            json!({"rate": 6, "fee": 1}),
+        );
+        let snapshot = build_config(
+            vec![
+                ("c1", city_condition, 1, json!({"rate": 10})),
+                ("c2", os_condition, 2, json!({"rate": 12, "surge": 2})),
+            ],
+            json!({"rate": 5, "surge": 0}),
+        );
+        let changes = compute_config_diff(&current, &snapshot);
+
+        let mut restored = restored_default_configs(&changes)
+            .into_iter()
+            .map(|(key, value)| (key.as_str(), value.to_owned()))
+            .collect::<Vec<_>>();
+        restored.sort_by_key(|(key, _)| *key);
+        assert_eq!(restored, vec![("rate", json!(5)), ("surge", json!(0))]);
+        assert!(changes.default_configs.removed.contains_key("fee"));
+
+        assert_eq!(
+            snapshot_override(&snapshot, "c1").ok(),
+            Some(("c1-override".to_string(), json!({"rate": 10})))
+        );
+        assert_eq!(
+            snapshot_override(&snapshot, "c2").ok(),
+            Some(("c2-override".to_string(), json!({"rate": 12, "surge": 2})))
+        );
+        assert!(snapshot_override(&snapshot, "c3").is_err());
+    }
+
+    #[test]
+    fn test_default_config_rule() {
+        let row = json!({
+            "key": "rate",
+            "value": 5,
+            "schema": {"type": "number"},
+            "function_name": "positive"
+        });
+        assert_eq!(
+            default_config_rule(&row),
+            Some((json!({"type": "number"}), Some("positive".to_string())))
+        );
+
+        let row =
+            json!({"key": "rate", "schema": {"type": "number"}, "function_name": null});
+        assert_eq!(
+            default_config_rule(&row),
+            Some((json!({"type": "number"}), None))
+        );
+        assert_eq!(default_config_rule(&json!({"key": "rate"})), None);
+    }
+
+    #[test]
+    fn test_compute_config_delta() {
+        let city_condition = json!({"==": [{"var": "city"}, "Bangalore"]});
+        let os_condition = json!({"==": [{"var": "os"}, "android"]});
+        let vehicle_condition = json!({"==": [{"var": "vehicle_type"}, "cab"]});
+
+        let from = build_config(
+            vec![
+                ("c1", city_condition.clone(), 1, json!({"rate": 10})),
+                ("c2", os_condition, 2, json!({"rate": 12})),
+            ],
+            json!({"rate": 5, "legacy": true}),
+        );
+        let to = build_config(
+            vec![
+                ("c1", city_condition, 1, json!({"rate": 10})),
+                ("c3", vehicle_condition, 3, json!({"rate": 20})),
+            ],
+            json!({"rate": 6}),
+        );
+
+        let delta = compute_config_delta(1, &from, 2, &to);
+
+        assert_eq!(delta.contexts.removed, vec!["c2"]);
+        assert_eq!(delta.contexts.upserted.len(), 1);
+        assert_eq!(delta.contexts.upserted[0].position, 1);
+        assert_eq!(delta.contexts.upserted[0].context.id, "c3");
+        assert_eq!(
+            delta.overrides.upserted.keys().collect::<Vec<_>>(),
+            vec!["c3-override"]
+        );
+        assert_eq!(delta.overrides.removed, vec!["c2-override"]);
+        assert_eq!(delta.default_configs.upserted.get("rate"), Some(&json!(6)));
+        assert_eq!(delta.default_configs.removed, vec!["legacy"]);
+    }
+
+    #[test]
+    fn test_generate_cac_toml() {
+        let config = build_config(
+            vec![
+                (
+                    "c1",
+                    json!({"==": [{"var": "city"}, "Delhi"]}),
+                    1,
+                    json!({"rate": 10}),
+                ),
+                (
+                    "c2",
+                    json!({"and": [
+                        {"==": [{"var": "city"}, "Delhi"]},
+                        {">=": [{"var": "hour_of_day"}, 18]}
+                    ]}),
+                    3,
+                    json!({"rate": 15, "surge": 1.5}),
+                ),
+            ],
+            json!({"rate": 5, "surge": 0.0}),
+        );
+        let dimensions = vec![
+            ("city".to_string(), 1, json!({"type": "string"})),
+            ("hour_of_day".to_string(), 2, json!({"type": "integer"})),
+        ];
+        let schemas = json!({
+            "rate": {"type": "integer"},
+            "surge": {"type": "number"}
+        });
+
+        let cac_toml =
+            generate_cac_toml(&config, &dimensions, schemas.as_object().unwrap())
+                .expect("failed to generate cac toml");
+        assert!(cac_toml.contains("$city == 'Delhi' && $hour_of_day >= 18"));
+
+        let cac = cac_toml::ContextAwareConfig::parse_str(&cac_toml)
+            .expect("generated cac toml should parse");
+        let resolved = cac.get_resolved_config(&HashMap::from([
+            ("city".to_string(), toml::Value::String("Delhi".to_string())),
+            ("hour_of_day".to_string(), toml::Value::Integer(20)),
+        ]));
+        assert_eq!(resolved.get("rate"), Some(&toml::Value::Integer(15)));
+        assert_eq!(resolved.get("surge"), Some(&toml::Value::Float(1.5)));
+
+        let or_inside_and = build_config(
+            vec![(
+                "c3",
+                json!({"and": [
+                    {"or": [
+                        {"==": [{"var": "city"}, "Delhi"]},
+                        {"==": [{"var": "city"}, "Mumbai"]}
+                    ]},
+                    {">=": [{"var": "hour_of_day"}, 18]}
+                ]}),
+                3,
+                json!({"rate": 15}),
+            )],
+            json!({"rate": 5, "surge": 0.0}),
+        );
+        assert!(generate_cac_toml(
+            &or_inside_and,
+            &dimensions,
+            schemas.as_object().unwrap()
+        )
+        .is_err());
+
+        let unsupported_dimension = build_config(
+            vec![(
+                "c4",
+                json!({"==": [{"var": "app-version"}, "1.0"]}),
+                1,
+                json!({"rate": 15}),
+            )],
+            json!({"rate": 5, "surge": 0.0}),
+        );
+        let dimensions = vec![("app-version".to_string(), 1, json!({"type": "string"}))];
+        assert!(generate_cac_toml(
+            &unsupported_dimension,
+            &dimensions,
+            schemas.as_object().unwrap()
+        )
+        .is_err());
+
+        // gaps in the priorities are fine as long as the contexts keep their order
+        let dimensions = vec![
+            ("city".to_string(), 1, json!({"type": "string"})),
+            ("hour_of_day".to_string(), 4, json!({"type": "integer"})),
+        ];
+        assert!(
+            generate_cac_toml(&config, &dimensions, schemas.as_object().unwrap()).is_ok()
+        );
+
+        let reordered = build_config(
+            vec![
+                (
+                    "c5",
+                    json!({"and": [
+                        {"==": [{"var": "city"}, "Delhi"]},
+                        {"==": [{"var": "os"}, "android"]}
+                    ]}),
+                    3,
+                    json!({"rate": 15}),
+                ),
+                (
+                    "c6",
+                    json!({"==": [{"var": "vehicle_type"}, "cab"]}),
+                    4,
+                    json!({"rate": 20}),
+                ),
+            ],
+            json!({"rate": 5, "surge": 0.0}),
+        );
+        let dimensions = vec![
+            ("city".to_string(), 1, json!({"type": "string"})),
+            ("os".to_string(), 2, json!({"type": "string"})),
+            ("vehicle_type".to_string(), 4, json!({"type": "string"})),
+        ];
+        assert!(
+            generate_cac_toml(&reordered, &dimensions, schemas.as_object().unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_get_representation_etag() {
+        let config_hash = Some("config-hash".to_string());
+        let query = Map::from_iter([("city".to_string(), json!("Bangalore"))]);
+        let etag = |endpoint, query: &Map<String, Value>, merge_strategy| {
+            get_representation_etag(&config_hash, endpoint, query, merge_strategy, None)
+                .expect("etag not generated")
+        };
+
+        let config_etag = etag("config", &query, None);
+        assert_eq!(config_etag, etag("config", &query, None));
+        assert_ne!(config_etag, etag("config", &Map::new(), None));
+        assert_ne!(config_etag, etag("resolve", &query, None));
+        assert_ne!(
+            etag("resolve", &query, Some(&MergeStrategy::MERGE)),
+            etag("resolve", &query, Some(&MergeStrategy::REPLACE))
+        );
+        let alias_expiry =
+            NaiveDateTime::parse_from_str("2024-01-01 00:00:00", "%Y-%m-%d %H:%M:%S")
+                .unwrap();
+        let aliased_etag = |expiry| {
+            get_representation_etag(&config_hash, "config", &query, None, Some(expiry))
+        };
+        assert_ne!(Some(config_etag), aliased_etag(alias_expiry));
+        assert_ne!(
+            aliased_etag(alias_expiry),
+            aliased_etag(alias_expiry + chrono::Duration::hours(1))
+        );
+        assert_eq!(
+            get_representation_etag(&None, "config", &query, None, None),
+            None
+        );
+    }
+}
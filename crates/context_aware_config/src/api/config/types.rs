@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -18,3 +18,135 @@ pub struct Context {
     pub priority: i32,
     pub override_with_keys: [String; 1],
 }
+
+#[derive(Deserialize)]
+pub struct ConfigVersionFilters {
+    pub tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigVersionTagsReq {
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ResolveBatchReq {
+    pub dimensions: Vec<Map<String, Value>>,
+    pub prefix: Option<Vec<String>>,
+    pub version: Option<i64>,
+}
+
+/// Resolved config along with the per-key resolution trace, kept apart so that
+/// no config key is shadowed by the trace.
+#[derive(Serialize)]
+pub struct ResolvedConfigWithTrace {
+    pub config: Map<String, Value>,
+    pub trace: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDeltaQuery {
+    pub since_version: i64,
+}
+
+#[derive(Serialize)]
+pub struct PositionedContext {
+    pub position: usize,
+    #[serde(flatten)]
+    pub context: Context,
+}
+
+/*
+  Contexts are identified by their id. Upserted contexts carry their position
+  in the newer config, so that clients can insert them in priority order after
+  dropping the removed and upserted ids from their copy of the older config.
+*/
+#[derive(Serialize, Default)]
+pub struct ContextsDelta {
+    pub upserted: Vec<PositionedContext>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct MapDelta {
+    pub upserted: Map<String, Value>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigDelta {
+    pub from_version: i64,
+    pub to_version: i64,
+    pub contexts: ContextsDelta,
+    pub overrides: MapDelta,
+    pub default_configs: MapDelta,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDiffQuery {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Clone)]
+pub struct ValueChange {
+    pub from: Value,
+    pub to: Value,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Default)]
+pub struct KeyDiff {
+    pub added: Map<String, Value>,
+    pub removed: Map<String, Value>,
+    pub changed: BTreeMap<String, ValueChange>,
+}
+
+impl KeyDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Serialize)]
+pub struct ContextWithOverride {
+    pub id: String,
+    pub condition: Condition,
+    pub priority: i32,
+    #[serde(rename(serialize = "override"))]
+    pub override_: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+pub struct ContextModification {
+    pub id: String,
+    pub condition: Condition,
+    pub old_priority: i32,
+    pub new_priority: i32,
+    pub overrides: KeyDiff,
+}
+
+#[derive(Serialize, Default)]
+pub struct ContextsDiff {
+    pub added: Vec<ContextWithOverride>,
+    pub removed: Vec<ContextWithOverride>,
+    pub modified: Vec<ContextModification>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigDiff {
+    pub contexts: ContextsDiff,
+    pub default_configs: KeyDiff,
+}
+
+#[derive(Serialize)]
+pub struct RestoreResponse {
+    pub version_id: i64,
+    pub restored_from: i64,
+    #[serde(flatten)]
+    pub changes: ConfigDiff,
+}
@@ -1,9 +1,11 @@
 mod handlers;
 pub mod helpers;
 mod types;
+pub use handlers::apply_context_actions;
 pub use handlers::delete_context_api;
 pub use handlers::endpoints;
 pub use handlers::hash;
+pub use handlers::override_helper;
 pub use handlers::put;
 pub use handlers::validate_dimensions_and_calculate_priority;
-pub use types::PutReq;
+pub use types::{ContextAction, ContextViolation, PutReq, RevalidationQuery};
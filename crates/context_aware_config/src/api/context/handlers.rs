@@ -6,9 +6,9 @@ use std::str;
 use actix_web::{
     delete, get, post, put,
     web::{Data, Json, Path, Query},
-    HttpResponse, Responder, Scope,
+    HttpRequest, HttpResponse, Responder, Scope,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use diesel::{
     delete,
     r2d2::{ConnectionManager, PooledConnection},
@@ -17,40 +17,51 @@ use diesel::{
     Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
 };
 use jsonschema::{Draft, JSONSchema, ValidationError};
-use serde_json::{from_value, json, Map, Value};
+use serde_json::{json, Map, Value};
 use service_utils::{
-    helpers::{parse_config_tags, validation_err_to_str},
+    helpers::{extract_dimension_constraints, parse_config_tags, validation_err_to_str},
     service::types::{AppHeader, AppState, CustomHeaders, DbConnection},
 };
 use superposition_macros::{
     bad_argument, db_error, not_found, unexpected_error, validation_error,
 };
-use superposition_types::{result as superposition, TenantConfig, User};
+use superposition_types::{
+    result as superposition, semantic_version::parse_version, Condition, TenantConfig,
+    User,
+};
 
 use crate::{
     api::{
+        change_requests::{
+            is_experiment_change, is_experiment_condition, parse_reviewers,
+            submit_change_request, ChangeOperation,
+        },
         context::types::{
-            ContextAction, ContextBulkResponse, DimensionCondition, MoveReq,
-            PaginationParams, PriorityRecomputeResponse, PutReq, PutResp,
+            ContextAction, ContextAnalysis, ContextBulkResponse, MoveReq,
+            PaginationParams, PriorityRecomputeResponse, PutReq, PutResp, SimulationReq,
+            SimulationResponse,
         },
-        dimension::get_all_dimension_schema_map,
+        default_config::get_deprecated_keys,
+        dimension::{apply_condition_aliases, get_all_dimension_schema_map},
+        type_templates::helpers::resolve_schema,
     },
     db::{
-        models::Context,
+        models::{ArchivedContext, Context},
         schema::{
+            archived_contexts,
             contexts::{self, id},
             default_configs::dsl,
         },
     },
     helpers::{
-        add_config_version, calculate_context_priority, json_to_sorted_string,
-        validate_context_jsonschema,
+        add_config_version, calculate_context_priority, generate_cac, get_merge_strategy,
+        json_to_sorted_string, validate_context_jsonschema,
     },
 };
 
 use super::helpers::{
-    validate_condition_with_functions, validate_condition_with_mandatory_dimensions,
-    validate_override_with_functions,
+    analyse_contexts, simulate_actions, validate_condition_with_functions,
+    validate_condition_with_mandatory_dimensions, validate_override_with_functions,
 };
 
 pub fn endpoints() -> Scope {
@@ -60,86 +71,64 @@ pub fn endpoints() -> Scope {
         .service(move_handler)
         .service(delete_context)
         .service(bulk_operations)
+        .service(simulate_context_actions)
         .service(list_contexts)
+        .service(list_archived_contexts)
         .service(get_context_from_condition)
+        .service(get_context_analysis)
         .service(get_context)
         .service(priority_recompute)
 }
 
 type DBConnection = PooledConnection<ConnectionManager<PgConnection>>;
 
+/*
+  Every dimension adds its priority once, however many constraints the context
+  puts on it, and every value it is compared against has to match its schema.
+*/
 pub fn validate_dimensions_and_calculate_priority(
     object_key: &str,
     cond: &Value,
-    dimension_schema_map: &HashMap<String, (JSONSchema, i32)>,
+    dimension_schema_map: &HashMap<String, (JSONSchema, i32, bool)>,
 ) -> superposition::Result<i32> {
-    let get_priority = |key: &String, val: &Value| -> superposition::Result<i32> {
-        if key == "var" {
-            let dimension_name = val
-                .as_str()
-                .ok_or(bad_argument!("Dimension name should be of `String` type"))?;
+    let condition = cond.as_object().ok_or(bad_argument!(
+        "{} should be an object. Ensure the context provided obeys the rules of JSON logic",
+        object_key
+    ))?;
+
+    let mut priority = 0;
+    for (dimension_name, constraints) in extract_dimension_constraints(condition)? {
+        let (dimension_value_schema, dimension_priority, is_version) =
             dimension_schema_map
-                .get(dimension_name)
-                .map(|(_, priority)| priority)
+                .get(&dimension_name)
                 .ok_or(bad_argument!(
                     "No matching dimension ({}) found",
                     dimension_name
-                ))
-                .copied()
-        } else {
-            validate_dimensions_and_calculate_priority(key, val, dimension_schema_map)
-        }
-    };
-
-    match cond {
-        Value::Object(x) => x.iter().try_fold(0, |acc, (key, val)| {
-            get_priority(key, val).map(|res| res + acc)
-        }),
-        Value::Array(arr) => {
-            let mut val: Option<Value> = None;
-            let mut condition: Option<DimensionCondition> = None;
-            for i in arr {
-                if let (None, Ok(x)) =
-                    (&condition, from_value::<DimensionCondition>(json!(i)))
-                {
-                    condition = Some(x);
-                } else if val.is_none() {
-                    val = Some(i.clone());
-                }
-
-                if let (Some(_dimension_value), Some(_dimension_condition)) =
-                    (&val, &condition)
+                ))?;
+        for constraint in constraints {
+            // versions are only ordered by precedence for version dimensions,
+            // whose schema already rejects anything that isn't a version
+            if let Value::String(value) = &constraint.value {
+                if constraint.is_ordering()
+                    && !is_version
+                    && parse_version(value).is_some()
                 {
-                    break;
+                    return Err(bad_argument!(
+                        "{} is compared as a string, add \"format\": \"semver\" to its schema to compare it with the version {}",
+                        dimension_name,
+                        value
+                    ));
                 }
             }
-
-            if let (Some(dimension_value), Some(dimension_condition)) = (val, condition) {
-                let expected_dimension_name = dimension_condition.var;
-                let (dimension_value_schema, _) = dimension_schema_map
-                    .get(&expected_dimension_name)
-                    .ok_or(bad_argument!(
-                        "No matching `dimension` {} in dimension table",
-                        expected_dimension_name
-                    ))?;
-
-                validate_context_jsonschema(
-                    object_key,
-                    &dimension_value,
-                    dimension_value_schema,
-                )?;
-            }
-            arr.iter().try_fold(0, |acc, item| {
-                validate_dimensions_and_calculate_priority(
-                    object_key,
-                    item,
-                    dimension_schema_map,
-                )
-                .map(|res| res + acc)
-            })
+            validate_context_jsonschema(
+                &constraint.operator.to_string(),
+                &constraint.value,
+                dimension_value_schema,
+            )?;
         }
-        _ => Ok(0),
+        priority += dimension_priority;
     }
+    Ok(priority)
 }
 
 fn validate_override_with_default_configs(
@@ -159,10 +148,11 @@ fn validate_override_with_default_configs(
             .get(key)
             // .map(|resp| resp)
             .ok_or(bad_argument!("failed to get schema for config key {}", key))?;
+        let schema = resolve_schema(conn, schema)?;
         let instance = value;
         let schema_compile_result = JSONSchema::options()
             .with_draft(Draft::Draft7)
-            .compile(schema);
+            .compile(&schema);
         let jschema = match schema_compile_result {
             Ok(jschema) => jschema,
             Err(e) => {
@@ -188,13 +178,71 @@ fn validate_override_with_default_configs(
     Ok(())
 }
 
+// deprecated keys can't be picked up by new overrides, only dropped from them
+fn validate_override_not_deprecated(
+    conn: &mut DBConnection,
+    override_: &Map<String, Value>,
+) -> superposition::Result<()> {
+    let deprecated_keys = get_deprecated_keys(conn)?;
+    for key in override_.keys() {
+        if let Some(deprecation) = deprecated_keys.get(key) {
+            let replacement = deprecation
+                .replacement_key
+                .as_ref()
+                .map(|replacement| format!(", use {replacement} instead"))
+                .unwrap_or_default();
+            return Err(bad_argument!(
+                "{} is deprecated and will be removed on {}{}",
+                key,
+                deprecation.sunset_at,
+                replacement
+            ));
+        }
+    }
+    Ok(())
+}
+
+/*
+  Experiment contexts live as long as their experiment, a window closing under a
+  running experiment would take its variants out of the config.
+*/
+fn validate_active_window(
+    condition: &Condition,
+    active_from: Option<DateTime<Utc>>,
+    active_until: Option<DateTime<Utc>>,
+) -> superposition::Result<()> {
+    if (active_from.is_some() || active_until.is_some())
+        && is_experiment_condition(condition)?
+    {
+        return Err(bad_argument!(
+            "Contexts of experiments can't have an active window"
+        ));
+    }
+    if let Some(active_until) = active_until {
+        if active_until <= Utc::now() {
+            return Err(bad_argument!(
+                "active_until {} has to be in the future",
+                active_until
+            ));
+        }
+        if active_from.is_some_and(|active_from| active_from >= active_until) {
+            return Err(bad_argument!(
+                "active_from has to be before active_until {}",
+                active_until
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn create_ctx_from_put_req(
     req: Json<PutReq>,
     conn: &mut DBConnection,
     user: &User,
     tenant_config: &TenantConfig,
 ) -> superposition::Result<Context> {
-    let ctx_condition = req.context.to_owned().into_inner();
+    let ctx_condition =
+        apply_condition_aliases(conn, req.context.to_owned())?.into_inner();
     let condition_val = json!(ctx_condition);
     let r_override = req.r#override.clone().into_inner();
     let ctx_override = json!(r_override.to_owned());
@@ -203,8 +251,10 @@ fn create_ctx_from_put_req(
         &tenant_config.mandatory_dimensions,
     )?;
     validate_override_with_default_configs(conn, &r_override)?;
-    validate_condition_with_functions(conn, &ctx_condition)?;
-    validate_override_with_functions(conn, &r_override)?;
+    validate_override_not_deprecated(conn, &r_override)?;
+    validate_active_window(&ctx_condition, req.active_from, req.active_until)?;
+    let mut function_versions = validate_condition_with_functions(conn, &ctx_condition)?;
+    function_versions.extend(validate_override_with_functions(conn, &r_override)?);
 
     let dimension_schema_map = get_all_dimension_schema_map(conn)?;
 
@@ -230,6 +280,9 @@ fn create_ctx_from_put_req(
         created_by: user.get_email(),
         last_modified_at: Utc::now().naive_utc(),
         last_modified_by: user.get_email(),
+        function_versions: Value::Object(function_versions),
+        active_from: req.active_from,
+        active_until: req.active_until,
     })
 }
 
@@ -244,15 +297,17 @@ fn update_override_of_existing_ctx(
     user: &User,
 ) -> superposition::Result<PutResp> {
     use contexts::dsl;
-    let mut new_override: Value = dsl::contexts
+    let (mut new_override, mut function_versions): (Value, Value) = dsl::contexts
         .filter(dsl::id.eq(&ctx.id))
-        .select(dsl::override_)
+        .select((dsl::override_, dsl::function_versions))
         .first(conn)?;
     cac_client::merge(&mut new_override, &ctx.override_);
+    cac_client::merge(&mut function_versions, &ctx.function_versions);
     let new_override_id = hash(&new_override);
     let new_ctx = Context {
         override_: new_override,
         override_id: new_override_id,
+        function_versions,
         ..ctx
     };
     db_update_override(conn, new_ctx, user)
@@ -279,13 +334,21 @@ fn db_update_override(
     user: &User,
 ) -> superposition::Result<PutResp> {
     use contexts::dsl;
+    // contexts keep their schedule unless the request sets a new one
+    let active_window = (ctx.active_from.is_some() || ctx.active_until.is_some())
+        .then_some((
+            dsl::active_from.eq(ctx.active_from),
+            dsl::active_until.eq(ctx.active_until),
+        ));
     let update_resp = diesel::update(dsl::contexts)
         .filter(dsl::id.eq(&ctx.id))
         .set((
             dsl::override_.eq(ctx.override_),
             dsl::override_id.eq(ctx.override_id),
+            dsl::function_versions.eq(ctx.function_versions),
             dsl::last_modified_at.eq(Utc::now().naive_utc()),
             dsl::last_modified_by.eq(user.get_email()),
+            active_window,
         ))
         .get_result::<Context>(conn)?;
     Ok(get_put_resp(update_resp))
@@ -339,6 +402,17 @@ async fn put_handler(
     tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let tags = parse_config_tags(custom_headers.config_tags)?;
+    if tenant_config.require_change_requests {
+        return submit_change_request(
+            &state,
+            &mut db_conn,
+            ChangeOperation::PutContext(req.into_inner()),
+            &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
+            &tenant_config,
+        );
+    }
     db_conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
         let put_response = put(req, transaction_conn, true, &user, &tenant_config)
             .map_err(|err: superposition::AppError| {
@@ -356,7 +430,7 @@ async fn put_handler(
     })
 }
 
-fn override_helper(
+pub fn override_helper(
     req: Json<PutReq>,
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     already_under_txn: bool,
@@ -395,6 +469,17 @@ async fn update_override_handler(
     tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let tags = parse_config_tags(custom_headers.config_tags)?;
+    if tenant_config.require_change_requests {
+        return submit_change_request(
+            &state,
+            &mut db_conn,
+            ChangeOperation::UpdateOverride(req.into_inner()),
+            &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
+            &tenant_config,
+        );
+    }
     db_conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
         let override_resp =
             override_helper(req, transaction_conn, true, &user, &tenant_config).map_err(
@@ -423,8 +508,8 @@ fn r#move(
     tenant_config: &TenantConfig,
 ) -> superposition::Result<PutResp> {
     use contexts::dsl;
-    let req = req.into_inner();
-    let ctx_condition = req.context.to_owned().into_inner();
+    let context = apply_condition_aliases(conn, req.into_inner().context)?;
+    let ctx_condition = context.to_owned().into_inner();
     let ctx_condition_value = Value::Object(ctx_condition.into());
     let new_ctx_id = hash(&ctx_condition_value);
     let dimension_schema_map = get_all_dimension_schema_map(conn)?;
@@ -434,7 +519,7 @@ fn r#move(
         &dimension_schema_map,
     )?;
     validate_condition_with_mandatory_dimensions(
-        &req.context.into_inner(),
+        &context.into_inner(),
         &tenant_config.mandatory_dimensions,
     )?;
 
@@ -467,6 +552,9 @@ fn r#move(
         override_: ctx.override_,
         last_modified_at: Utc::now().naive_utc(),
         last_modified_by: user.get_email(),
+        function_versions: ctx.function_versions,
+        active_from: ctx.active_from,
+        active_until: ctx.active_until,
     };
 
     let handle_unique_violation =
@@ -515,19 +603,29 @@ async fn move_handler(
     tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let tags = parse_config_tags(custom_headers.config_tags)?;
-    db_conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
-        let move_reponse = r#move(
-            path.into_inner(),
-            req,
-            transaction_conn,
-            true,
+    let ctx_id = path.into_inner();
+    if tenant_config.require_change_requests {
+        return submit_change_request(
+            &state,
+            &mut db_conn,
+            ChangeOperation::BulkOperations(vec![ContextAction::Move((
+                ctx_id,
+                req.into_inner(),
+            ))]),
             &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
             &tenant_config,
-        )
-        .map_err(|err| {
-            log::info!("move api failed with error: {:?}", err);
-            err
-        })?;
+        );
+    }
+    db_conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        let move_reponse =
+            r#move(ctx_id, req, transaction_conn, true, &user, &tenant_config).map_err(
+                |err| {
+                    log::info!("move api failed with error: {:?}", err);
+                    err
+                },
+            )?;
         let version_id = add_config_version(&state, tags, transaction_conn)?;
         let mut http_resp = HttpResponse::Ok();
 
@@ -556,6 +654,15 @@ async fn get_context_from_condition(
     Ok(Json(ctx))
 }
 
+#[get("/analysis")]
+async fn get_context_analysis(
+    db_conn: DbConnection,
+) -> superposition::Result<Json<ContextAnalysis>> {
+    let DbConnection(mut conn) = db_conn;
+    let config = generate_cac(&mut conn)?;
+    Ok(Json(analyse_contexts(&config)?))
+}
+
 #[get("/{ctx_id}")]
 async fn get_context(
     path: Path<String>,
@@ -605,6 +712,28 @@ async fn list_contexts(
     Ok(Json(result))
 }
 
+#[get("/archived")]
+async fn list_archived_contexts(
+    qparams: Query<PaginationParams>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<Vec<ArchivedContext>>> {
+    let DbConnection(mut conn) = db_conn;
+    let page = qparams.page.unwrap_or(1);
+    let size = qparams.size.unwrap_or(20);
+    if page < 1 {
+        return Err(bad_argument!("Param 'page' has to be at least 1."));
+    } else if size < 1 {
+        return Err(bad_argument!("Param 'size' has to be at least 1."));
+    }
+
+    let result = archived_contexts::table
+        .order(archived_contexts::archived_at.desc())
+        .limit(i64::from(size))
+        .offset(i64::from(size * (page - 1)))
+        .load::<ArchivedContext>(&mut conn)?;
+    Ok(Json(result))
+}
+
 pub fn delete_context_api(
     ctx_id: String,
     user: User,
@@ -639,9 +768,21 @@ async fn delete_context(
     custom_headers: CustomHeaders,
     user: User,
     mut db_conn: DbConnection,
+    tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let ctx_id = path.into_inner();
     let tags = parse_config_tags(custom_headers.config_tags)?;
+    if tenant_config.require_change_requests {
+        return submit_change_request(
+            &state,
+            &mut db_conn,
+            ChangeOperation::BulkOperations(vec![ContextAction::Delete(ctx_id)]),
+            &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
+            &tenant_config,
+        );
+    }
     db_conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
         delete_context_api(ctx_id, user, transaction_conn)?;
         let version_id = add_config_version(&state, tags, transaction_conn)?;
@@ -654,8 +795,61 @@ async fn delete_context(
     })
 }
 
+pub fn apply_context_actions(
+    actions: Vec<ContextAction>,
+    conn: &mut DBConnection,
+    user: &User,
+    tenant_config: &TenantConfig,
+) -> superposition::Result<Vec<ContextBulkResponse>> {
+    use contexts::dsl::contexts;
+    let mut response = Vec::<ContextBulkResponse>::new();
+    for action in actions.into_iter() {
+        match action {
+            ContextAction::Put(put_req) => {
+                let put_resp = put(Json(put_req), conn, true, user, tenant_config)
+                    .map_err(|err| {
+                        log::error!("Failed at insert into contexts due to {:?}", err);
+                        err
+                    })?;
+                response.push(ContextBulkResponse::Put(put_resp));
+            }
+            ContextAction::Delete(ctx_id) => {
+                let deleted_row = delete(contexts.filter(id.eq(&ctx_id))).execute(conn);
+                let email: String = user.get_email();
+                match deleted_row {
+                    // Any kind of error would rollback the tranction but explicitly returning rollback tranction allows you to rollback from any point in transaction.
+                    Ok(0) => {
+                        return Err(bad_argument!("context with id {} not found", ctx_id))
+                    }
+                    Ok(_) => {
+                        log::info!("{ctx_id} context deleted by {email}");
+                        response.push(ContextBulkResponse::Delete(format!(
+                            "{ctx_id} deleted succesfully"
+                        )))
+                    }
+                    Err(e) => {
+                        log::error!("Delete context failed due to {:?}", e);
+                        return Err(db_error!(e));
+                    }
+                };
+            }
+            ContextAction::Move((old_ctx_id, move_req)) => {
+                let move_context_resp =
+                    r#move(old_ctx_id, Json(move_req), conn, true, user, tenant_config)
+                        .map_err(|err| {
+                        log::error!("Failed at moving context reponse due to {:?}", err);
+                        err
+                    })?;
+                response.push(ContextBulkResponse::Move(move_context_resp));
+            }
+        }
+    }
+    Ok(response)
+}
+
 #[put("/bulk-operations")]
 async fn bulk_operations(
+    http_req: HttpRequest,
     state: Data<AppState>,
     custom_headers: CustomHeaders,
     reqs: Json<Vec<ContextAction>>,
@@ -663,67 +857,27 @@ async fn bulk_operations(
     user: User,
     tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
-    use contexts::dsl::contexts;
     let DbConnection(mut conn) = db_conn;
     let tags = parse_config_tags(custom_headers.config_tags)?;
+    let actions = reqs.into_inner();
+
+    if tenant_config.require_change_requests
+        && !is_experiment_change(&http_req, &actions, &mut conn)?
+    {
+        return submit_change_request(
+            &state,
+            &mut conn,
+            ChangeOperation::BulkOperations(actions),
+            &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
+            &tenant_config,
+        );
+    }
 
-    let mut response = Vec::<ContextBulkResponse>::new();
     conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
-        for action in reqs.into_inner().into_iter() {
-            match action {
-                ContextAction::Put(put_req) => {
-                    let put_resp =
-                        put(Json(put_req), transaction_conn, true, &user, &tenant_config)
-                            .map_err(|err| {
-                                log::error!(
-                                    "Failed at insert into contexts due to {:?}",
-                                    err
-                                );
-                                err
-                            })?;
-                    response.push(ContextBulkResponse::Put(put_resp));
-                }
-                ContextAction::Delete(ctx_id) => {
-                    let deleted_row =
-                        delete(contexts.filter(id.eq(&ctx_id))).execute(transaction_conn);
-                    let email: String = user.get_email();
-                    match deleted_row {
-                        // Any kind of error would rollback the tranction but explicitly returning rollback tranction allows you to rollback from any point in transaction.
-                        Ok(0) => {
-                            return Err(bad_argument!(
-                                "context with id {} not found",
-                                ctx_id
-                            ))
-                        }
-                        Ok(_) => {
-                            log::info!("{ctx_id} context deleted by {email}");
-                            response.push(ContextBulkResponse::Delete(format!(
-                                "{ctx_id} deleted succesfully"
-                            )))
-                        }
-                        Err(e) => {
-                            log::error!("Delete context failed due to {:?}", e);
-                            return Err(db_error!(e));
-                        }
-                    };
-                }
-                ContextAction::Move((old_ctx_id, move_req)) => {
-                    let move_context_resp = r#move(
-                        old_ctx_id,
-                        Json(move_req),
-                        transaction_conn,
-                        true,
-                        &user,
-                        &tenant_config,
-                    )
-                    .map_err(|err| {
-                        log::error!("Failed at moving context reponse due to {:?}", err);
-                        err
-                    })?;
-                    response.push(ContextBulkResponse::Move(move_context_resp));
-                }
-            }
-        }
+        let response =
+            apply_context_actions(actions, transaction_conn, &user, &tenant_config)?;
 
         let version_id = add_config_version(&state, tags, transaction_conn)?;
 
@@ -738,6 +892,43 @@ async fn bulk_operations(
     })
 }
 
+/*
+  The proposed actions are applied the same way as in bulk operations, inside a
+  transaction that is always rolled back, so they go through the same validations
+  and nothing is written.
+*/
+#[post("/simulate")]
+async fn simulate_context_actions(
+    http_req: HttpRequest,
+    req: Json<SimulationReq>,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<Json<SimulationResponse>> {
+    let DbConnection(mut conn) = db_conn;
+    let SimulationReq {
+        actions,
+        dimensions,
+    } = req.into_inner();
+
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        diesel::sql_query("SAVEPOINT simulate_ctx_savepoint")
+            .execute(transaction_conn)?;
+
+        let response = simulate_actions(
+            transaction_conn,
+            generate_cac,
+            |conn| apply_context_actions(actions, conn, &user, &tenant_config),
+            &dimensions,
+            get_merge_strategy(&http_req),
+        )?;
+
+        diesel::sql_query("ROLLBACK TO simulate_ctx_savepoint")
+            .execute(transaction_conn)?;
+        Ok(Json(response))
+    })
+}
+
 #[put("/priority/recompute")]
 async fn priority_recompute(
     state: Data<AppState>,
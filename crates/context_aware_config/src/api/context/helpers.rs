@@ -1,25 +1,39 @@
 extern crate base64;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str;
 
 use base64::prelude::*;
+use cac_client::{eval_cac, MergeStrategy};
 use diesel::{
     r2d2::{ConnectionManager, PooledConnection},
     ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
 };
-use serde_json::{Map, Value};
-use service_utils::helpers::extract_dimensions;
-use superposition_macros::{unexpected_error, validation_error};
+use serde_json::{json, Map, Value};
+use service_utils::helpers::{
+    are_constraints_satisfiable, constraints_imply, extract_dimension_constraints,
+    extract_dimensions, DimensionConstraint,
+};
+use superposition_macros::{bad_argument, unexpected_error, validation_error};
 use superposition_types::{result as superposition, Condition};
 
 use crate::api::functions::helpers::get_published_functions_by_names;
+use crate::helpers::to_cac_client_config;
 use crate::validation_functions::execute_fn;
 use crate::{
-    api::context::types::FunctionsInfo,
-    db::schema::{
-        default_configs::dsl,
-        dimensions::{self},
+    api::{
+        config::{diff_key_maps, types::Config},
+        context::types::{
+            ContextAnalysis, ContextBulkResponse, ContextViolation, FunctionsInfo,
+            OverrideConflict, RedundantOverride, ShadowedContext, SimulationResponse,
+        },
+    },
+    db::{
+        models::Context,
+        schema::{
+            default_configs::dsl,
+            dimensions::{self},
+        },
     },
 };
 
@@ -43,12 +57,16 @@ pub fn validate_condition_with_mandatory_dimensions(
     Ok(())
 }
 
+/*
+  The validations below return the published version of every function they ran,
+  so that the contexts validated by them can be traced back to it.
+*/
 pub fn validate_condition_with_functions(
     conn: &mut DBConnection,
     context: &Condition,
-) -> superposition::Result<()> {
+) -> superposition::Result<Map<String, Value>> {
     use dimensions::dsl;
-    let context = extract_dimensions(context)?;
+    let context = extract_dimension_constraints(context)?;
     let dimensions_list: Vec<String> = context.keys().cloned().collect();
     let keys_function_array: Vec<(String, Option<String>)> = dsl::dimensions
         .filter(dsl::dimension.eq_any(dimensions_list))
@@ -60,22 +78,31 @@ pub fn validate_condition_with_functions(
         .collect();
 
     let dimension_functions_map = get_functions_map(conn, new_keys_function_array)?;
-    for (key, value) in context.iter() {
+    let mut function_versions = Map::new();
+    for (key, constraints) in context.iter() {
         if let Some(functions_map) = dimension_functions_map.get(key) {
             if let (function_name, Some(function_code)) =
                 (functions_map.name.clone(), functions_map.code.clone())
             {
-                validate_value_with_function(&function_name, &function_code, key, value)?;
+                for value in constraints.iter().flat_map(|c| c.values()) {
+                    validate_value_with_function(
+                        &function_name,
+                        &function_code,
+                        key,
+                        value,
+                    )?;
+                }
+                function_versions.insert(function_name, json!(functions_map.version));
             }
         }
     }
-    Ok(())
+    Ok(function_versions)
 }
 
 pub fn validate_override_with_functions(
     conn: &mut DBConnection,
     override_: &Map<String, Value>,
-) -> superposition::Result<()> {
+) -> superposition::Result<Map<String, Value>> {
     let default_config_keys: Vec<String> = override_.keys().cloned().collect();
     let keys_function_array: Vec<(String, Option<String>)> = dsl::default_configs
         .filter(dsl::key.eq_any(default_config_keys))
@@ -87,23 +114,25 @@ pub fn validate_override_with_functions(
         .collect();
 
     let default_config_functions_map = get_functions_map(conn, new_keys_function_array)?;
+    let mut function_versions = Map::new();
     for (key, value) in override_.iter() {
         if let Some(functions_map) = default_config_functions_map.get(key) {
             if let (function_name, Some(function_code)) =
                 (functions_map.name.clone(), functions_map.code.clone())
             {
                 validate_value_with_function(&function_name, &function_code, key, value)?;
+                function_versions.insert(function_name, json!(functions_map.version));
             }
         }
     }
-    Ok(())
+    Ok(function_versions)
 }
 
 fn get_functions_map(
     conn: &mut DBConnection,
     keys_function_array: Vec<(String, String)>,
 ) -> superposition::Result<HashMap<String, FunctionsInfo>> {
-    let functions_map: HashMap<String, Option<String>> =
+    let functions_map: HashMap<String, (Option<String>, Option<i32>)> =
         get_published_functions_by_names(
             conn,
             keys_function_array
@@ -112,6 +141,7 @@ fn get_functions_map(
                 .collect(),
         )?
         .into_iter()
+        .map(|(name, code, version)| (name, (code, version)))
         .collect();
 
     let default_config_functions_map: HashMap<String, FunctionsInfo> =
@@ -122,7 +152,12 @@ fn get_functions_map(
                     key.clone(),
                     FunctionsInfo {
                         name: function_name.clone(),
-                        code: functions_map.get(&function_name).cloned().flatten(),
+                        code: functions_map
+                            .get(&function_name)
+                            .and_then(|(code, _)| code.clone()),
+                        version: functions_map
+                            .get(&function_name)
+                            .and_then(|(_, version)| *version),
                     },
                 )
             })
@@ -131,7 +166,7 @@ fn get_functions_map(
 }
 
 pub fn validate_value_with_function(
-    _fun_name: &str,
+    fun_name: &str,
     function: &str,
     key: &String,
     value: &Value,
@@ -144,7 +179,8 @@ pub fn validate_value_with_function(
         log::error!("Failed to parse function code in UTF-8: {}", err);
         unexpected_error!("Failed to parse function code in UTF-8: {}", err)
     })?;
-    if let Err((err, stdout)) = execute_fn(utf8_decoded, key, value.to_owned()) {
+    if let Err((err, stdout)) = execute_fn(fun_name, utf8_decoded, key, value.to_owned())
+    {
         let stdout = stdout.unwrap_or(String::new());
         log::error!("function validation failed for {key} with error: {err}");
         return Err(validation_error!(
@@ -156,3 +192,646 @@ pub fn validate_value_with_function(
     }
     Ok(())
 }
+
+/*
+  Schema and function changes are checked against the values already stored in
+  contexts before they are applied. A violation is an override value, or a value a
+  dimension is compared against in a condition, that the new rule rejects.
+*/
+pub fn find_override_violations(
+    contexts: &[Context],
+    key: &str,
+    validate: impl Fn(&Value) -> superposition::Result<()>,
+) -> Vec<ContextViolation> {
+    contexts
+        .iter()
+        .filter_map(|context| {
+            let value = context.override_.get(key)?;
+            validate(value).err().map(|err| ContextViolation {
+                context_id: context.id.clone(),
+                name: key.to_string(),
+                value: value.clone(),
+                message: err.message(),
+            })
+        })
+        .collect()
+}
+
+pub fn find_dimension_violations(
+    contexts: &[Context],
+    dimension: &str,
+    validate: impl Fn(&DimensionConstraint) -> superposition::Result<()>,
+) -> superposition::Result<Vec<ContextViolation>> {
+    let mut violations = Vec::new();
+    for context in contexts {
+        let condition = context.value.as_object().ok_or_else(|| {
+            log::error!("condition of context {} is not an object", context.id);
+            unexpected_error!("failed to decode condition of context {}", context.id)
+        })?;
+        let constraints = extract_dimension_constraints(condition)?
+            .remove(dimension)
+            .unwrap_or_default();
+        for constraint in constraints {
+            if let Err(err) = validate(&constraint) {
+                violations.push(ContextViolation {
+                    context_id: context.id.clone(),
+                    name: dimension.to_string(),
+                    value: constraint.value,
+                    message: err.message(),
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+pub fn violations_error(
+    change: String,
+    violations: &[ContextViolation],
+) -> superposition::AppError {
+    let details = violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "{} in context {}: {}",
+                violation.name, violation.context_id, violation.message
+            )
+        })
+        .collect::<Vec<_>>();
+    bad_argument!(
+        "{} would invalidate {} existing context value(s), pass force=true to apply it anyway. {}",
+        change,
+        violations.len(),
+        details.join("; ")
+    )
+}
+
+type DimensionConstraints = HashMap<String, Vec<DimensionConstraint>>;
+
+// whether some request can match both the contexts
+fn can_match_together(a: &DimensionConstraints, b: &DimensionConstraints) -> bool {
+    a.iter()
+        .all(|(dimension, constraints)| match b.get(dimension) {
+            Some(other_constraints) => are_constraints_satisfiable(
+                &constraints
+                    .iter()
+                    .chain(other_constraints)
+                    .collect::<Vec<_>>(),
+            ),
+            None => true,
+        })
+}
+
+// whether every request matching `context` also matches `other`
+fn always_matches_with(
+    context: &DimensionConstraints,
+    other: &DimensionConstraints,
+) -> bool {
+    other.iter().all(
+        |(dimension, other_constraints)| match context.get(dimension) {
+            Some(constraints) => constraints_imply(
+                &constraints.iter().collect::<Vec<_>>(),
+                &other_constraints.iter().collect::<Vec<_>>(),
+            ),
+            None => false,
+        },
+    )
+}
+
+/*
+  Contexts are applied in increasing order of priority, so when two contexts can
+  match the same request the one applied later wins for the keys both override.
+  A context is shadowed when, for each of its keys, some later context overriding
+  that key matches every request the context matches. Override values are
+  compared as a whole.
+*/
+pub fn analyse_contexts(config: &Config) -> superposition::Result<ContextAnalysis> {
+    let empty_overrides = Map::new();
+    let contexts = config
+        .contexts
+        .iter()
+        .map(|context| -> superposition::Result<_> {
+            let overrides = config
+                .overrides
+                .get(&context.override_with_keys[0])
+                .map_or(&empty_overrides, |overrides| &**overrides);
+            Ok((
+                context,
+                extract_dimension_constraints(&context.condition)?,
+                overrides,
+            ))
+        })
+        .collect::<superposition::Result<Vec<_>>>()?;
+
+    let mut analysis = ContextAnalysis::default();
+    for (index, (context, constraints, overrides)) in contexts.iter().enumerate() {
+        for (key, value) in overrides.iter() {
+            if config.default_configs.get(key) == Some(value) {
+                analysis.redundant_overrides.push(RedundantOverride {
+                    context_id: context.id.clone(),
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+
+        let mut shadowed_keys = HashSet::new();
+        let mut shadowed_by = Vec::new();
+        for (winner, winner_constraints, winner_overrides) in &contexts[index + 1..] {
+            if !can_match_together(constraints, winner_constraints) {
+                continue;
+            }
+            let always_overridden = always_matches_with(constraints, winner_constraints);
+            let mut shadows = false;
+            for (key, value) in overrides.iter() {
+                let Some(winning_value) = winner_overrides.get(key) else {
+                    continue;
+                };
+                if always_overridden {
+                    shadowed_keys.insert(key);
+                    shadows = true;
+                }
+                if winning_value != value {
+                    analysis.conflicts.push(OverrideConflict {
+                        key: key.clone(),
+                        winning_context_id: winner.id.clone(),
+                        winning_value: winning_value.clone(),
+                        losing_context_id: context.id.clone(),
+                        losing_value: value.clone(),
+                    });
+                }
+            }
+            if shadows {
+                shadowed_by.push(winner.id.clone());
+            }
+        }
+        if !overrides.is_empty() && shadowed_keys.len() == overrides.len() {
+            analysis.shadowed_contexts.push(ShadowedContext {
+                context_id: context.id.clone(),
+                shadowed_by,
+            });
+        }
+    }
+    Ok(analysis)
+}
+
+fn resolve_config(
+    config: &Config,
+    query_data: &Map<String, Value>,
+    merge_strategy: MergeStrategy,
+) -> superposition::Result<Map<String, Value>> {
+    let (cac_client_contexts, override_map) = to_cac_client_config(config);
+    eval_cac(
+        config.default_configs.clone(),
+        &cac_client_contexts,
+        &override_map,
+        query_data,
+        merge_strategy,
+    )
+    .map_err(|err| {
+        log::error!("failed to eval cac with err: {}", err);
+        unexpected_error!("cac eval failed")
+    })
+}
+
+/*
+  Resolves the config for the dimensions before and after the actions are applied
+  to the state, undoing the actions is left to the caller.
+*/
+pub fn simulate_actions<S>(
+    state: &mut S,
+    generate_config: impl Fn(&mut S) -> superposition::Result<Config>,
+    apply_actions: impl FnOnce(&mut S) -> superposition::Result<Vec<ContextBulkResponse>>,
+    dimensions: &Map<String, Value>,
+    merge_strategy: MergeStrategy,
+) -> superposition::Result<SimulationResponse> {
+    let before = resolve_config(&generate_config(state)?, dimensions, merge_strategy)?;
+    let actions = apply_actions(state)?;
+    let after = resolve_config(&generate_config(state)?, dimensions, merge_strategy)?;
+    Ok(SimulationResponse {
+        actions,
+        changes: diff_key_maps(&before, &after),
+        before,
+        after,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::api::{
+        config::{
+            build_config,
+            types::{KeyDiff, ValueChange},
+        },
+        context::types::PutResp,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_analyse_contexts() {
+        let config = build_config(
+            vec![
+                (
+                    "delhi",
+                    json!({"==": [{"var": "city"}, "Delhi"]}),
+                    0,
+                    json!({"rate": 10, "surge": 0}),
+                ),
+                (
+                    "delhi_evening",
+                    json!({"and": [
+                        {"==": [{"var": "city"}, "Delhi"]},
+                        {">=": [{"var": "hour_of_day"}, 18]}
+                    ]}),
+                    1,
+                    json!({"rate": 15}),
+                ),
+                (
+                    "metros",
+                    json!({"in": [{"var": "city"}, ["Delhi", "Mumbai"]]}),
+                    2,
+                    json!({"surge": 1}),
+                ),
+                (
+                    "chennai",
+                    json!({"==": [{"var": "city"}, "Chennai"]}),
+                    3,
+                    json!({"rate": 20}),
+                ),
+            ],
+            json!({"rate": 5, "surge": 0}),
+        );
+
+        let analysis = analyse_contexts(&config).expect("analysis failed");
+
+        let conflicts = analysis
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                (
+                    conflict.key.as_str(),
+                    conflict.winning_context_id.as_str(),
+                    conflict.losing_context_id.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![
+                ("rate", "delhi_evening", "delhi"),
+                ("surge", "metros", "delhi")
+            ]
+        );
+
+        // delhi_evening only wins in the evening, so rate of delhi is still used
+        assert!(analysis.shadowed_contexts.is_empty());
+
+        let redundant = analysis
+            .redundant_overrides
+            .iter()
+            .map(|redundant| (redundant.context_id.as_str(), redundant.key.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(redundant, vec![("delhi", "surge")]);
+
+        let shadowing_config = build_config(
+            vec![
+                (
+                    "delhi_evening",
+                    json!({"and": [
+                        {"==": [{"var": "city"}, "Delhi"]},
+                        {">=": [{"var": "hour_of_day"}, 18]}
+                    ]}),
+                    0,
+                    json!({"rate": 15}),
+                ),
+                (
+                    "delhi",
+                    json!({"==": [{"var": "city"}, "Delhi"]}),
+                    1,
+                    json!({"rate": 10}),
+                ),
+            ],
+            json!({"rate": 5}),
+        );
+        let analysis = analyse_contexts(&shadowing_config).expect("analysis failed");
+        assert_eq!(analysis.shadowed_contexts.len(), 1);
+        assert_eq!(analysis.shadowed_contexts[0].context_id, "delhi_evening");
+        assert_eq!(analysis.shadowed_contexts[0].shadowed_by, vec!["delhi"]);
+    }
+
+    type ContextRows = Vec<(&'static str, Value, i32, Value)>;
+
+    fn simulate(
+        contexts: &mut ContextRows,
+        dimensions: Value,
+        apply_actions: impl FnOnce(
+            &mut ContextRows,
+        ) -> superposition::Result<Vec<ContextBulkResponse>>,
+    ) -> superposition::Result<SimulationResponse> {
+        simulate_actions(
+            contexts,
+            |contexts| {
+                Ok(build_config(
+                    contexts.clone(),
+                    json!({"rate": 5, "surge": 0}),
+                ))
+            },
+            apply_actions,
+            &dimensions.as_object().cloned().unwrap_or_default(),
+            MergeStrategy::MERGE,
+        )
+    }
+
+    fn delhi() -> ContextRows {
+        vec![(
+            "delhi",
+            json!({"==": [{"var": "city"}, "Delhi"]}),
+            0,
+            json!({"rate": 10}),
+        )]
+    }
+
+    fn put_resp(context_id: &str) -> ContextBulkResponse {
+        ContextBulkResponse::Put(PutResp {
+            context_id: context_id.to_string(),
+            override_id: format!("{context_id}-override"),
+            priority: 1,
+        })
+    }
+
+    fn rate_change(from: i64, to: i64) -> BTreeMap<String, ValueChange> {
+        BTreeMap::from([(
+            "rate".to_string(),
+            ValueChange {
+                from: json!(from),
+                to: json!(to),
+            },
+        )])
+    }
+
+    #[test]
+    fn test_simulate_put() {
+        let mut contexts = delhi();
+        let simulation = simulate(
+            &mut contexts,
+            json!({"city": "Delhi", "vehicle_type": "suv"}),
+            |contexts| {
+                contexts.push((
+                    "delhi_suv",
+                    json!({"and": [
+                        {"==": [{"var": "city"}, "Delhi"]},
+                        {"==": [{"var": "vehicle_type"}, "suv"]}
+                    ]}),
+                    1,
+                    json!({"rate": 20, "surge": 1}),
+                ));
+                Ok(vec![put_resp("delhi_suv")])
+            },
+        )
+        .expect("simulation failed");
+
+        assert_eq!(
+            Value::Object(simulation.before),
+            json!({"rate": 10, "surge": 0})
+        );
+        assert_eq!(
+            Value::Object(simulation.after),
+            json!({"rate": 20, "surge": 1})
+        );
+        let mut changed = rate_change(10, 20);
+        changed.insert(
+            "surge".to_string(),
+            ValueChange {
+                from: json!(0),
+                to: json!(1),
+            },
+        );
+        assert_eq!(
+            simulation.changes,
+            KeyDiff {
+                changed,
+                ..Default::default()
+            }
+        );
+        assert!(matches!(
+            simulation.actions.as_slice(),
+            [ContextBulkResponse::Put(resp)] if resp.context_id == "delhi_suv"
+        ));
+    }
+
+    #[test]
+    fn test_simulate_delete() {
+        let mut contexts = delhi();
+        let simulation = simulate(&mut contexts, json!({"city": "Delhi"}), |contexts| {
+            contexts.clear();
+            Ok(vec![ContextBulkResponse::Delete(
+                "delhi deleted succesfully".to_string(),
+            )])
+        })
+        .expect("simulation failed");
+
+        assert_eq!(
+            Value::Object(simulation.before),
+            json!({"rate": 10, "surge": 0})
+        );
+        assert_eq!(
+            Value::Object(simulation.after),
+            json!({"rate": 5, "surge": 0})
+        );
+        assert_eq!(
+            simulation.changes,
+            KeyDiff {
+                changed: rate_change(10, 5),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_simulate_move() {
+        let mut contexts = delhi();
+        let dimensions = json!({"city": "Mumbai"});
+        let simulation = simulate(&mut contexts, dimensions, |contexts| {
+            contexts[0].1 = json!({"==": [{"var": "city"}, "Mumbai"]});
+            Ok(vec![ContextBulkResponse::Move(PutResp {
+                context_id: "mumbai".to_string(),
+                override_id: "delhi-override".to_string(),
+                priority: 0,
+            })])
+        })
+        .expect("simulation failed");
+
+        assert_eq!(
+            Value::Object(simulation.before),
+            json!({"rate": 5, "surge": 0})
+        );
+        assert_eq!(
+            Value::Object(simulation.after),
+            json!({"rate": 10, "surge": 0})
+        );
+        assert_eq!(
+            simulation.changes,
+            KeyDiff {
+                changed: rate_change(5, 10),
+                ..Default::default()
+            }
+        );
+
+        // nothing that resolves for the dimensions changed, so neither does the config
+        let simulation = simulate(&mut delhi(), json!({"city": "Chennai"}), |contexts| {
+            contexts[0].1 = json!({"==": [{"var": "city"}, "Mumbai"]});
+            Ok(Vec::new())
+        })
+        .expect("simulation failed");
+        assert!(simulation.changes.is_empty());
+    }
+
+    #[test]
+    fn test_simulate_invalid_action() {
+        let mut contexts = delhi();
+        let result = simulate(&mut contexts, json!({"city": "Delhi"}), |_| {
+            Err(validation_error!(
+                "schema validation failed for rate: {}",
+                "\"ten\" is not of type \"integer\""
+            ))
+        });
+
+        match result {
+            Err(err) => assert!(err
+                .to_string()
+                .contains("schema validation failed for rate")),
+            Ok(_) => panic!("an invalid action should fail the simulation"),
+        }
+    }
+
+    fn stored_context(id: &str, condition: Value, override_: Value) -> Context {
+        Context {
+            id: id.to_string(),
+            value: condition,
+            override_id: format!("{id}_override"),
+            created_at: chrono::Utc::now(),
+            created_by: String::from("user@superposition.io"),
+            priority: 1,
+            override_,
+            last_modified_at: chrono::Utc::now().naive_utc(),
+            last_modified_by: String::from("user@superposition.io"),
+            function_versions: json!({}),
+            active_from: None,
+            active_until: None,
+        }
+    }
+
+    fn violation_summary(violations: &[ContextViolation]) -> Vec<(&str, &str, &Value)> {
+        violations
+            .iter()
+            .map(|violation| {
+                (
+                    violation.context_id.as_str(),
+                    violation.name.as_str(),
+                    &violation.value,
+                )
+            })
+            .collect()
+    }
+
+    fn stored_contexts() -> Vec<Context> {
+        vec![
+            stored_context(
+                "delhi",
+                json!({"==": [{"var": "city"}, "Delhi"]}),
+                json!({"rate": 10}),
+            ),
+            stored_context(
+                "metros",
+                json!({"in": [{"var": "city"}, ["Mumbai", "chennai"]]}),
+                json!({"rate": -1, "surge": 2}),
+            ),
+            stored_context(
+                "ios",
+                json!({"==": [{"var": "os"}, "ios"]}),
+                json!({"surge": 0}),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_find_override_violations() {
+        let positive = |value: &Value| {
+            if value.as_i64().is_some_and(|value| value > 0) {
+                Ok(())
+            } else {
+                Err(validation_error!("{} is not positive", value))
+            }
+        };
+        let contexts = stored_contexts();
+
+        let violations = find_override_violations(&contexts, "rate", positive);
+        assert_eq!(
+            violation_summary(&violations),
+            vec![("metros", "rate", &json!(-1))]
+        );
+        assert_eq!(violations[0].message, "-1 is not positive");
+
+        let violations = find_override_violations(&contexts, "surge", positive);
+        assert_eq!(
+            violation_summary(&violations),
+            vec![("ios", "surge", &json!(0))]
+        );
+
+        assert!(find_override_violations(&contexts, "unused", positive).is_empty());
+    }
+
+    #[test]
+    fn test_find_dimension_violations() {
+        let capitalized = |constraint: &DimensionConstraint| {
+            constraint.values().into_iter().try_for_each(|value| {
+                match value.as_str().and_then(|city| city.chars().next()) {
+                    Some(initial) if initial.is_uppercase() => Ok(()),
+                    _ => Err(validation_error!("{} is not capitalized", value)),
+                }
+            })
+        };
+        let contexts = stored_contexts();
+
+        let violations = find_dimension_violations(&contexts, "city", capitalized)
+            .expect("conditions should be readable");
+        assert_eq!(
+            violation_summary(&violations),
+            vec![("metros", "city", &json!(["Mumbai", "chennai"]))]
+        );
+        assert_eq!(violations[0].message, "\"chennai\" is not capitalized");
+
+        let violations = find_dimension_violations(&contexts, "vehicle", capitalized)
+            .expect("conditions should be readable");
+        assert!(violations.is_empty());
+
+        let unreadable = vec![stored_context("broken", json!("city"), json!({}))];
+        assert!(find_dimension_violations(&unreadable, "city", capitalized).is_err());
+    }
+
+    #[test]
+    fn test_violations_error() {
+        let violations = vec![
+            ContextViolation {
+                context_id: "metros".to_string(),
+                name: "rate".to_string(),
+                value: json!(-1),
+                message: "-1 is not positive".to_string(),
+            },
+            ContextViolation {
+                context_id: "ios".to_string(),
+                name: "surge".to_string(),
+                value: json!(0),
+                message: "0 is not positive".to_string(),
+            },
+        ];
+        let err = violations_error("Publishing positive".to_string(), &violations);
+        assert_eq!(
+            err.message(),
+            "Publishing positive would invalidate 2 existing context value(s), pass force=true to apply it anyway. rate in context metros: -1 is not positive; surge in context ios: 0 is not positive"
+        );
+    }
+}
@@ -1,25 +1,27 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use superposition_types::{Cac, Condition, Overrides};
 
+use crate::api::config::types::KeyDiff;
+
 #[cfg_attr(test, derive(Debug, PartialEq))] // Derive traits only when running tests
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct PutReq {
     pub context: Cac<Condition>,
     pub r#override: Cac<Overrides>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<DateTime<Utc>>,
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq))] // Derive traits only when running tests
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct MoveReq {
     pub context: Cac<Condition>,
 }
 
-#[derive(Deserialize, Clone)]
-pub struct DimensionCondition {
-    pub var: String,
-}
-
 #[derive(Serialize, Debug)]
 pub struct PutResp {
     pub context_id: String,
@@ -34,7 +36,7 @@ pub struct PaginationParams {
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq))] // Derive traits only when running tests
-#[derive(serde::Deserialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ContextAction {
     Put(PutReq),
@@ -50,10 +52,26 @@ pub enum ContextBulkResponse {
     Move(PutResp),
 }
 
+#[derive(Deserialize)]
+pub struct SimulationReq {
+    pub actions: Vec<ContextAction>,
+    #[serde(default)]
+    pub dimensions: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+pub struct SimulationResponse {
+    pub actions: Vec<ContextBulkResponse>,
+    pub before: Map<String, Value>,
+    pub after: Map<String, Value>,
+    pub changes: KeyDiff,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct FunctionsInfo {
     pub name: String,
     pub code: Option<String>,
+    pub version: Option<i32>,
 }
 
 #[derive(Serialize)]
@@ -64,6 +82,49 @@ pub struct PriorityRecomputeResponse {
     pub new_priority: i32,
 }
 
+#[derive(Serialize)]
+pub struct OverrideConflict {
+    pub key: String,
+    pub winning_context_id: String,
+    pub winning_value: Value,
+    pub losing_context_id: String,
+    pub losing_value: Value,
+}
+
+#[derive(Serialize)]
+pub struct ShadowedContext {
+    pub context_id: String,
+    pub shadowed_by: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RedundantOverride {
+    pub context_id: String,
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Serialize, Default)]
+pub struct ContextAnalysis {
+    pub conflicts: Vec<OverrideConflict>,
+    pub shadowed_contexts: Vec<ShadowedContext>,
+    pub redundant_overrides: Vec<RedundantOverride>,
+}
+
+#[derive(Serialize)]
+pub struct ContextViolation {
+    pub context_id: String,
+    pub name: String,
+    pub value: Value,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct RevalidationQuery {
+    #[serde(default)]
+    pub force: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,6 +164,8 @@ mod tests {
         let expected_action = ContextAction::Put(PutReq {
             context: context,
             r#override: override_,
+            active_from: None,
+            active_until: None,
         });
 
         let action_deserialized =
@@ -1,3 +1,9 @@
 mod handlers;
 mod types;
 pub use handlers::endpoints;
+pub use handlers::{
+    apply_default_config_aliases, delete_default_config, get_default_config_aliases,
+    get_deprecated_keys, put_default_config, validate_default_config,
+    validate_key_usages,
+};
+pub use types::CreateReq;
@@ -1,27 +1,50 @@
 extern crate base64;
-use super::types::CreateReq;
+use std::collections::HashMap;
+
+use super::types::{
+    CreateReq, DefaultConfigWithResolvedSchema, DeprecateReq, DeprecatedKeyUsage,
+    KeyDeprecation, RenameReq, RenameResponse, RenamedOverride,
+};
 use service_utils::{
     helpers::{parse_config_tags, validation_err_to_str},
-    service::types::{AppHeader, AppState, CustomHeaders, DbConnection},
+    service::types::{AppHeader, AppState, CustomHeaders, DbConnection, Tenant},
 };
 
 use superposition_macros::{
     bad_argument, db_error, not_found, unexpected_error, validation_error,
 };
-use superposition_types::{result as superposition, User};
+use superposition_types::{result as superposition, TenantConfig, User};
 
 use crate::{
     api::{
-        context::helpers::validate_value_with_function,
+        change_requests::{
+            parse_reviewers, reject_unreviewed_change, submit_change_request,
+            ChangeOperation,
+        },
+        context::{
+            hash,
+            helpers::{
+                find_override_violations, validate_value_with_function, violations_error,
+            },
+            ContextViolation, RevalidationQuery,
+        },
         default_config::types::DefaultConfigKey,
         functions::helpers::get_published_function_code,
+        type_templates::helpers::{
+            get_type_templates_map, resolve_schema, resolve_type_refs,
+        },
     },
     db::{
         self,
-        models::{self, Context, DefaultConfig},
-        schema::{contexts::dsl::contexts, default_configs::dsl},
+        models::{
+            self, Context, DefaultConfig, DefaultConfigAlias, DefaultConfigDeprecation,
+        },
+        schema::{
+            contexts, default_config_aliases, default_config_deprecations,
+            default_configs::dsl,
+        },
     },
-    helpers::add_config_version,
+    helpers::{add_config_version, experimentation_namespace},
 };
 use actix_web::{
     delete, get, put,
@@ -31,14 +54,24 @@ use actix_web::{
 use chrono::Utc;
 use diesel::{
     r2d2::{ConnectionManager, PooledConnection},
-    ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+    sql_query,
+    sql_types::{Array, BigInt, Json as JsonType, Text},
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryableByName,
+    RunQueryDsl,
 };
 use diesel::{Connection, SelectableHelper};
 use jsonschema::{Draft, JSONSchema, ValidationError};
 use serde_json::{from_value, Map, Value};
 
 pub fn endpoints() -> Scope {
-    Scope::new("").service(create).service(get).service(delete)
+    Scope::new("")
+        .service(create)
+        .service(get)
+        .service(delete)
+        .service(rename)
+        .service(deprecate)
+        .service(undeprecate)
+        .service(get_deprecation_report)
 }
 
 #[put("/{key}")]
@@ -47,14 +80,56 @@ async fn create(
     key: web::Path<DefaultConfigKey>,
     custom_headers: CustomHeaders,
     request: web::Json<CreateReq>,
+    query: web::Query<RevalidationQuery>,
     db_conn: DbConnection,
     user: User,
+    tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let DbConnection(mut conn) = db_conn;
     let req = request.into_inner();
-    let key = key.into_inner().into();
+    let key: String = key.into_inner().into();
     let tags = parse_config_tags(custom_headers.config_tags)?;
 
+    if tenant_config.require_change_requests {
+        return submit_change_request(
+            &state,
+            &mut conn,
+            ChangeOperation::PutDefaultConfig {
+                key,
+                request: req,
+                force: query.force,
+            },
+            &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
+            &tenant_config,
+        );
+    }
+
+    let (default_config, version_id) = conn
+        .transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let default_config =
+                put_default_config(key, req, query.force, transaction_conn, &user)?;
+            let version_id = add_config_version(&state, tags, transaction_conn)?;
+            Ok((default_config, version_id))
+        })?;
+
+    let mut http_resp = HttpResponse::Ok();
+
+    http_resp.insert_header((
+        AppHeader::XConfigVersion.to_string(),
+        version_id.to_string(),
+    ));
+    Ok(http_resp.json(default_config))
+}
+
+pub fn put_default_config(
+    key: String,
+    req: CreateReq,
+    force: bool,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    user: &User,
+) -> superposition::Result<DefaultConfig> {
     if req.value.is_none() && req.schema.is_none() && req.function_name.is_none() {
         log::error!("No data provided in the request body for {key}");
         return Err(bad_argument!("Please provide data in the request body."));
@@ -70,7 +145,11 @@ async fn create(
         }
     };
 
-    let result = fetch_default_key(&key, &mut conn);
+    let result = fetch_default_key(&key, conn);
+    let previous_rule = result
+        .as_ref()
+        .ok()
+        .map(|row| (row.schema.clone(), row.function_name.clone()));
 
     let (value, schema, function_name, created_at_val, created_by_val) = match result {
         Ok(default_config_row) => {
@@ -123,9 +202,55 @@ async fn create(
         last_modified_by: user.get_email(),
     };
 
+    validate_default_config(&default_config, conn)?;
+
+    let rule_changed = previous_rule.map_or(false, |(schema, function_name)| {
+        schema != default_config.schema || function_name != default_config.function_name
+    });
+    if rule_changed {
+        let violations = validate_key_usages(&default_config, conn)?;
+        if !violations.is_empty() {
+            if !force {
+                return Err(violations_error(
+                    format!("Updating the schema or function of {key}"),
+                    &violations,
+                ));
+            }
+            log::warn!(
+                "{key} updated by {} with {} overrides violating it",
+                user.get_email(),
+                violations.len()
+            );
+        }
+    }
+
+    let upsert = diesel::insert_into(dsl::default_configs)
+        .values(&default_config)
+        .on_conflict(db::schema::default_configs::key)
+        .do_update()
+        .set(&default_config)
+        .execute(conn);
+    if let Err(e) = upsert {
+        log::info!("DefaultConfig creation failed with error: {e}");
+        return Err(unexpected_error!(
+            "Something went wrong, failed to create DefaultConfig"
+        ));
+    }
+    // a key created under an alias name takes over from the alias
+    diesel::delete(default_config_aliases::table)
+        .filter(default_config_aliases::alias.eq(&default_config.key))
+        .execute(conn)?;
+    Ok(default_config)
+}
+
+pub fn validate_default_config(
+    default_config: &DefaultConfig,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<()> {
+    let schema = resolve_schema(conn, &default_config.schema)?;
     let schema_compile_result = JSONSchema::options()
         .with_draft(Draft::Draft7)
-        .compile(&default_config.schema);
+        .compile(&schema);
     let jschema = match schema_compile_result {
         Ok(jschema) => jschema,
         Err(e) => {
@@ -149,7 +274,7 @@ async fn create(
     }
 
     if let Some(f_name) = &default_config.function_name {
-        let function_code = get_published_function_code(&mut conn, f_name.to_string())
+        let function_code = get_published_function_code(conn, f_name.to_string())
             .map_err(|e| {
                 log::info!("Function not found with error : {e}");
                 bad_argument!("Function {} doesn't exists.", f_name)
@@ -163,33 +288,53 @@ async fn create(
             )?;
         }
     }
-    let version_id =
-        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
-            let upsert = diesel::insert_into(dsl::default_configs)
-                .values(&default_config)
-                .on_conflict(db::schema::default_configs::key)
-                .do_update()
-                .set(&default_config)
-                .execute(transaction_conn);
-            let version_id = add_config_version(&state, tags, transaction_conn)?;
-            match upsert {
-                Ok(_) => Ok(version_id),
-                Err(e) => {
-                    log::info!("DefaultConfig creation failed with error: {e}");
-                    Err(unexpected_error!(
-                        "Something went wrong, failed to create DefaultConfig"
-                    ))
-                }
-            }
-        })?;
+    Ok(())
+}
 
-    let mut http_resp = HttpResponse::Ok();
+// overrides of the key checked against its new schema and function
+pub fn validate_key_usages(
+    default_config: &DefaultConfig,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Vec<ContextViolation>> {
+    let schema = resolve_schema(conn, &default_config.schema)?;
+    let jschema = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .map_err(|e| {
+            log::info!("Failed to compile as a Draft-7 JSON schema: {e}");
+            bad_argument!("Invalid JSON schema (failed to compile)")
+        })?;
+    let function = match &default_config.function_name {
+        Some(f_name) => get_published_function_code(conn, f_name.to_string())?
+            .map(|f_code| (f_name, f_code)),
+        None => None,
+    };
 
-    http_resp.insert_header((
-        AppHeader::XConfigVersion.to_string(),
-        version_id.to_string(),
-    ));
-    Ok(http_resp.json(default_config))
+    let key_usages = get_key_usage_contexts(&default_config.key, conn)?;
+    Ok(find_override_violations(
+        &key_usages,
+        &default_config.key,
+        |value| {
+            if let Err(e) = jschema.validate(value) {
+                let verrors = e.collect::<Vec<ValidationError>>();
+                return Err(validation_error!(
+                    "schema validation failed: {}",
+                    validation_err_to_str(verrors)
+                        .first()
+                        .unwrap_or(&String::new())
+                ));
+            }
+            match &function {
+                Some((f_name, f_code)) => validate_value_with_function(
+                    f_name,
+                    f_code,
+                    &default_config.key,
+                    value,
+                ),
+                None => Ok(()),
+            }
+        },
+    ))
 }
 
 fn fetch_default_key(
@@ -204,33 +349,59 @@ fn fetch_default_key(
 }
 
 #[get("")]
-async fn get(db_conn: DbConnection) -> superposition::Result<Json<Vec<DefaultConfig>>> {
+async fn get(
+    db_conn: DbConnection,
+) -> superposition::Result<Json<Vec<DefaultConfigWithResolvedSchema>>> {
     let DbConnection(mut conn) = db_conn;
 
     let result: Vec<DefaultConfig> = dsl::default_configs.get_results(&mut conn)?;
+    let templates = get_type_templates_map(&mut conn)?;
+
+    // clients read the type of a key from the resolved schema, edits send back the saved one
+    let result = result
+        .into_iter()
+        .map(|default_config| {
+            Ok(DefaultConfigWithResolvedSchema {
+                resolved_schema: resolve_type_refs(&default_config.schema, &templates)?,
+                default_config,
+            })
+        })
+        .collect::<superposition::Result<Vec<_>>>()?;
     Ok(Json(result))
 }
 
-pub fn get_key_usage_context_ids(
+pub fn get_key_usage_contexts(
     key: &str,
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-) -> superposition::Result<Vec<String>> {
-    let result: Vec<Context> = contexts.load(conn).map_err(|err| {
+) -> superposition::Result<Vec<Context>> {
+    let result: Vec<Context> = contexts::table.load(conn).map_err(|err| {
         log::error!("failed to fetch contexts with error: {}", err);
         db_error!(err)
     })?;
 
-    let mut context_ids = vec![];
-    for context in result.iter() {
-        from_value::<Map<String, Value>>(context.override_.to_owned())
+    let mut key_usages = vec![];
+    for context in result.into_iter() {
+        let uses_key = from_value::<Map<String, Value>>(context.override_.to_owned())
             .map_err(|err| {
                 log::error!("failed decode override into object: {}", err);
                 unexpected_error!("failed to decode override")
             })?
-            .get(key)
-            .map_or((), |_| context_ids.push(context.id.to_owned()))
+            .contains_key(key);
+        if uses_key {
+            key_usages.push(context);
+        }
     }
-    Ok(context_ids)
+    Ok(key_usages)
+}
+
+pub fn get_key_usage_context_ids(
+    key: &str,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Vec<String>> {
+    Ok(get_key_usage_contexts(key, conn)?
+        .into_iter()
+        .map(|context| context.id)
+        .collect())
 }
 
 #[delete("/{key}")]
@@ -240,52 +411,614 @@ async fn delete(
     custom_headers: CustomHeaders,
     db_conn: DbConnection,
     user: User,
+    tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let DbConnection(mut conn) = db_conn;
     let tags = parse_config_tags(custom_headers.config_tags)?;
-
     let key: String = path.into_inner().into();
-    fetch_default_key(&key, &mut conn)?;
-    let context_ids = get_key_usage_context_ids(&key, &mut conn)
+
+    if tenant_config.require_change_requests {
+        return submit_change_request(
+            &state,
+            &mut conn,
+            ChangeOperation::DeleteDefaultConfig { key },
+            &user,
+            tags,
+            parse_reviewers(custom_headers.reviewers),
+            &tenant_config,
+        );
+    }
+
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        delete_default_config(&key, transaction_conn, &user)?;
+        let version_id = add_config_version(&state, tags, transaction_conn)?;
+        log::info!("default config key: {key} deleted by {}", user.get_email());
+        Ok(HttpResponse::NoContent()
+            .insert_header((
+                AppHeader::XConfigVersion.to_string(),
+                version_id.to_string(),
+            ))
+            .finish())
+    })
+}
+
+pub fn delete_default_config(
+    key: &String,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    user: &User,
+) -> superposition::Result<()> {
+    fetch_default_key(key, conn)?;
+    let context_ids = get_key_usage_context_ids(key, conn)
         .map_err(|_| unexpected_error!("Something went wrong"))?;
-    if context_ids.is_empty() {
+    if !context_ids.is_empty() {
+        return Err(bad_argument!(
+            "Given key already in use in contexts: {}",
+            context_ids.join(",")
+        ));
+    }
+
+    diesel::update(dsl::default_configs)
+        .filter(dsl::key.eq(key))
+        .set((
+            dsl::last_modified_at.eq(Utc::now().naive_utc()),
+            dsl::last_modified_by.eq(user.get_email()),
+        ))
+        .execute(conn)?;
+
+    let deleted_row =
+        diesel::delete(dsl::default_configs.filter(dsl::key.eq(key))).execute(conn);
+    match deleted_row {
+        Ok(0) => Err(not_found!("default config key `{}` doesn't exists", key)),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            log::error!("default config delete query failed with error: {e}");
+            Err(unexpected_error!("Something went wrong."))
+        }
+    }
+}
+
+/// Aliases left behind by renamed keys that haven't expired, with their keys.
+pub fn get_default_config_aliases(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Vec<(String, String)>> {
+    Ok(default_config_aliases::table
+        .filter(default_config_aliases::expires_at.gt(Utc::now().naive_utc()))
+        .select((default_config_aliases::alias, default_config_aliases::key))
+        .load(conn)?)
+}
+
+/*
+  While a renamed key is being migrated, resolved configs also carry its value
+  under the old name, so that clients reading the old key keep working until
+  the alias expires.
+*/
+pub fn apply_default_config_aliases(
+    aliases: &[(String, String)],
+    resolved_config: &mut Map<String, Value>,
+) {
+    for (alias, key) in aliases {
+        if let Some(value) = resolved_config.get(key).cloned() {
+            resolved_config.entry(alias.clone()).or_insert(value);
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct ExperimentOverrides {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Array<Text>)]
+    override_keys: Vec<String>,
+    #[diesel(sql_type = JsonType)]
+    variants: Value,
+}
+
+fn rename_override_key(override_: &Value, old_key: &str, new_key: &str) -> Value {
+    match override_ {
+        Value::Object(override_map) if override_map.contains_key(old_key) => {
+            Value::Object(
+                override_map
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = if key == old_key {
+                            new_key
+                        } else {
+                            key.as_str()
+                        };
+                        (key.to_string(), value.clone())
+                    })
+                    .collect(),
+            )
+        }
+        _ => override_.clone(),
+    }
+}
+
+fn rename_key_in_variants(
+    variants: &Value,
+    old_key: &str,
+    new_key: &str,
+    renamed_overrides: &[RenamedOverride],
+) -> Value {
+    let mut variants = variants.clone();
+    if let Value::Array(variant_list) = &mut variants {
+        for variant in variant_list.iter_mut().filter_map(Value::as_object_mut) {
+            if let Some(overrides) = variant.get("overrides") {
+                let overrides = rename_override_key(overrides, old_key, new_key);
+                variant.insert("overrides".to_string(), overrides);
+            }
+            let new_override_id = variant
+                .get("context_id")
+                .and_then(Value::as_str)
+                .and_then(|ctx_id| {
+                    renamed_overrides
+                        .iter()
+                        .find(|renamed| renamed.context_id == ctx_id)
+                })
+                .map(|renamed| Value::String(renamed.new_override_id.clone()));
+            if let Some(new_override_id) = new_override_id {
+                variant.insert("override_id".to_string(), new_override_id);
+            }
+        }
+    }
+    variants
+}
+
+/*
+  Experiments that haven't concluded keep overriding the key through their
+  variants, which are pointed at the new override ids of their contexts.
+*/
+fn rename_key_in_experiments(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    exp_schema: &str,
+    old_key: &str,
+    new_key: &str,
+    renamed_overrides: &[RenamedOverride],
+    user: &User,
+) -> superposition::Result<Vec<i64>> {
+    let experiments = sql_query(format!(
+        r#"SELECT id, override_keys, variants FROM "{exp_schema}".experiments WHERE status IN ('CREATED', 'INPROGRESS') AND $1 = ANY(override_keys)"#
+    ))
+    .bind::<Text, _>(old_key)
+    .load::<ExperimentOverrides>(conn)?;
+
+    let mut updated_experiments = Vec::new();
+    for experiment in experiments {
+        let override_keys = experiment
+            .override_keys
+            .iter()
+            .map(|key| {
+                if key == old_key {
+                    new_key
+                } else {
+                    key.as_str()
+                }
+                .to_string()
+            })
+            .collect::<Vec<_>>();
+        let variants = rename_key_in_variants(
+            &experiment.variants,
+            old_key,
+            new_key,
+            renamed_overrides,
+        );
+        sql_query(format!(
+            r#"UPDATE "{exp_schema}".experiments SET override_keys = $1, variants = $2, last_modified = now(), last_modified_by = $3 WHERE id = $4"#
+        ))
+        .bind::<Array<Text>, _>(override_keys)
+        .bind::<JsonType, _>(variants)
+        .bind::<Text, _>(user.get_email())
+        .bind::<BigInt, _>(experiment.id)
+        .execute(conn)?;
+        updated_experiments.push(experiment.id);
+    }
+    Ok(updated_experiments)
+}
+
+#[put("/{key}/rename")]
+#[allow(clippy::too_many_arguments)]
+async fn rename(
+    state: Data<AppState>,
+    path: Path<DefaultConfigKey>,
+    request: web::Json<RenameReq>,
+    custom_headers: CustomHeaders,
+    tenant: Option<Tenant>,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    reject_unreviewed_change(&tenant_config, "Renaming a key")?;
+    let DbConnection(mut conn) = db_conn;
+    let tags = parse_config_tags(custom_headers.config_tags)?;
+    let old_key: String = path.into_inner().into();
+    let RenameReq {
+        new_key,
+        alias_expires_at,
+    } = request.into_inner();
+    let new_key: String = new_key.into();
+
+    if old_key == new_key {
+        return Err(bad_argument!("The new name of {} is the same", old_key));
+    }
+    if alias_expires_at.map_or(false, |expiry| expiry <= Utc::now()) {
+        return Err(bad_argument!("The alias should expire in the future"));
+    }
+    let exp_schema = experimentation_namespace(&state, tenant.as_ref())?;
+
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        let key_taken = dsl::default_configs
+            .filter(dsl::key.eq(&new_key))
+            .select(dsl::key)
+            .first::<String>(transaction_conn)
+            .optional()?
+            .is_some();
+        if key_taken {
+            return Err(bad_argument!("Key {} already exists", new_key));
+        }
+
+        // aliases of the key follow it through the foreign key
+        let default_config = diesel::update(dsl::default_configs)
+            .filter(dsl::key.eq(&old_key))
+            .set((
+                dsl::key.eq(&new_key),
+                dsl::last_modified_at.eq(Utc::now().naive_utc()),
+                dsl::last_modified_by.eq(user.get_email()),
+            ))
+            .get_result::<DefaultConfig>(transaction_conn)
+            .optional()?
+            .ok_or_else(|| not_found!("default config key `{}` doesn't exists", old_key))?;
+        diesel::delete(default_config_aliases::table)
+            .filter(default_config_aliases::alias.eq(&new_key))
+            .execute(transaction_conn)?;
+
+        let mut renamed_overrides = Vec::new();
+        for context in get_key_usage_contexts(&old_key, transaction_conn)? {
+            let override_ = rename_override_key(&context.override_, &old_key, &new_key);
+            let override_id = hash(&override_);
+            diesel::update(contexts::table)
+                .filter(contexts::id.eq(&context.id))
+                .set((
+                    contexts::override_.eq(&override_),
+                    contexts::override_id.eq(&override_id),
+                    contexts::last_modified_at.eq(Utc::now().naive_utc()),
+                    contexts::last_modified_by.eq(user.get_email()),
+                ))
+                .execute(transaction_conn)?;
+            renamed_overrides.push(RenamedOverride {
+                context_id: context.id,
+                old_override_id: context.override_id,
+                new_override_id: override_id,
+            });
+        }
+
+        let experiments = rename_key_in_experiments(
+            transaction_conn,
+            &exp_schema,
+            &old_key,
+            &new_key,
+            &renamed_overrides,
+            &user,
+        )?;
+
+        if let Some(expiry) = alias_expires_at {
+            diesel::insert_into(default_config_aliases::table)
+                .values(DefaultConfigAlias {
+                    alias: old_key.clone(),
+                    key: new_key.clone(),
+                    expires_at: expiry.naive_utc(),
+                    created_by: user.get_email(),
+                    created_at: Utc::now().naive_utc(),
+                })
+                .on_conflict(default_config_aliases::alias)
+                .do_update()
+                .set((
+                    default_config_aliases::key.eq(&new_key),
+                    default_config_aliases::expires_at.eq(expiry.naive_utc()),
+                ))
+                .execute(transaction_conn)?;
+        }
+
+        let version_id = add_config_version(&state, tags, transaction_conn)?;
+        log::info!(
+            "default config key {old_key} renamed to {new_key} by {}, {} overrides and {} experiments rewritten",
+            user.get_email(),
+            renamed_overrides.len(),
+            experiments.len()
+        );
+        Ok(HttpResponse::Ok()
+            .insert_header((
+                AppHeader::XConfigVersion.to_string(),
+                version_id.to_string(),
+            ))
+            .json(RenameResponse {
+                default_config,
+                overrides: renamed_overrides,
+                experiments,
+                alias_expires_at,
+            }))
+    })
+}
+
+/// Deprecated keys with their sunset dates and replacements, by key.
+pub fn get_deprecated_keys(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<HashMap<String, KeyDeprecation>> {
+    let deprecations: Vec<DefaultConfigDeprecation> =
+        default_config_deprecations::table.load(conn)?;
+    Ok(deprecations
+        .into_iter()
+        .map(|deprecation| {
+            (
+                deprecation.key,
+                KeyDeprecation {
+                    sunset_at: deprecation.sunset_at.and_utc(),
+                    replacement_key: deprecation.replacement_key,
+                },
+            )
+        })
+        .collect())
+}
+
+#[put("/{key}/deprecate")]
+async fn deprecate(
+    state: Data<AppState>,
+    path: Path<DefaultConfigKey>,
+    request: web::Json<DeprecateReq>,
+    custom_headers: CustomHeaders,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    reject_unreviewed_change(&tenant_config, "Deprecating a key")?;
+    let DbConnection(mut conn) = db_conn;
+    let tags = parse_config_tags(custom_headers.config_tags)?;
+    let key: String = path.into_inner().into();
+    let DeprecateReq {
+        sunset_at,
+        replacement_key,
+    } = request.into_inner();
+    let replacement_key: Option<String> = replacement_key.map(Into::into);
+
+    if sunset_at <= Utc::now() {
+        return Err(bad_argument!("The sunset date should be in the future"));
+    }
+    if replacement_key.as_ref() == Some(&key) {
+        return Err(bad_argument!("{} can't be replaced by itself", key));
+    }
+
+    fetch_default_key(&key, &mut conn)
+        .map_err(|_| not_found!("default config key `{}` doesn't exists", key))?;
+    if let Some(replacement) = &replacement_key {
+        fetch_default_key(replacement, &mut conn).map_err(|_| {
+            bad_argument!("Replacement key {} doesn't exist", replacement)
+        })?;
+    }
+
+    let deprecation = DefaultConfigDeprecation {
+        key: key.clone(),
+        sunset_at: sunset_at.naive_utc(),
+        replacement_key,
+        created_by: user.get_email(),
+        created_at: Utc::now().naive_utc(),
+    };
+    // a new config version lets clients pick up the deprecation on their next update
+    let version_id =
         conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
-            diesel::update(dsl::default_configs)
-                .filter(dsl::key.eq(&key))
+            diesel::insert_into(default_config_deprecations::table)
+                .values(&deprecation)
+                .on_conflict(default_config_deprecations::key)
+                .do_update()
                 .set((
-                    dsl::last_modified_at.eq(Utc::now().naive_utc()),
-                    dsl::last_modified_by.eq(user.get_email()),
+                    default_config_deprecations::sunset_at.eq(deprecation.sunset_at),
+                    default_config_deprecations::replacement_key
+                        .eq(&deprecation.replacement_key),
                 ))
                 .execute(transaction_conn)?;
+            add_config_version(&state, tags, transaction_conn)
+        })?;
+    log::info!(
+        "default config key {key} deprecated until {sunset_at} by {}",
+        user.get_email()
+    );
 
-            let deleted_row =
-                diesel::delete(dsl::default_configs.filter(dsl::key.eq(&key)))
-                    .execute(transaction_conn);
-            match deleted_row {
-                Ok(0) => Err(not_found!("default config key `{}` doesn't exists", key)),
-                Ok(_) => {
-                    let version_id = add_config_version(&state, tags, transaction_conn)?;
-                    log::info!(
-                        "default config key: {key} deleted by {}",
-                        user.get_email()
-                    );
-                    Ok(HttpResponse::NoContent()
-                        .insert_header((
-                            AppHeader::XConfigVersion.to_string(),
-                            version_id.to_string(),
-                        ))
-                        .finish())
-                }
-                Err(e) => {
-                    log::error!("default config delete query failed with error: {e}");
-                    Err(unexpected_error!("Something went wrong."))
-                }
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            AppHeader::XConfigVersion.to_string(),
+            version_id.to_string(),
+        ))
+        .json(deprecation))
+}
+
+#[delete("/{key}/deprecate")]
+async fn undeprecate(
+    state: Data<AppState>,
+    path: Path<DefaultConfigKey>,
+    custom_headers: CustomHeaders,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    reject_unreviewed_change(&tenant_config, "Undeprecating a key")?;
+    let DbConnection(mut conn) = db_conn;
+    let tags = parse_config_tags(custom_headers.config_tags)?;
+    let key: String = path.into_inner().into();
+
+    let version_id =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let deleted = diesel::delete(default_config_deprecations::table)
+                .filter(default_config_deprecations::key.eq(&key))
+                .execute(transaction_conn)?;
+            if deleted == 0 {
+                return Err(not_found!("default config key `{}` isn't deprecated", key));
             }
-        })
-    } else {
-        Err(bad_argument!(
-            "Given key already in use in contexts: {}",
-            context_ids.join(",")
+            add_config_version(&state, tags, transaction_conn)
+        })?;
+    log::info!(
+        "deprecation of default config key {key} withdrawn by {}",
+        user.get_email()
+    );
+
+    Ok(HttpResponse::NoContent()
+        .insert_header((
+            AppHeader::XConfigVersion.to_string(),
+            version_id.to_string(),
         ))
+        .finish())
+}
+
+#[derive(QueryableByName)]
+struct ExperimentId {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+}
+
+fn get_key_usage_experiment_ids(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    exp_schema: &str,
+    key: &str,
+) -> superposition::Result<Vec<i64>> {
+    let experiments = sql_query(format!(
+        r#"SELECT id FROM "{exp_schema}".experiments WHERE status IN ('CREATED', 'INPROGRESS') AND $1 = ANY(override_keys) ORDER BY id"#
+    ))
+    .bind::<Text, _>(key)
+    .load::<ExperimentId>(conn)?;
+    Ok(experiments
+        .into_iter()
+        .map(|experiment| experiment.id)
+        .collect())
+}
+
+/*
+  Lists every deprecated key with the contexts overriding it and the experiments
+  still running with it, which have to move off the key before its sunset date.
+*/
+#[get("/deprecations")]
+async fn get_deprecation_report(
+    state: Data<AppState>,
+    tenant: Option<Tenant>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<Vec<DeprecatedKeyUsage>>> {
+    let DbConnection(mut conn) = db_conn;
+    let exp_schema = experimentation_namespace(&state, tenant.as_ref())?;
+
+    let mut deprecated_keys = get_deprecated_keys(&mut conn)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    deprecated_keys.sort_by_key(|(_, deprecation)| deprecation.sunset_at);
+
+    let mut report = Vec::new();
+    for (key, deprecation) in deprecated_keys {
+        report.push(DeprecatedKeyUsage {
+            contexts: get_key_usage_context_ids(&key, &mut conn)?,
+            experiments: get_key_usage_experiment_ids(&mut conn, &exp_schema, &key)?,
+            key,
+            deprecation,
+        });
+    }
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn aliases() -> Vec<(String, String)> {
+        vec![("timeout".to_string(), "request_timeout".to_string())]
+    }
+
+    #[test]
+    fn test_rename_override_key() {
+        let override_ = json!({"timeout": 30, "retries": 3});
+        assert_eq!(
+            rename_override_key(&override_, "timeout", "request_timeout"),
+            json!({"request_timeout": 30, "retries": 3})
+        );
+        assert_eq!(
+            rename_override_key(&override_, "max_connections", "connections"),
+            override_
+        );
+        assert_eq!(
+            rename_override_key(&json!("timeout"), "timeout", "request_timeout"),
+            json!("timeout")
+        );
+    }
+
+    #[test]
+    fn test_rename_key_in_variants() {
+        let variants = json!([
+            {
+                "id": "exp-control",
+                "context_id": "ctx-1",
+                "override_id": "ovr-1",
+                "overrides": {"timeout": 30}
+            },
+            {
+                "id": "exp-test",
+                "context_id": "ctx-2",
+                "override_id": "ovr-2",
+                "overrides": {"retries": 5}
+            }
+        ]);
+        let renamed_overrides = vec![RenamedOverride {
+            context_id: "ctx-1".to_string(),
+            old_override_id: "ovr-1".to_string(),
+            new_override_id: "ovr-3".to_string(),
+        }];
+        assert_eq!(
+            rename_key_in_variants(
+                &variants,
+                "timeout",
+                "request_timeout",
+                &renamed_overrides
+            ),
+            json!([
+                {
+                    "id": "exp-control",
+                    "context_id": "ctx-1",
+                    "override_id": "ovr-3",
+                    "overrides": {"request_timeout": 30}
+                },
+                {
+                    "id": "exp-test",
+                    "context_id": "ctx-2",
+                    "override_id": "ovr-2",
+                    "overrides": {"retries": 5}
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_apply_default_config_aliases() {
+        let mut resolved_config = Map::from_iter([
+            ("request_timeout".to_string(), json!(30)),
+            ("retries".to_string(), json!(3)),
+        ]);
+        apply_default_config_aliases(&aliases(), &mut resolved_config);
+        assert_eq!(
+            Value::Object(resolved_config),
+            json!({"request_timeout": 30, "timeout": 30, "retries": 3})
+        );
+
+        // a filtered config without the new key doesn't get the alias either
+        let mut resolved_config = Map::from_iter([("retries".to_string(), json!(3))]);
+        apply_default_config_aliases(&aliases(), &mut resolved_config);
+        assert_eq!(Value::Object(resolved_config), json!({"retries": 3}));
+    }
+
+    #[test]
+    fn test_apply_default_config_aliases_keeps_existing_values() {
+        // a key holding the old name wins over the alias copied from the new key
+        let mut resolved_config = Map::from_iter([
+            ("request_timeout".to_string(), json!(30)),
+            ("timeout".to_string(), json!(10)),
+        ]);
+        apply_default_config_aliases(&aliases(), &mut resolved_config);
+        assert_eq!(
+            Value::Object(resolved_config),
+            json!({"request_timeout": 30, "timeout": 10})
+        );
     }
 }
@@ -1,17 +1,71 @@
+use chrono::{DateTime, Utc};
 use derive_more::{AsRef, Deref, DerefMut, Into};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{Map, Value};
 use superposition_types::RegexEnum;
 
-#[derive(Debug, Deserialize)]
+use crate::db::models::DefaultConfig;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CreateReq {
-    #[serde(default, deserialize_with = "deserialize_option")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<Map<String, Value>>,
-    #[serde(default, deserialize_with = "deserialize_option")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_option",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub function_name: Option<Value>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RenameReq {
+    pub new_key: DefaultConfigKey,
+    pub alias_expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct RenamedOverride {
+    pub context_id: String,
+    pub old_override_id: String,
+    pub new_override_id: String,
+}
+
+#[derive(Serialize)]
+pub struct RenameResponse {
+    pub default_config: DefaultConfig,
+    pub overrides: Vec<RenamedOverride>,
+    pub experiments: Vec<i64>,
+    pub alias_expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeprecateReq {
+    pub sunset_at: DateTime<Utc>,
+    pub replacement_key: Option<DefaultConfigKey>,
+}
+
+#[derive(Serialize)]
+pub struct KeyDeprecation {
+    pub sunset_at: DateTime<Utc>,
+    pub replacement_key: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeprecatedKeyUsage {
+    pub key: String,
+    #[serde(flatten)]
+    pub deprecation: KeyDeprecation,
+    pub contexts: Vec<String>,
+    pub experiments: Vec<i64>,
+}
+
 #[derive(Debug, Deserialize, AsRef, Deref, DerefMut, Into)]
 #[serde(try_from = "String")]
 pub struct DefaultConfigKey(String);
@@ -38,3 +92,11 @@ where
     let value: Value = Deserialize::deserialize(deserializer)?;
     Ok(Some(value))
 }
+
+/// A key as saved, along with its schema after resolving type references.
+#[derive(Serialize)]
+pub struct DefaultConfigWithResolvedSchema {
+    #[serde(flatten)]
+    pub default_config: DefaultConfig,
+    pub resolved_schema: Value,
+}
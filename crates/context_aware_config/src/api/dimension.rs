@@ -2,4 +2,7 @@ mod handlers;
 mod types;
 mod utils;
 pub use handlers::endpoints;
-pub use utils::get_all_dimension_schema_map;
+pub use utils::{
+    apply_condition_aliases, apply_dimension_aliases, compile_dimension_schema,
+    get_all_dimension_schema_map, get_dimension_aliases, validate_dimension_schema,
+};
@@ -5,36 +5,70 @@ use actix_web::{
 };
 use chrono::Utc;
 use diesel::{
-    delete, Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper,
+    delete,
+    r2d2::{ConnectionManager, PooledConnection},
+    sql_query,
+    sql_types::{BigInt, Json, Text},
+    Connection, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
+    QueryableByName, RunQueryDsl, SelectableHelper,
 };
-use jsonschema::{Draft, JSONSchema};
 use serde_json::Value;
-use service_utils::service::types::{AppState, DbConnection};
+use service_utils::{
+    helpers::parse_config_tags,
+    service::types::{AppHeader, AppState, CustomHeaders, DbConnection, Tenant},
+};
 use superposition_macros::{bad_argument, not_found, unexpected_error};
 use superposition_types::{result as superposition, TenantConfig, User};
 
 use crate::{
-    api::dimension::{types::CreateReq, utils::get_dimension_usage_context_ids},
+    api::{
+        change_requests::reject_unreviewed_change,
+        context::{
+            hash,
+            helpers::{
+                find_dimension_violations, validate_value_with_function, violations_error,
+            },
+            ContextViolation, RevalidationQuery,
+        },
+        dimension::{
+            types::{CreateReq, RenameReq, RenameResponse, RenamedContext},
+            utils::{
+                compile_dimension_schema, get_dimension_usage_context_ids,
+                rename_dimension_in_condition, rename_variant_contexts,
+                validate_dimension_schema,
+            },
+        },
+        functions::helpers::get_published_function_code,
+        type_templates::helpers::{
+            get_type_templates_map, resolve_schema, resolve_type_refs,
+        },
+    },
     db::{
-        models::Dimension,
-        schema::{dimensions, dimensions::dsl::*},
+        models::{Context, Dimension, DimensionAlias},
+        schema::{contexts, dimension_aliases, dimensions, dimensions::dsl::*},
+    },
+    helpers::{
+        add_config_version, experimentation_namespace, validate_context_jsonschema,
     },
-    helpers::validate_jsonschema,
 };
 
-use super::types::{DeleteReq, DimensionWithMandatory};
+use super::types::{
+    DeleteReq, DimensionName, DimensionWithMandatory, DimensionWithResolvedSchema,
+};
 
 pub fn endpoints() -> Scope {
     Scope::new("")
         .service(create)
         .service(get)
         .service(delete_dimension)
+        .service(rename_dimension)
 }
 
 #[put("")]
 async fn create(
     state: Data<AppState>,
     req: web::Json<CreateReq>,
+    query: web::Query<RevalidationQuery>,
     user: User,
     db_conn: DbConnection,
     tenant_config: TenantConfig,
@@ -44,18 +78,10 @@ async fn create(
     let create_req = req.into_inner();
     let schema_value = create_req.schema;
 
-    validate_jsonschema(&state.meta_schema, &schema_value)?;
-
-    let schema_compile_result = JSONSchema::options()
-        .with_draft(Draft::Draft7)
-        .compile(&schema_value);
-
-    if let Err(e) = schema_compile_result {
-        return Err(bad_argument!(
-            "Invalid JSON schema (failed to compile): {:?}",
-            e
-        ));
-    };
+    validate_dimension_schema(
+        &state.meta_schema,
+        &resolve_schema(&mut conn, &schema_value)?,
+    )?;
 
     let fun_name = match create_req.function_name {
         Some(Value::String(func_name)) => Some(func_name),
@@ -79,40 +105,119 @@ async fn create(
         last_modified_by: user.get_email(),
     };
 
-    let upsert = diesel::insert_into(dimensions)
-        .values(&new_dimension)
-        .on_conflict(dimension)
-        .do_update()
-        .set(&new_dimension)
-        .get_result::<Dimension>(&mut conn);
-
-    match upsert {
-        Ok(upserted_dimension) => {
-            let is_mandatory = tenant_config
-                .mandatory_dimensions
-                .contains(&upserted_dimension.dimension);
-            Ok(HttpResponse::Created().json(DimensionWithMandatory::new(
-                upserted_dimension,
-                is_mandatory,
-            )))
+    // the checks against existing conditions and the upsert see the same contexts
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        let previous_rule = dimensions
+            .filter(dimension.eq(&new_dimension.dimension))
+            .select((schema, function_name))
+            .for_update()
+            .first::<(Value, Option<String>)>(transaction_conn)
+            .optional()?;
+        let rule_changed = previous_rule.map_or(false, |(prev_schema, prev_function)| {
+            prev_schema != new_dimension.schema
+                || prev_function != new_dimension.function_name
+        });
+        if rule_changed {
+            let violations = validate_dimension_usages(&new_dimension, transaction_conn)?;
+            if !violations.is_empty() {
+                if !query.force {
+                    return Err(violations_error(
+                        format!(
+                            "Updating the schema or function of {}",
+                            new_dimension.dimension
+                        ),
+                        &violations,
+                    ));
+                }
+                log::warn!(
+                    "{} updated by {} with {} conditions violating it",
+                    new_dimension.dimension,
+                    user.get_email(),
+                    violations.len()
+                );
+            }
         }
-        Err(diesel::result::Error::DatabaseError(
-            diesel::result::DatabaseErrorKind::ForeignKeyViolation,
-            e,
-        )) => {
-            log::error!("{fun_name:?} function not found with error: {e:?}");
-            Err(bad_argument!(
-                "Function {} doesn't exists",
-                fun_name.unwrap_or(String::new())
-            ))
+
+        // a dimension taking the name of an alias replaces it
+        diesel::delete(dimension_aliases::table)
+            .filter(dimension_aliases::alias.eq(&new_dimension.dimension))
+            .execute(transaction_conn)?;
+
+        let upsert = diesel::insert_into(dimensions)
+            .values(&new_dimension)
+            .on_conflict(dimension)
+            .do_update()
+            .set(&new_dimension)
+            .get_result::<Dimension>(transaction_conn);
+
+        match upsert {
+            Ok(upserted_dimension) => {
+                let is_mandatory = tenant_config
+                    .mandatory_dimensions
+                    .contains(&upserted_dimension.dimension);
+                Ok(HttpResponse::Created().json(DimensionWithMandatory::new(
+                    upserted_dimension,
+                    is_mandatory,
+                )))
+            }
+            Err(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::ForeignKeyViolation,
+                e,
+            )) => {
+                log::error!("{fun_name:?} function not found with error: {e:?}");
+                Err(bad_argument!(
+                    "Function {} doesn't exists",
+                    fun_name.unwrap_or(String::new())
+                ))
+            }
+            Err(e) => {
+                log::error!("Dimension upsert failed with error: {e}");
+                Err(unexpected_error!(
+                    "Something went wrong, failed to create/update dimension"
+                ))
+            }
         }
-        Err(e) => {
-            log::error!("Dimension upsert failed with error: {e}");
-            Err(unexpected_error!(
-                "Something went wrong, failed to create/update dimension"
-            ))
+    })
+}
+
+// values compared against the dimension checked against its new schema and function
+fn validate_dimension_usages(
+    new_dimension: &Dimension,
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Vec<ContextViolation>> {
+    let dimension_schema = resolve_schema(conn, &new_dimension.schema)?;
+    let jschema = compile_dimension_schema(&dimension_schema).map_err(|e| {
+        log::info!("Failed to compile as a Draft-7 JSON schema: {e}");
+        bad_argument!("Invalid JSON schema (failed to compile)")
+    })?;
+    let function = match &new_dimension.function_name {
+        Some(f_name) => get_published_function_code(conn, f_name.to_string())
+            .map_err(|_| bad_argument!("Function {} doesn't exists", f_name))?
+            .map(|f_code| (f_name, f_code)),
+        None => None,
+    };
+
+    let all_contexts: Vec<Context> = contexts::table.load(conn)?;
+    find_dimension_violations(&all_contexts, &new_dimension.dimension, |constraint| {
+        validate_context_jsonschema(
+            &constraint.operator.to_string(),
+            &constraint.value,
+            &jschema,
+        )?;
+        match &function {
+            Some((f_name, f_code)) => {
+                constraint.values().into_iter().try_for_each(|value| {
+                    validate_value_with_function(
+                        f_name,
+                        f_code,
+                        &new_dimension.dimension,
+                        value,
+                    )
+                })
+            }
+            None => Ok(()),
         }
-    }
+    })
 }
 
 #[get("")]
@@ -123,15 +228,21 @@ async fn get(
     let DbConnection(mut conn) = db_conn;
 
     let result: Vec<Dimension> = dimensions.get_results(&mut conn)?;
+    let templates = get_type_templates_map(&mut conn)?;
 
-    let dimensions_with_mandatory: Vec<DimensionWithMandatory> = result
+    // clients read the type of a dimension from the resolved schema, edits send back the saved one
+    let dimensions_with_mandatory: Vec<DimensionWithResolvedSchema> = result
         .into_iter()
         .map(|ele| {
+            let resolved_schema = resolve_type_refs(&ele.schema, &templates)?;
             let is_mandatory =
                 tenant_config.mandatory_dimensions.contains(&ele.dimension);
-            DimensionWithMandatory::new(ele, is_mandatory)
+            Ok(DimensionWithResolvedSchema {
+                dimension: DimensionWithMandatory::new(ele, is_mandatory),
+                resolved_schema,
+            })
         })
-        .collect();
+        .collect::<superposition::Result<_>>()?;
 
     Ok(HttpResponse::Ok().json(dimensions_with_mandatory))
 }
@@ -178,3 +289,188 @@ async fn delete_dimension(
         ))
     }
 }
+
+#[derive(QueryableByName)]
+struct ExperimentContext {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Json)]
+    context: Value,
+    #[diesel(sql_type = Json)]
+    variants: Value,
+}
+
+/*
+  Experiment contexts are rewritten like the contexts of the config, and their
+  variants are pointed at the new ids of the contexts created for them.
+*/
+fn rename_dimension_in_experiments(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    exp_schema: &str,
+    old_name: &str,
+    new_name: &str,
+    renamed_contexts: &[RenamedContext],
+    user: &User,
+) -> superposition::Result<Vec<i64>> {
+    let experiments = sql_query(format!(
+        r#"SELECT id, context, variants FROM "{exp_schema}".experiments"#
+    ))
+    .load::<ExperimentContext>(conn)?;
+
+    let mut updated_experiments = Vec::new();
+    for experiment in experiments {
+        let exp_context =
+            rename_dimension_in_condition(&experiment.context, old_name, new_name);
+        let variants = rename_variant_contexts(&experiment.variants, renamed_contexts);
+        if exp_context == experiment.context && variants == experiment.variants {
+            continue;
+        }
+        sql_query(format!(
+            r#"UPDATE "{exp_schema}".experiments SET context = $1, variants = $2, last_modified = now(), last_modified_by = $3 WHERE id = $4"#
+        ))
+        .bind::<Json, _>(exp_context)
+        .bind::<Json, _>(variants)
+        .bind::<Text, _>(user.get_email())
+        .bind::<BigInt, _>(experiment.id)
+        .execute(conn)?;
+        updated_experiments.push(experiment.id);
+    }
+    Ok(updated_experiments)
+}
+
+#[put("/{name}/rename")]
+#[allow(clippy::too_many_arguments)]
+async fn rename_dimension(
+    state: Data<AppState>,
+    path: Path<DimensionName>,
+    req: web::Json<RenameReq>,
+    custom_headers: CustomHeaders,
+    tenant: Option<Tenant>,
+    user: User,
+    db_conn: DbConnection,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    reject_unreviewed_change(&tenant_config, "Renaming a dimension")?;
+    let DbConnection(mut conn) = db_conn;
+    let tags = parse_config_tags(custom_headers.config_tags)?;
+    let old_name: String = path.into_inner().into();
+    let RenameReq {
+        new_name,
+        alias_expires_at,
+    } = req.into_inner();
+    let new_name: String = new_name.into();
+
+    if old_name == "variantIds" || new_name == "variantIds" {
+        return Err(bad_argument!("variantIds cannot be renamed"));
+    }
+    if old_name == new_name {
+        return Err(bad_argument!("The new name of {} is the same", old_name));
+    }
+    if tenant_config.mandatory_dimensions.contains(&old_name) {
+        return Err(bad_argument!(
+            "{} is a mandatory dimension and cannot be renamed",
+            old_name
+        ));
+    }
+    if alias_expires_at.map_or(false, |expiry| expiry <= Utc::now()) {
+        return Err(bad_argument!("The alias should expire in the future"));
+    }
+    let exp_schema = experimentation_namespace(&state, tenant.as_ref())?;
+
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        let name_taken = dimensions
+            .filter(dimension.eq(&new_name))
+            .select(dimension)
+            .first::<String>(transaction_conn)
+            .optional()?
+            .is_some();
+        if name_taken {
+            return Err(bad_argument!("Dimension {} already exists", new_name));
+        }
+
+        // aliases of the dimension follow it through the foreign key
+        let renamed_dimension = diesel::update(dimensions)
+            .filter(dimension.eq(&old_name))
+            .set((
+                dimension.eq(&new_name),
+                last_modified_at.eq(Utc::now().naive_utc()),
+                last_modified_by.eq(user.get_email()),
+            ))
+            .get_result::<Dimension>(transaction_conn)
+            .optional()?
+            .ok_or_else(|| not_found!("Dimension `{}` doesn't exists", old_name))?;
+        diesel::delete(dimension_aliases::table)
+            .filter(dimension_aliases::alias.eq(&new_name))
+            .execute(transaction_conn)?;
+
+        let all_contexts: Vec<Context> = contexts::table.load(transaction_conn)?;
+        let mut renamed_contexts = Vec::new();
+        for context in all_contexts {
+            let condition =
+                rename_dimension_in_condition(&context.value, &old_name, &new_name);
+            if condition == context.value {
+                continue;
+            }
+            let new_id = hash(&condition);
+            diesel::update(contexts::table)
+                .filter(contexts::id.eq(&context.id))
+                .set((
+                    contexts::id.eq(&new_id),
+                    contexts::value.eq(&condition),
+                    contexts::last_modified_at.eq(Utc::now().naive_utc()),
+                    contexts::last_modified_by.eq(user.get_email()),
+                ))
+                .execute(transaction_conn)?;
+            renamed_contexts.push(RenamedContext {
+                old_id: context.id,
+                new_id,
+            });
+        }
+
+        let experiments = rename_dimension_in_experiments(
+            transaction_conn,
+            &exp_schema,
+            &old_name,
+            &new_name,
+            &renamed_contexts,
+            &user,
+        )?;
+
+        if let Some(expiry) = alias_expires_at {
+            diesel::insert_into(dimension_aliases::table)
+                .values(DimensionAlias {
+                    alias: old_name.clone(),
+                    dimension: new_name.clone(),
+                    expires_at: expiry.naive_utc(),
+                    created_by: user.get_email(),
+                    created_at: Utc::now().naive_utc(),
+                })
+                .on_conflict(dimension_aliases::alias)
+                .do_update()
+                .set((
+                    dimension_aliases::dimension.eq(&new_name),
+                    dimension_aliases::expires_at.eq(expiry.naive_utc()),
+                ))
+                .execute(transaction_conn)?;
+        }
+
+        let version_id = add_config_version(&state, tags, transaction_conn)?;
+        log::info!(
+            "dimension {old_name} renamed to {new_name} by {}, {} contexts and {} experiments rewritten",
+            user.get_email(),
+            renamed_contexts.len(),
+            experiments.len()
+        );
+        Ok(HttpResponse::Ok()
+            .insert_header((
+                AppHeader::XConfigVersion.to_string(),
+                version_id.to_string(),
+            ))
+            .json(RenameResponse {
+                dimension: DimensionWithMandatory::new(renamed_dimension, false),
+                contexts: renamed_contexts,
+                experiments,
+                alias_expires_at,
+            }))
+    })
+}
@@ -15,6 +15,26 @@ pub struct CreateReq {
     pub function_name: Option<Value>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RenameReq {
+    pub new_name: DimensionName,
+    pub alias_expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct RenamedContext {
+    pub old_id: String,
+    pub new_id: String,
+}
+
+#[derive(Serialize)]
+pub struct RenameResponse {
+    pub dimension: DimensionWithMandatory,
+    pub contexts: Vec<RenamedContext>,
+    pub experiments: Vec<i64>,
+    pub alias_expires_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Deserialize, AsRef, Deref, DerefMut, Into)]
 #[serde(try_from = "i32")]
 pub struct Priority(i32);
@@ -91,6 +111,14 @@ impl DimensionWithMandatory {
     }
 }
 
+/// A dimension as saved, along with its schema after resolving type references.
+#[derive(Serialize)]
+pub struct DimensionWithResolvedSchema {
+    #[serde(flatten)]
+    pub dimension: DimensionWithMandatory,
+    pub resolved_schema: Value,
+}
+
 #[derive(Debug, Deserialize, AsRef, Deref, DerefMut, Into)]
 #[serde(try_from = "String")]
 pub struct DeleteReq(String);
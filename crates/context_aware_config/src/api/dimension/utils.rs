@@ -1,36 +1,91 @@
-use crate::db::{
-    models::{Context, Dimension},
-    schema::{contexts::dsl::contexts, dimensions::dsl::*},
+use crate::{
+    api::{
+        dimension::types::RenamedContext,
+        type_templates::helpers::{get_type_templates_map, resolve_type_refs},
+    },
+    db::{
+        models::{Context, Dimension},
+        schema::{contexts::dsl::contexts, dimension_aliases, dimensions::dsl::*},
+    },
+    helpers::validate_jsonschema,
 };
+use chrono::Utc;
 use diesel::{
     r2d2::{ConnectionManager, PooledConnection},
-    PgConnection, RunQueryDsl,
+    ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
 };
 use jsonschema::{Draft, JSONSchema};
-use serde_json::Map;
+use serde_json::{Map, Value};
 use service_utils::helpers::extract_dimensions;
 use std::collections::HashMap;
-use superposition_macros::{db_error, unexpected_error};
-use superposition_types::{result as superposition, Cac, Condition};
+use superposition_macros::{bad_argument, db_error, unexpected_error};
+use superposition_types::{
+    result as superposition, semantic_version::parse_version, Cac, Condition,
+};
+
+const SEMVER_FORMAT: &str = "semver";
+
+/*
+  Dimensions holding versions are declared with `"format": "semver"` in their
+  schema. Their values have to be semantic versions and they are compared by
+  version precedence, other string dimensions are compared as plain strings.
+*/
+pub fn is_version_dimension(dimension_schema: &Value) -> bool {
+    dimension_schema.get("format").and_then(Value::as_str) == Some(SEMVER_FORMAT)
+}
+
+pub fn compile_dimension_schema(dimension_schema: &Value) -> Result<JSONSchema, String> {
+    JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .with_format(SEMVER_FORMAT, |value| parse_version(value).is_some())
+        .compile(dimension_schema)
+        .map_err(|err| err.to_string())
+}
 
+pub fn validate_dimension_schema(
+    meta_schema: &JSONSchema,
+    schema_value: &Value,
+) -> superposition::Result<()> {
+    validate_jsonschema(meta_schema, schema_value)?;
+
+    let schema_compile_result = compile_dimension_schema(schema_value);
+
+    if let Err(e) = schema_compile_result {
+        return Err(bad_argument!(
+            "Invalid JSON schema (failed to compile): {}",
+            e
+        ));
+    };
+    Ok(())
+}
+
+/// Compiled schema, priority and whether it is a version dimension, by dimension.
 pub fn get_all_dimension_schema_map(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-) -> superposition::Result<HashMap<String, (JSONSchema, i32)>> {
+) -> superposition::Result<HashMap<String, (JSONSchema, i32, bool)>> {
     let dimensions_vec = dimensions.load::<Dimension>(conn)?;
+    let templates = get_type_templates_map(conn)?;
 
-    let dimension_schema_map = dimensions_vec
+    dimensions_vec
         .into_iter()
-        .filter_map(|item| {
-            let compiled_schema = JSONSchema::options()
-                .with_draft(Draft::Draft7)
-                .compile(&item.schema)
-                .ok()?;
+        .map(|item| {
+            let resolved_schema = resolve_type_refs(&item.schema, &templates)?;
+            let compiled_schema =
+                compile_dimension_schema(&resolved_schema).map_err(|err| {
+                    log::error!(
+                        "failed to compile the schema of dimension {}: {err}",
+                        item.dimension
+                    );
+                    unexpected_error!(
+                        "Schema of dimension {} failed to compile",
+                        item.dimension
+                    )
+                })?;
+            let is_version = is_version_dimension(&resolved_schema);
 
-            Some((item.dimension, (compiled_schema, item.priority)))
+            Ok((item.dimension, (compiled_schema, item.priority, is_version)))
         })
-        .collect();
-
-    Ok(dimension_schema_map)
+        .collect()
 }
 
 pub fn get_dimension_usage_context_ids(
@@ -59,3 +114,228 @@ pub fn get_dimension_usage_context_ids(
     }
     Ok(context_ids)
 }
+
+/// Rewrites every `var` reference to `from` in a jsonlogic condition.
+pub fn rename_dimension_in_condition(condition: &Value, from: &str, to: &str) -> Value {
+    match condition {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(operator, operands)| {
+                    let operands = match (operator.as_str(), operands) {
+                        ("var", Value::String(name)) if name == from => {
+                            Value::String(to.to_string())
+                        }
+                        ("var", Value::Array(args))
+                            if args.first().and_then(Value::as_str) == Some(from) =>
+                        {
+                            let mut args = args.clone();
+                            args[0] = Value::String(to.to_string());
+                            Value::Array(args)
+                        }
+                        (_, operands) => {
+                            rename_dimension_in_condition(operands, from, to)
+                        }
+                    };
+                    (operator.clone(), operands)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| rename_dimension_in_condition(value, from, to))
+                .collect(),
+        ),
+        _ => condition.clone(),
+    }
+}
+
+/// Points the variants of an experiment at the new ids of its renamed contexts.
+pub fn rename_variant_contexts(
+    variants: &Value,
+    renamed_contexts: &[RenamedContext],
+) -> Value {
+    let mut variants = variants.clone();
+    if let Value::Array(variant_list) = &mut variants {
+        for variant in variant_list.iter_mut() {
+            let new_id = variant
+                .get("context_id")
+                .and_then(Value::as_str)
+                .and_then(|ctx_id| {
+                    renamed_contexts.iter().find(|ctx| ctx.old_id == ctx_id)
+                })
+                .map(|ctx| Value::String(ctx.new_id.clone()));
+            if let (Some(new_id), Value::Object(variant)) = (new_id, variant) {
+                variant.insert("context_id".to_string(), new_id);
+            }
+        }
+    }
+    variants
+}
+
+/// Aliases left behind by renamed dimensions that haven't expired, by alias.
+pub fn get_dimension_aliases(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<HashMap<String, String>> {
+    let aliases: Vec<(String, String)> = dimension_aliases::table
+        .filter(dimension_aliases::expires_at.gt(Utc::now().naive_utc()))
+        .select((dimension_aliases::alias, dimension_aliases::dimension))
+        .load(conn)?;
+    Ok(aliases.into_iter().collect())
+}
+
+/*
+  Clients still sending the old name of a renamed dimension keep working until
+  the alias expires, the old name is read as the new one unless both are sent.
+*/
+pub fn apply_dimension_aliases(
+    aliases: &HashMap<String, String>,
+    query_data: &mut Map<String, Value>,
+) {
+    for (alias, dimension_name) in aliases {
+        if query_data.contains_key(dimension_name) {
+            continue;
+        }
+        if let Some(value) = query_data.remove(alias) {
+            query_data.insert(dimension_name.clone(), value);
+        }
+    }
+}
+
+pub fn apply_condition_aliases(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    condition: Cac<Condition>,
+) -> superposition::Result<Cac<Condition>> {
+    let aliases = get_dimension_aliases(conn)?;
+    if aliases.is_empty() {
+        return Ok(condition);
+    }
+    let condition_value = Value::Object(condition.clone().into_inner().into());
+    let aliased_value =
+        aliases
+            .iter()
+            .fold(condition_value.clone(), |value, (alias, dimension_name)| {
+                rename_dimension_in_condition(&value, alias, dimension_name)
+            });
+    if aliased_value == condition_value {
+        return Ok(condition);
+    }
+    match aliased_value {
+        Value::Object(map) => {
+            Cac::<Condition>::try_from(map).map_err(|err| bad_argument!(err))
+        }
+        _ => Ok(condition),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_rename_dimension_in_condition() {
+        let condition = json!({
+            "and": [
+                {"==": [{"var": "os"}, "android"]},
+                {"in": [{"var": ["city", "Delhi"]}, ["Delhi", "Chennai"]]}
+            ]
+        });
+        assert_eq!(
+            rename_dimension_in_condition(&condition, "city", "region"),
+            json!({
+                "and": [
+                    {"==": [{"var": "os"}, "android"]},
+                    {"in": [{"var": ["region", "Delhi"]}, ["Delhi", "Chennai"]]}
+                ]
+            })
+        );
+        assert_eq!(
+            rename_dimension_in_condition(&condition, "os", "platform"),
+            json!({
+                "and": [
+                    {"==": [{"var": "platform"}, "android"]},
+                    {"in": [{"var": ["city", "Delhi"]}, ["Delhi", "Chennai"]]}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_rename_dimension_in_nested_condition() {
+        let condition = json!({
+            "or": [
+                {"and": [
+                    {"==": [{"var": "os"}, "os"]},
+                    {"!": {"==": [{"var": "os"}, "ios"]}}
+                ]},
+                {"<=": [1, {"var": "build"}, 10]}
+            ]
+        });
+        assert_eq!(
+            rename_dimension_in_condition(&condition, "os", "platform"),
+            json!({
+                "or": [
+                    {"and": [
+                        {"==": [{"var": "platform"}, "os"]},
+                        {"!": {"==": [{"var": "platform"}, "ios"]}}
+                    ]},
+                    {"<=": [1, {"var": "build"}, 10]}
+                ]
+            })
+        );
+        assert_eq!(
+            rename_dimension_in_condition(&condition, "city", "region"),
+            condition
+        );
+    }
+
+    #[test]
+    fn test_rename_variant_contexts() {
+        let variants = json!([
+            {"id": "exp-control", "context_id": "ctx-1", "overrides": {"key": 1}},
+            {"id": "exp-test", "context_id": "ctx-2", "overrides": {"key": 2}},
+            {"id": "exp-draft", "overrides": {"key": 3}}
+        ]);
+        let renamed_contexts = vec![RenamedContext {
+            old_id: "ctx-2".to_string(),
+            new_id: "ctx-3".to_string(),
+        }];
+        assert_eq!(
+            rename_variant_contexts(&variants, &renamed_contexts),
+            json!([
+                {"id": "exp-control", "context_id": "ctx-1", "overrides": {"key": 1}},
+                {"id": "exp-test", "context_id": "ctx-3", "overrides": {"key": 2}},
+                {"id": "exp-draft", "overrides": {"key": 3}}
+            ])
+        );
+        assert_eq!(rename_variant_contexts(&variants, &[]), variants);
+    }
+
+    #[test]
+    fn test_apply_dimension_aliases() {
+        let aliases = HashMap::from([("city".to_string(), "region".to_string())]);
+
+        let mut query_data = Map::from_iter([
+            ("city".to_string(), json!("Delhi")),
+            ("os".to_string(), json!("android")),
+        ]);
+        apply_dimension_aliases(&aliases, &mut query_data);
+        assert_eq!(
+            Value::Object(query_data),
+            json!({"region": "Delhi", "os": "android"})
+        );
+
+        let mut query_data = Map::from_iter([
+            ("city".to_string(), json!("Delhi")),
+            ("region".to_string(), json!("Chennai")),
+        ]);
+        apply_dimension_aliases(&aliases, &mut query_data);
+        assert_eq!(
+            Value::Object(query_data),
+            json!({"city": "Delhi", "region": "Chennai"})
+        );
+    }
+}
@@ -1,4 +1,4 @@
 mod handlers;
 pub use handlers::endpoints;
 pub mod helpers;
-mod types;
+pub mod types;
@@ -1,24 +1,40 @@
 extern crate base64;
 use base64::prelude::*;
 
-use super::helpers::{decode_function, fetch_function};
+use super::helpers::{
+    add_function_version, check_function_usages, decode_base64_to_string,
+    decode_function, decode_function_versions, ensure_rollback_passes,
+    ensure_test_cases_pass, fetch_function, fetch_function_version,
+    function_version_diff, parse_test_cases, run_test_cases,
+};
 
 use crate::{
-    api::functions::types::{Stage, TestFunctionRequest, TestParam},
+    api::{
+        context::RevalidationQuery,
+        functions::types::{
+            FunctionTestCase, FunctionVersionDiff, FunctionVersionDiffQuery,
+            FunctionVersionParam, Stage, TestFunctionRequest, TestParam, TestSuiteResult,
+        },
+    },
     db::{
         self,
-        models::Function,
-        schema::functions::{dsl, dsl::functions, function_name},
+        models::{Function, FunctionVersion},
+        schema::{
+            function_versions,
+            functions::{dsl, dsl::functions, function_name},
+        },
     },
     validation_functions,
 };
 use actix_web::{
     delete, get, patch, post, put,
-    web::{self, Json, Path},
+    web::{self, Json, Path, Query},
     HttpResponse, Result, Scope,
 };
 use chrono::Utc;
-use diesel::{delete, ExpressionMethods, QueryDsl, RunQueryDsl};
+use diesel::{
+    delete, Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
 use serde_json::json;
 use service_utils::service::types::DbConnection;
 
@@ -37,7 +53,12 @@ pub fn endpoints() -> Scope {
         .service(list_functions)
         .service(delete_function)
         .service(test)
+        .service(update_test_cases)
+        .service(run_function_test_cases)
         .service(publish)
+        .service(list_function_versions)
+        .service(diff_function_versions)
+        .service(rollback_function)
 }
 
 #[post("")]
@@ -64,6 +85,8 @@ async fn create(
         function_description: req.description,
         last_modified_at: Utc::now().naive_utc(),
         last_modified_by: user.get_email(),
+        published_version: None,
+        test_cases: json!(req.test_cases),
     };
 
     let insert: Result<Function, diesel::result::Error> = diesel::insert_into(functions)
@@ -143,6 +166,8 @@ async fn update(
         published_runtime_version: result.published_runtime_version,
         last_modified_at: Utc::now().naive_utc(),
         last_modified_by: user.get_email(),
+        published_version: result.published_version,
+        test_cases: result.test_cases,
     };
 
     let mut updated_function = diesel::update(functions)
@@ -238,9 +263,9 @@ async fn test(
 
     decode_function(&mut function)?;
     let result = match path_params.stage {
-        Stage::Draft => execute_fn(&function.draft_code, &req.key, req.value),
+        Stage::Draft => execute_fn(fun_name, &function.draft_code, &req.key, req.value),
         Stage::Published => match function.published_code {
-            Some(code) => execute_fn(&code, &req.key, req.value),
+            Some(code) => execute_fn(fun_name, &code, &req.key, req.value),
             None => {
                 log::error!("Function test failed: function not published yet");
                 Err((
@@ -258,39 +283,210 @@ async fn test(
     }
 }
 
+#[put("/{function_name}/test-cases")]
+async fn update_test_cases(
+    params: web::Path<FunctionName>,
+    request: web::Json<Vec<FunctionTestCase>>,
+    db_conn: DbConnection,
+    user: User,
+) -> superposition::Result<Json<Function>> {
+    let DbConnection(mut conn) = db_conn;
+    let fun_name: String = params.into_inner().into();
+
+    let mut updated_function = diesel::update(functions)
+        .filter(dsl::function_name.eq(&fun_name))
+        .set((
+            dsl::test_cases.eq(json!(request.into_inner())),
+            dsl::last_modified_at.eq(Utc::now().naive_utc()),
+            dsl::last_modified_by.eq(user.get_email()),
+        ))
+        .get_result::<Function>(&mut conn)
+        .map_err(|err| match err {
+            diesel::result::Error::NotFound => {
+                bad_argument!("Function {} doesn't exists", fun_name)
+            }
+            err => {
+                log::error!("failed to update test cases of {fun_name}: {err}");
+                unexpected_error!("Something went wrong, failed to update test cases")
+            }
+        })?;
+
+    decode_function(&mut updated_function)?;
+    Ok(Json(updated_function))
+}
+
+#[put("/{function_name}/{stage}/test-cases/run")]
+async fn run_function_test_cases(
+    params: Path<TestParam>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<TestSuiteResult>> {
+    let DbConnection(mut conn) = db_conn;
+    let path_params = params.into_inner();
+    let fun_name: String = path_params.function_name.into();
+    let mut function = fetch_function(&fun_name, &mut conn)?;
+    decode_function(&mut function)?;
+
+    let code = match path_params.stage {
+        Stage::Draft => function.draft_code,
+        Stage::Published => function
+            .published_code
+            .ok_or(bad_argument!("Function {} is not published yet", fun_name))?,
+    };
+    let test_cases = parse_test_cases(&function.test_cases)?;
+    Ok(Json(run_test_cases(&fun_name, &code, test_cases)))
+}
+
 #[put("/{function_name}/publish")]
 async fn publish(
     params: web::Path<FunctionName>,
+    query: Query<RevalidationQuery>,
     db_conn: DbConnection,
     user: User,
 ) -> superposition::Result<Json<Function>> {
     let DbConnection(mut conn) = db_conn;
     let fun_name: String = params.into_inner().into();
 
-    let function = match fetch_function(&fun_name, &mut conn) {
-        Ok(val) => val,
-        Err(superposition::AppError::DbError(diesel::result::Error::NotFound)) => {
-            log::error!("Function {} not found.", fun_name);
-            return Err(bad_argument!("Function {} doesn't exists", fun_name));
-        }
-        Err(e) => {
-            log::error!("Failed to update Function with error: {e}");
-            return Err(unexpected_error!(
-                "Something went wrong, failed to update function"
-            ));
-        }
-    };
+    let updated_function =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let function = match functions
+                .filter(function_name.eq(&fun_name))
+                .for_update()
+                .get_result::<Function>(transaction_conn)
+            {
+                Ok(val) => val,
+                Err(diesel::result::Error::NotFound) => {
+                    log::error!("Function {} not found.", fun_name);
+                    return Err(bad_argument!("Function {} doesn't exists", fun_name));
+                }
+                Err(e) => {
+                    log::error!("Failed to update Function with error: {e}");
+                    return Err(unexpected_error!(
+                        "Something went wrong, failed to update function"
+                    ));
+                }
+            };
+
+            ensure_test_cases_pass(
+                &fun_name,
+                &decode_base64_to_string(&function.draft_code)?,
+                &function.test_cases,
+                "published",
+            )?;
+
+            check_function_usages(
+                transaction_conn,
+                &fun_name,
+                &function.draft_code,
+                query.force,
+                &user,
+                format!("Publishing {fun_name}"),
+            )?;
 
-    let updated_function = diesel::update(functions)
-        .filter(dsl::function_name.eq(fun_name.clone()))
+            let function_version = add_function_version(
+                transaction_conn,
+                &function,
+                &function.draft_code,
+                &function.draft_runtime_version,
+                &user,
+            )?;
+            publish_function_version(transaction_conn, function_version, &user)
+        })?;
+
+    Ok(Json(updated_function))
+}
+
+fn publish_function_version(
+    conn: &mut PgConnection,
+    function_version: FunctionVersion,
+    user: &User,
+) -> superposition::Result<Function> {
+    Ok(diesel::update(functions)
+        .filter(dsl::function_name.eq(function_version.function_name))
         .set((
-            dsl::published_code.eq(Some(function.draft_code.clone())),
-            dsl::published_runtime_version
-                .eq(Some(function.draft_runtime_version.clone())),
-            dsl::published_by.eq(Some(user.get_email())),
-            dsl::published_at.eq(Some(Utc::now().naive_utc())),
+            dsl::published_code.eq(Some(function_version.code)),
+            dsl::published_runtime_version.eq(Some(function_version.runtime_version)),
+            dsl::published_by.eq(Some(function_version.published_by)),
+            dsl::published_at.eq(Some(function_version.published_at)),
+            dsl::published_version.eq(Some(function_version.version)),
+            dsl::last_modified_at.eq(Utc::now().naive_utc()),
+            dsl::last_modified_by.eq(user.get_email()),
         ))
-        .get_result::<Function>(&mut conn)?;
+        .get_result::<Function>(conn)?)
+}
+
+#[get("/{function_name}/versions")]
+async fn list_function_versions(
+    params: web::Path<FunctionName>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<Vec<FunctionVersion>>> {
+    let DbConnection(mut conn) = db_conn;
+    let fun_name: String = params.into_inner().into();
+    // makes sure the function exists, an empty list means it was never published
+    fetch_function(&fun_name, &mut conn)?;
+
+    let versions = function_versions::table
+        .filter(function_versions::function_name.eq(&fun_name))
+        .order(function_versions::version.desc())
+        .get_results::<FunctionVersion>(&mut conn)?;
+    Ok(Json(decode_function_versions(versions)?))
+}
 
+#[get("/{function_name}/versions/diff")]
+async fn diff_function_versions(
+    params: web::Path<FunctionName>,
+    query: Query<FunctionVersionDiffQuery>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<FunctionVersionDiff>> {
+    let DbConnection(mut conn) = db_conn;
+    let fun_name: String = params.into_inner().into();
+    let FunctionVersionDiffQuery { from, to } = query.into_inner();
+
+    let from_version = fetch_function_version(&mut conn, &fun_name, from)?;
+    let to_version = fetch_function_version(&mut conn, &fun_name, to)?;
+    Ok(Json(function_version_diff(from_version, to_version)?))
+}
+
+/*
+  Rolling back moves the published pointer to an older version, the function row
+  stays locked from the checks until the pointer is moved.
+*/
+#[put("/{function_name}/versions/{version}/rollback")]
+async fn rollback_function(
+    params: Path<FunctionVersionParam>,
+    query: Query<RevalidationQuery>,
+    db_conn: DbConnection,
+    user: User,
+) -> superposition::Result<Json<Function>> {
+    let DbConnection(mut conn) = db_conn;
+    let FunctionVersionParam {
+        function_name: fun_name,
+        version,
+    } = params.into_inner();
+    let fun_name: String = fun_name.into();
+
+    let mut updated_function =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let function = functions
+                .filter(function_name.eq(&fun_name))
+                .for_update()
+                .get_result::<Function>(transaction_conn)?;
+            let function_version =
+                fetch_function_version(transaction_conn, &fun_name, version)?;
+            ensure_rollback_passes(&function, &function_version)?;
+            check_function_usages(
+                transaction_conn,
+                &fun_name,
+                &function_version.code,
+                query.force,
+                &user,
+                format!("Rolling {fun_name} back to version {version}"),
+            )?;
+            publish_function_version(transaction_conn, function_version, &user)
+        })?;
+    log::info!(
+        "{fun_name} rolled back to version {version} by {}",
+        user.get_email()
+    );
+    decode_function(&mut updated_function)?;
     Ok(Json(updated_function))
 }
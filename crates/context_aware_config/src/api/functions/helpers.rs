@@ -1,14 +1,42 @@
 extern crate base64;
 use base64::prelude::*;
+use chrono::Utc;
 use diesel::{
     r2d2::{ConnectionManager, PooledConnection},
-    ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
 };
+use serde_json::Value;
+use service_utils::service::types::AppState;
+use similar::TextDiff;
 use std::str;
-use superposition_macros::unexpected_error;
-use superposition_types::result as superposition;
+use superposition_macros::{bad_argument, db_error, not_found, unexpected_error};
+use superposition_types::{result as superposition, User};
 
-use crate::db::{self, models::Function, schema::functions::dsl::functions};
+use crate::{
+    api::{
+        context::{
+            helpers::{
+                find_dimension_violations, find_override_violations,
+                validate_value_with_function, violations_error,
+            },
+            ContextViolation,
+        },
+        functions::types::{
+            FunctionTestCase, FunctionVersionDiff, TestCaseResult, TestOutcome,
+            TestSuiteResult,
+        },
+    },
+    db::{
+        self,
+        models::{Context, Function, FunctionVersion},
+        schema::{
+            contexts::dsl::contexts, default_configs, dimensions, function_versions,
+            functions::dsl::functions,
+        },
+    },
+    helpers::tenant_namespaces,
+    validation_functions::{compile_fn, execute_fn, uses_axios},
+};
 
 pub fn fetch_function(
     f_name: &String,
@@ -55,16 +83,505 @@ pub fn get_published_function_code(
     Ok(function)
 }
 
+// function name, published code and published version
+type PublishedFunction = (String, Option<String>, Option<i32>);
+
 pub fn get_published_functions_by_names(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     function_names: Vec<String>,
-) -> superposition::Result<Vec<(String, Option<String>)>> {
-    let function: Vec<(String, Option<String>)> = functions
+) -> superposition::Result<Vec<PublishedFunction>> {
+    let function: Vec<PublishedFunction> = functions
         .filter(db::schema::functions::function_name.eq_any(function_names))
         .select((
             db::schema::functions::function_name,
             db::schema::functions::published_code,
+            db::schema::functions::published_version,
         ))
         .load(conn)?;
     Ok(function)
 }
+
+/*
+  Every publish is stored as the next immutable version of the function, the
+  published columns of the function point to one of these versions. The function
+  row is locked until the transaction ends, so concurrent publishes take turns
+  picking the next version.
+*/
+pub fn add_function_version(
+    conn: &mut PgConnection,
+    function: &Function,
+    code: &str,
+    runtime_version: &str,
+    user: &User,
+) -> superposition::Result<FunctionVersion> {
+    functions
+        .filter(db::schema::functions::function_name.eq(&function.function_name))
+        .select(db::schema::functions::function_name)
+        .for_update()
+        .first::<String>(conn)?;
+    let latest_version: Option<i32> = function_versions::table
+        .filter(function_versions::function_name.eq(&function.function_name))
+        .select(function_versions::version)
+        .order(function_versions::version.desc())
+        .first(conn)
+        .optional()?;
+    let function_version = FunctionVersion {
+        function_name: function.function_name.clone(),
+        version: latest_version.unwrap_or(0) + 1,
+        code: code.to_string(),
+        runtime_version: runtime_version.to_string(),
+        description: function.function_description.clone(),
+        published_by: user.get_email(),
+        published_at: Utc::now().naive_utc(),
+    };
+    diesel::insert_into(function_versions::table)
+        .values(&function_version)
+        .execute(conn)?;
+    Ok(function_version)
+}
+
+pub fn fetch_function_version(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    f_name: &String,
+    version: i32,
+) -> superposition::Result<FunctionVersion> {
+    function_versions::table
+        .filter(function_versions::function_name.eq(f_name))
+        .filter(function_versions::version.eq(version))
+        .get_result::<FunctionVersion>(conn)
+        .map_err(|err| match err {
+            diesel::result::Error::NotFound => {
+                not_found!("Version {} of function {} doesn't exist", version, f_name)
+            }
+            err => {
+                log::error!("failed to fetch version {version} of {f_name}: {err}");
+                db_error!(err)
+            }
+        })
+}
+
+pub fn decode_function_versions(
+    versions: Vec<FunctionVersion>,
+) -> superposition::Result<Vec<FunctionVersion>> {
+    versions
+        .into_iter()
+        .map(|version| {
+            Ok(FunctionVersion {
+                code: decode_base64_to_string(&version.code)?,
+                ..version
+            })
+        })
+        .collect()
+}
+
+pub fn function_version_diff(
+    from_version: FunctionVersion,
+    to_version: FunctionVersion,
+) -> superposition::Result<FunctionVersionDiff> {
+    let from_code = decode_base64_to_string(&from_version.code)?;
+    let to_code = decode_base64_to_string(&to_version.code)?;
+    let diff = TextDiff::from_lines(&from_code, &to_code)
+        .unified_diff()
+        .header(
+            &format!("version {}", from_version.version),
+            &format!("version {}", to_version.version),
+        )
+        .to_string();
+    Ok(FunctionVersionDiff {
+        function_name: to_version.function_name,
+        from: from_version.version,
+        to: to_version.version,
+        from_runtime_version: from_version.runtime_version,
+        to_runtime_version: to_version.runtime_version,
+        diff,
+    })
+}
+
+/*
+  The code of an older version goes through the same compile check and saved
+  test cases as a new draft before it gets published again.
+*/
+pub fn ensure_rollback_passes(
+    function: &Function,
+    function_version: &FunctionVersion,
+) -> superposition::Result<()> {
+    let code = decode_base64_to_string(&function_version.code)?;
+    compile_fn(&code)?;
+    ensure_test_cases_pass(
+        &function.function_name,
+        &code,
+        &function.test_cases,
+        &format!("rolled back to version {}", function_version.version),
+    )
+}
+
+pub fn parse_test_cases(
+    test_cases: &Value,
+) -> superposition::Result<Vec<FunctionTestCase>> {
+    serde_json::from_value(test_cases.clone()).map_err(|err| {
+        log::error!("failed to decode function test cases: {err}");
+        unexpected_error!("Failed to decode function test cases")
+    })
+}
+
+pub fn run_test_cases(
+    f_name: &str,
+    code: &str,
+    test_cases: Vec<FunctionTestCase>,
+) -> TestSuiteResult {
+    let results = test_cases
+        .into_iter()
+        .map(|test_case| {
+            let (actual, message) =
+                match execute_fn(f_name, code, &test_case.key, test_case.value.clone()) {
+                    Ok(stdout) => (TestOutcome::Pass, stdout),
+                    Err((err, stdout)) => (
+                        TestOutcome::Fail,
+                        format!("{err}\n{}", stdout.unwrap_or_default())
+                            .trim_end()
+                            .to_string(),
+                    ),
+                };
+            TestCaseResult {
+                passed: actual == test_case.expected,
+                key: test_case.key,
+                value: test_case.value,
+                expected: test_case.expected,
+                actual,
+                message,
+            }
+        })
+        .collect::<Vec<_>>();
+    TestSuiteResult {
+        passed: results.iter().all(|result| result.passed),
+        results,
+    }
+}
+
+/*
+  The saved test cases have to hold for code before it goes live, whether it is
+  a draft being published or an older version being rolled back to.
+*/
+pub fn ensure_test_cases_pass(
+    f_name: &str,
+    code: &str,
+    test_cases: &Value,
+    action: &str,
+) -> superposition::Result<()> {
+    let test_result = run_test_cases(f_name, code, parse_test_cases(test_cases)?);
+    if test_result.passed {
+        return Ok(());
+    }
+    let failed_keys = test_result
+        .results
+        .iter()
+        .filter(|result| !result.passed)
+        .map(|result| format!("{} = {}", result.key, result.value))
+        .collect::<Vec<_>>();
+    Err(bad_argument!(
+        "Function {} can't be {}, {} of {} saved test cases fail: {}",
+        f_name,
+        action,
+        failed_keys.len(),
+        test_result.results.len(),
+        failed_keys.join(", ")
+    ))
+}
+
+/*
+  Overrides of the keys and conditions on the dimensions validated by the function
+  are checked against the code about to go live, which is base64 encoded.
+*/
+pub fn check_function_usages(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    f_name: &str,
+    code: &str,
+    force: bool,
+    user: &User,
+    change: String,
+) -> superposition::Result<()> {
+    let keys: Vec<String> = default_configs::dsl::default_configs
+        .filter(default_configs::function_name.eq(f_name))
+        .select(default_configs::key)
+        .load(conn)?;
+    let dimension_names: Vec<String> = dimensions::dsl::dimensions
+        .filter(dimensions::function_name.eq(f_name))
+        .select(dimensions::dimension)
+        .load(conn)?;
+    if keys.is_empty() && dimension_names.is_empty() {
+        return Ok(());
+    }
+
+    let all_contexts: Vec<Context> = contexts.load(conn)?;
+    let mut violations: Vec<ContextViolation> = Vec::new();
+    for key in keys.iter() {
+        violations.extend(find_override_violations(&all_contexts, key, |value| {
+            validate_value_with_function(f_name, code, key, value)
+        }));
+    }
+    for dimension_name in dimension_names.iter() {
+        violations.extend(find_dimension_violations(
+            &all_contexts,
+            dimension_name,
+            |constraint| {
+                constraint.values().into_iter().try_for_each(|value| {
+                    validate_value_with_function(f_name, code, dimension_name, value)
+                })
+            },
+        )?);
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+    if !force {
+        return Err(violations_error(change, &violations));
+    }
+    log::warn!(
+        "{change} forced by {} with {} context values violating it",
+        user.get_email(),
+        violations.len()
+    );
+    Ok(())
+}
+
+/// Names of the published functions whose code still calls axios.
+pub fn published_functions_using_axios(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Vec<String>> {
+    let published: Vec<(String, Option<String>)> = functions
+        .select((
+            db::schema::functions::function_name,
+            db::schema::functions::published_code,
+        ))
+        .filter(db::schema::functions::published_code.is_not_null())
+        .load(conn)?;
+    let mut using_axios = Vec::new();
+    for (f_name, code) in published {
+        let code = decode_base64_to_string(&code.unwrap_or_default())?;
+        if uses_axios(&code) {
+            using_axios.push(f_name);
+        }
+    }
+    Ok(using_axios)
+}
+
+/*
+  Functions no longer get axios, so every write validated by a published function
+  calling it fails until the function is published again without it. They are
+  listed once at startup so that they can be fixed before anyone runs into them.
+*/
+pub fn report_functions_using_axios(state: &AppState) {
+    for (namespace, _) in tenant_namespaces(state) {
+        let result = state
+            .db_pool
+            .get_conn(namespace.clone())
+            .map_err(|err| unexpected_error!("failed to get db connection: {}", err))
+            .and_then(|mut conn| published_functions_using_axios(&mut conn));
+        match result {
+            Ok(names) if names.is_empty() => (),
+            Ok(names) => log::warn!(
+                "{namespace}: published functions {} use axios, which is not available to functions anymore, writes validated by them will fail until they are published without it",
+                names.join(",")
+            ),
+            Err(err) => log::error!(
+                "{namespace}: failed to look for published functions using axios: {err}"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const CODE: &str = r#"
+        function validate(key, value) {
+            console.log("validating", key);
+            return value > 0;
+        }
+    "#;
+
+    fn test_case(value: Value, expected: TestOutcome) -> FunctionTestCase {
+        FunctionTestCase {
+            key: "max_retries".to_string(),
+            value,
+            expected,
+        }
+    }
+
+    fn function_version(version: i32, code: &str) -> FunctionVersion {
+        FunctionVersion {
+            function_name: "positive".to_string(),
+            version,
+            code: BASE64_STANDARD.encode(code),
+            runtime_version: "1.0.0".to_string(),
+            description: "checks that values are positive".to_string(),
+            published_by: "user@superposition.io".to_string(),
+            published_at: Utc::now().naive_utc(),
+        }
+    }
+
+    fn function(test_cases: Vec<FunctionTestCase>) -> Function {
+        let now = Utc::now().naive_utc();
+        Function {
+            function_name: "positive".to_string(),
+            published_code: Some(BASE64_STANDARD.encode(CODE)),
+            draft_code: BASE64_STANDARD.encode(CODE),
+            function_description: "checks that values are positive".to_string(),
+            published_runtime_version: Some("1.0.0".to_string()),
+            draft_runtime_version: "1.0.0".to_string(),
+            published_at: Some(now),
+            draft_edited_at: now,
+            published_by: Some("user@superposition.io".to_string()),
+            draft_edited_by: "user@superposition.io".to_string(),
+            last_modified_at: now,
+            last_modified_by: "user@superposition.io".to_string(),
+            published_version: Some(2),
+            test_cases: serde_json::to_value(test_cases).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_decode_function_versions() {
+        let versions = decode_function_versions(vec![
+            function_version(2, "function validate() { return false; }"),
+            function_version(1, CODE),
+        ])
+        .expect("versions should decode");
+        let decoded = versions
+            .iter()
+            .map(|version| (version.version, version.code.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            vec![(2, "function validate() { return false; }"), (1, CODE)]
+        );
+
+        let corrupt = FunctionVersion {
+            code: "not base64!".to_string(),
+            ..function_version(3, CODE)
+        };
+        assert!(decode_function_versions(vec![corrupt]).is_err());
+    }
+
+    #[test]
+    fn test_function_version_diff() {
+        let from = function_version(
+            1,
+            "function validate(key, value) {\n    return value > 0;\n}\n",
+        );
+        let to = FunctionVersion {
+            runtime_version: "1.1.0".to_string(),
+            ..function_version(
+                3,
+                "function validate(key, value) {\n    return value >= 0;\n}\n",
+            )
+        };
+
+        let diff = function_version_diff(from, to).expect("diff failed");
+        assert_eq!(diff.function_name, "positive");
+        assert_eq!((diff.from, diff.to), (1, 3));
+        assert_eq!(diff.from_runtime_version, "1.0.0");
+        assert_eq!(diff.to_runtime_version, "1.1.0");
+        assert_eq!(
+            diff.diff,
+            concat!(
+                "--- version 1\n",
+                "+++ version 3\n",
+                "@@ -1,3 +1,3 @@\n",
+                " function validate(key, value) {\n",
+                "-    return value > 0;\n",
+                "+    return value >= 0;\n",
+                " }\n",
+            )
+        );
+
+        let same =
+            function_version_diff(function_version(1, CODE), function_version(2, CODE))
+                .expect("diff failed");
+        assert!(same.diff.is_empty());
+    }
+
+    #[test]
+    fn test_ensure_rollback_passes() {
+        let function = function(vec![
+            test_case(json!(3), TestOutcome::Pass),
+            test_case(json!(-1), TestOutcome::Fail),
+        ]);
+
+        assert!(ensure_rollback_passes(&function, &function_version(1, CODE)).is_ok());
+
+        let accepts_all = function_version(1, "function validate() { return true; }");
+        let err = ensure_rollback_passes(&function, &accepts_all).unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "can't be rolled back to version 1, 1 of 2 saved test cases fail: max_retries = -1"
+            ),
+            "{err}"
+        );
+
+        let broken = function_version(1, "function validate( {");
+        assert!(ensure_rollback_passes(&function, &broken).is_err());
+    }
+
+    #[test]
+    fn test_run_test_cases_matches_expected_outcomes() {
+        let result = run_test_cases(
+            "positive",
+            CODE,
+            vec![
+                test_case(json!(3), TestOutcome::Pass),
+                test_case(json!(-1), TestOutcome::Fail),
+            ],
+        );
+        assert!(result.passed);
+        assert_eq!(result.results[0].actual, TestOutcome::Pass);
+        assert_eq!(result.results[0].message, "validating max_retries");
+        assert_eq!(result.results[1].actual, TestOutcome::Fail);
+        assert!(result.results[1].passed);
+        assert!(result.results[1]
+            .message
+            .ends_with("\nvalidating max_retries"));
+    }
+
+    #[test]
+    fn test_run_test_cases_reports_mismatches() {
+        let result = run_test_cases(
+            "positive",
+            CODE,
+            vec![
+                test_case(json!(3), TestOutcome::Fail),
+                test_case(json!(2), TestOutcome::Pass),
+            ],
+        );
+        assert!(!result.passed);
+        assert!(!result.results[0].passed);
+        assert_eq!(result.results[0].actual, TestOutcome::Pass);
+        assert!(result.results[1].passed);
+
+        assert!(run_test_cases("positive", CODE, Vec::new()).passed);
+    }
+
+    #[test]
+    fn test_ensure_test_cases_pass() {
+        let passing = json!([{"key": "max_retries", "value": 3, "expected": "PASS"}]);
+        assert!(ensure_test_cases_pass("positive", CODE, &passing, "published").is_ok());
+
+        let failing = json!([
+            {"key": "max_retries", "value": 3, "expected": "PASS"},
+            {"key": "max_retries", "value": 0, "expected": "PASS"}
+        ]);
+        let err = ensure_test_cases_pass("positive", CODE, &failing, "published")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(
+                "can't be published, 1 of 2 saved test cases fail: max_retries = 0"
+            ),
+            "{err}"
+        );
+    }
+}
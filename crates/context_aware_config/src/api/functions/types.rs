@@ -3,6 +3,20 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use superposition_types::RegexEnum;
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TestOutcome {
+    Pass,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionTestCase {
+    pub key: String,
+    pub value: Value,
+    pub expected: TestOutcome,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateFunctionRequest {
     pub function: Option<String>,
@@ -16,6 +30,8 @@ pub struct CreateFunctionRequest {
     pub function: String,
     pub runtime_version: String,
     pub description: String,
+    #[serde(default)]
+    pub test_cases: Vec<FunctionTestCase>,
 }
 
 #[derive(Debug, Deserialize, AsRef, Deref, DerefMut, Into)]
@@ -62,8 +78,46 @@ pub struct TestParam {
     pub stage: Stage,
 }
 
+#[derive(Deserialize)]
+pub struct FunctionVersionParam {
+    pub function_name: FunctionName,
+    pub version: i32,
+}
+
+#[derive(Deserialize)]
+pub struct FunctionVersionDiffQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+#[derive(Serialize)]
+pub struct FunctionVersionDiff {
+    pub function_name: String,
+    pub from: i32,
+    pub to: i32,
+    pub from_runtime_version: String,
+    pub to_runtime_version: String,
+    pub diff: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestFunctionRequest {
     pub key: String,
     pub value: Value,
 }
+
+#[derive(Serialize)]
+pub struct TestCaseResult {
+    pub key: String,
+    pub value: Value,
+    pub expected: TestOutcome,
+    pub actual: TestOutcome,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct TestSuiteResult {
+    pub passed: bool,
+    pub results: Vec<TestCaseResult>,
+}
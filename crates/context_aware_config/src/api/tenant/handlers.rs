@@ -0,0 +1,815 @@
+extern crate base64;
+use base64::prelude::*;
+
+use actix_web::{
+    get, post,
+    web::{Data, Json, Query},
+    HttpResponse, Scope,
+};
+use cac_toml::ContextAwareConfig;
+use chrono::Utc;
+use diesel::{
+    dsl::exists,
+    r2d2::{ConnectionManager, PooledConnection},
+    Connection, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
+    RunQueryDsl,
+};
+use jsonschema::JSONSchema;
+use serde_json::{json, Map, Value};
+use service_utils::{
+    helpers::parse_config_tags,
+    service::types::{AppHeader, AppState, CustomHeaders, DbConnection},
+};
+use superposition_macros::{bad_argument, unexpected_error};
+use superposition_types::{
+    result as superposition, Cac, Condition, Overrides, RegexEnum, TenantConfig, User,
+};
+
+use crate::{
+    api::{
+        change_requests::reject_unreviewed_change,
+        context::{
+            hash, override_helper, validate_dimensions_and_calculate_priority, PutReq,
+        },
+        default_config::validate_default_config,
+        dimension::{get_all_dimension_schema_map, validate_dimension_schema},
+        functions::helpers::{
+            add_function_version, check_function_usages, decode_base64_to_string,
+            ensure_test_cases_pass, parse_test_cases,
+        },
+        type_templates::helpers::resolve_schema,
+    },
+    db::{
+        models::{Context, DefaultConfig, Dimension, Function, TypeTemplates},
+        schema::{contexts, default_configs, dimensions, functions, type_templates},
+    },
+    helpers::add_config_version,
+    validation_functions::compile_fn,
+};
+
+use super::types::{
+    ContextBundle, DefaultConfigBundle, DimensionBundle, FunctionBundle, ImportQuery,
+    ImportResponse, ImportSummary, TenantBundle, TypeTemplateBundle,
+};
+
+type DBConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+pub fn endpoints() -> Scope {
+    Scope::new("")
+        .service(export_tenant)
+        .service(import_tenant)
+        .service(import_tenant_from_toml)
+}
+
+fn context_to_bundle(context: Context) -> superposition::Result<ContextBundle> {
+    let condition = Cac::<Condition>::try_from_db(
+        context.value.as_object().cloned().unwrap_or_default(),
+    )
+    .map_err(|err| {
+        log::error!(
+            "failed to decode condition of context {}: {err}",
+            context.id
+        );
+        unexpected_error!("failed to decode context {}", context.id)
+    })?;
+    let override_ = Cac::<Overrides>::try_from_db(
+        context.override_.as_object().cloned().unwrap_or_default(),
+    )
+    .map_err(|err| {
+        log::error!("failed to decode override of context {}: {err}", context.id);
+        unexpected_error!("failed to decode context {}", context.id)
+    })?;
+    Ok(ContextBundle {
+        context: condition,
+        r#override: override_,
+        active_from: context.active_from,
+        active_until: context.active_until,
+    })
+}
+
+fn function_to_bundle(function: Function) -> superposition::Result<FunctionBundle> {
+    Ok(FunctionBundle {
+        draft_code: decode_base64_to_string(&function.draft_code)?,
+        published_code: function
+            .published_code
+            .as_ref()
+            .map(decode_base64_to_string)
+            .transpose()?,
+        function_name: function.function_name,
+        function_description: function.function_description,
+        draft_runtime_version: function.draft_runtime_version,
+        published_runtime_version: function.published_runtime_version,
+        test_cases: parse_test_cases(&function.test_cases)?,
+    })
+}
+
+#[get("/export")]
+async fn export_tenant(
+    db_conn: DbConnection,
+) -> superposition::Result<Json<TenantBundle>> {
+    let DbConnection(mut conn) = db_conn;
+
+    let functions = functions::table
+        .order(functions::function_name.asc())
+        .load::<Function>(&mut conn)?
+        .into_iter()
+        .map(function_to_bundle)
+        .collect::<superposition::Result<Vec<_>>>()?;
+
+    let type_templates = type_templates::table
+        .order(type_templates::type_name.asc())
+        .load::<TypeTemplates>(&mut conn)?
+        .into_iter()
+        .map(|type_template| TypeTemplateBundle {
+            type_name: type_template.type_name,
+            type_schema: type_template.type_schema,
+        })
+        .collect();
+
+    let dimensions = dimensions::table
+        .order(dimensions::priority.asc())
+        .load::<Dimension>(&mut conn)?
+        .into_iter()
+        .map(|dimension| DimensionBundle {
+            dimension: dimension.dimension,
+            priority: dimension.priority,
+            schema: dimension.schema,
+            function_name: dimension.function_name,
+        })
+        .collect();
+
+    let default_configs = default_configs::table
+        .order(default_configs::key.asc())
+        .load::<DefaultConfig>(&mut conn)?
+        .into_iter()
+        .map(|default_config| DefaultConfigBundle {
+            key: default_config.key,
+            value: default_config.value,
+            schema: default_config.schema,
+            function_name: default_config.function_name,
+        })
+        .collect();
+
+    let contexts = contexts::table
+        .order((contexts::priority.asc(), contexts::created_at.asc()))
+        .load::<Context>(&mut conn)?
+        .into_iter()
+        .map(context_to_bundle)
+        .collect::<superposition::Result<Vec<_>>>()?;
+
+    Ok(Json(TenantBundle {
+        functions,
+        type_templates,
+        dimensions,
+        default_configs,
+        contexts,
+    }))
+}
+
+/*
+  Published code goes through the same checks as a publish, its saved test cases
+  and the context values validated by the function. Code that is already the
+  published one keeps its version.
+*/
+fn import_functions(
+    bundle: Vec<FunctionBundle>,
+    user: &User,
+    summary: &mut ImportSummary,
+    conn: &mut DBConnection,
+) -> superposition::Result<()> {
+    for function in bundle {
+        RegexEnum::FunctionName
+            .match_regex(&function.function_name)
+            .map_err(|err| bad_argument!(err))?;
+        compile_fn(&function.draft_code)?;
+        if let Some(code) = &function.published_code {
+            compile_fn(code)?;
+        }
+
+        let existing = functions::table
+            .filter(functions::function_name.eq(&function.function_name))
+            .first::<Function>(conn)
+            .optional()?;
+        let published_code = function
+            .published_code
+            .as_ref()
+            .map(|code| BASE64_STANDARD.encode(code));
+        let republished = existing.as_ref().filter(|existing| {
+            published_code.is_some()
+                && existing.published_code == published_code
+                && existing.published_runtime_version
+                    == function.published_runtime_version
+        });
+        let test_cases = json!(function.test_cases);
+
+        if let (Some(code), Some(encoded_code), None) =
+            (&function.published_code, &published_code, republished)
+        {
+            ensure_test_cases_pass(
+                &function.function_name,
+                code,
+                &test_cases,
+                "imported",
+            )?;
+            check_function_usages(
+                conn,
+                &function.function_name,
+                encoded_code,
+                false,
+                user,
+                format!("Importing {}", function.function_name),
+            )?;
+        }
+
+        let now = Utc::now().naive_utc();
+        let is_published = published_code.is_some();
+        let new_function = Function {
+            function_name: function.function_name,
+            published_code,
+            draft_code: BASE64_STANDARD.encode(function.draft_code),
+            function_description: function.function_description,
+            published_runtime_version: function.published_runtime_version,
+            draft_runtime_version: function.draft_runtime_version,
+            published_at: republished
+                .and_then(|existing| existing.published_at)
+                .or(is_published.then_some(now)),
+            draft_edited_at: now,
+            published_by: republished
+                .and_then(|existing| existing.published_by.clone())
+                .or(is_published.then(|| user.get_email())),
+            draft_edited_by: user.get_email(),
+            last_modified_at: now,
+            last_modified_by: user.get_email(),
+            published_version: None,
+            test_cases,
+        };
+
+        diesel::insert_into(functions::table)
+            .values(&new_function)
+            .on_conflict(functions::function_name)
+            .do_update()
+            .set(&new_function)
+            .execute(conn)?;
+        if let (Some(code), None) = (&new_function.published_code, republished) {
+            let runtime_version = new_function
+                .published_runtime_version
+                .as_ref()
+                .unwrap_or(&new_function.draft_runtime_version);
+            let function_version =
+                add_function_version(conn, &new_function, code, runtime_version, user)?;
+            diesel::update(functions::table)
+                .filter(functions::function_name.eq(&new_function.function_name))
+                .set(functions::published_version.eq(function_version.version))
+                .execute(conn)?;
+        }
+        summary.record(new_function.function_name, existing.is_some());
+    }
+    Ok(())
+}
+
+fn import_type_templates(
+    bundle: Vec<TypeTemplateBundle>,
+    user: &User,
+    summary: &mut ImportSummary,
+    conn: &mut DBConnection,
+) -> superposition::Result<()> {
+    for type_template in bundle {
+        RegexEnum::TypeTemplateName
+            .match_regex(&type_template.type_name)
+            .map_err(|err| bad_argument!(err))?;
+        JSONSchema::compile(&type_template.type_schema).map_err(|err| {
+            bad_argument!(
+                "Invalid jsonschema for type {}, validation error is: {}",
+                type_template.type_name,
+                err.to_string()
+            )
+        })?;
+
+        let already_exists = diesel::select(exists(
+            type_templates::table
+                .filter(type_templates::type_name.eq(&type_template.type_name)),
+        ))
+        .get_result::<bool>(conn)?;
+
+        let now = Utc::now().naive_utc();
+        let new_type_template = TypeTemplates {
+            type_name: type_template.type_name,
+            type_schema: type_template.type_schema,
+            created_by: user.get_email(),
+            created_at: now,
+            last_modified_at: now,
+            last_modified_by: user.get_email(),
+        };
+
+        diesel::insert_into(type_templates::table)
+            .values(&new_type_template)
+            .on_conflict(type_templates::type_name)
+            .do_update()
+            .set((
+                type_templates::type_schema.eq(&new_type_template.type_schema),
+                type_templates::last_modified_at.eq(now),
+                type_templates::last_modified_by.eq(user.get_email()),
+            ))
+            .execute(conn)?;
+        summary.record(new_type_template.type_name, already_exists);
+    }
+    Ok(())
+}
+
+fn import_dimensions(
+    state: &Data<AppState>,
+    bundle: Vec<DimensionBundle>,
+    user: &User,
+    summary: &mut ImportSummary,
+    conn: &mut DBConnection,
+) -> superposition::Result<()> {
+    for dimension in bundle {
+        RegexEnum::DimensionName
+            .match_regex(&dimension.dimension)
+            .map_err(|err| bad_argument!(err))?;
+        if dimension.priority <= 0 {
+            return Err(bad_argument!(
+                "Priority of dimension {} should be greater than 0",
+                dimension.dimension
+            ));
+        }
+        validate_dimension_schema(
+            &state.meta_schema,
+            &resolve_schema(conn, &dimension.schema)?,
+        )?;
+
+        let already_exists = diesel::select(exists(
+            dimensions::table.filter(dimensions::dimension.eq(&dimension.dimension)),
+        ))
+        .get_result::<bool>(conn)?;
+
+        let new_dimension = Dimension {
+            dimension: dimension.dimension,
+            priority: dimension.priority,
+            schema: dimension.schema,
+            function_name: dimension.function_name,
+            created_by: user.get_email(),
+            created_at: Utc::now(),
+            last_modified_at: Utc::now().naive_utc(),
+            last_modified_by: user.get_email(),
+        };
+
+        diesel::insert_into(dimensions::table)
+            .values(&new_dimension)
+            .on_conflict(dimensions::dimension)
+            .do_update()
+            .set(&new_dimension)
+            .execute(conn)?;
+        summary.record(new_dimension.dimension, already_exists);
+    }
+    Ok(())
+}
+
+fn import_default_configs(
+    bundle: Vec<DefaultConfigBundle>,
+    user: &User,
+    summary: &mut ImportSummary,
+    conn: &mut DBConnection,
+) -> superposition::Result<()> {
+    for default_config in bundle {
+        RegexEnum::DefaultConfigKey
+            .match_regex(&default_config.key)
+            .map_err(|err| bad_argument!(err))?;
+
+        let already_exists = diesel::select(exists(
+            default_configs::table.filter(default_configs::key.eq(&default_config.key)),
+        ))
+        .get_result::<bool>(conn)?;
+
+        let new_default_config = DefaultConfig {
+            key: default_config.key,
+            value: default_config.value,
+            schema: default_config.schema,
+            function_name: default_config.function_name,
+            created_by: user.get_email(),
+            created_at: Utc::now(),
+            last_modified_at: Utc::now().naive_utc(),
+            last_modified_by: user.get_email(),
+        };
+        validate_default_config(&new_default_config, conn)?;
+
+        diesel::insert_into(default_configs::table)
+            .values(&new_default_config)
+            .on_conflict(default_configs::key)
+            .do_update()
+            .set((
+                default_configs::value.eq(&new_default_config.value),
+                default_configs::schema.eq(&new_default_config.schema),
+                default_configs::function_name.eq(&new_default_config.function_name),
+                default_configs::last_modified_at.eq(Utc::now().naive_utc()),
+                default_configs::last_modified_by.eq(user.get_email()),
+            ))
+            .execute(conn)?;
+        summary.record(new_default_config.key, already_exists);
+    }
+    Ok(())
+}
+
+fn import_contexts(
+    bundle: Vec<ContextBundle>,
+    user: &User,
+    tenant_config: &TenantConfig,
+    summary: &mut ImportSummary,
+    conn: &mut DBConnection,
+) -> superposition::Result<()> {
+    let dimension_schema_map = get_all_dimension_schema_map(conn)?;
+    for context in bundle {
+        let condition = json!(context.context);
+        let context_id = hash(&condition);
+        let already_exists =
+            diesel::select(exists(contexts::table.filter(contexts::id.eq(&context_id))))
+                .get_result::<bool>(conn)?;
+
+        let put_req = PutReq {
+            context: context.context,
+            r#override: context.r#override,
+            active_from: context.active_from,
+            active_until: context.active_until,
+        };
+        let put_resp = override_helper(Json(put_req), conn, true, user, tenant_config)?;
+
+        // existing contexts keep their priority on update, recompute it in case
+        // the imported dimensions have different priorities
+        let priority = validate_dimensions_and_calculate_priority(
+            "context",
+            &condition,
+            &dimension_schema_map,
+        )?;
+        if put_resp.priority != priority {
+            diesel::update(contexts::table)
+                .filter(contexts::id.eq(&put_resp.context_id))
+                .set(contexts::priority.eq(priority))
+                .execute(conn)?;
+        }
+        summary.record(put_resp.context_id, already_exists);
+    }
+    Ok(())
+}
+
+/*
+  The bundle is applied in dependency order within a single transaction, going
+  through the same validations as the individual create APIs. A dry run applies
+  the bundle the same way and rolls it back, reporting what would change.
+*/
+fn import_bundle(
+    state: &Data<AppState>,
+    tags: Option<Vec<String>>,
+    dry_run: bool,
+    bundle: TenantBundle,
+    user: &User,
+    tenant_config: &TenantConfig,
+    conn: &mut DBConnection,
+) -> superposition::Result<HttpResponse> {
+    if !dry_run {
+        reject_unreviewed_change(tenant_config, "Importing a tenant")?;
+    }
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        diesel::sql_query("SAVEPOINT import_tenant_savepoint")
+            .execute(transaction_conn)?;
+
+        let mut response = ImportResponse {
+            dry_run,
+            ..Default::default()
+        };
+        import_functions(
+            bundle.functions,
+            user,
+            &mut response.functions,
+            transaction_conn,
+        )?;
+        import_type_templates(
+            bundle.type_templates,
+            user,
+            &mut response.type_templates,
+            transaction_conn,
+        )?;
+        import_dimensions(
+            state,
+            bundle.dimensions,
+            user,
+            &mut response.dimensions,
+            transaction_conn,
+        )?;
+        import_default_configs(
+            bundle.default_configs,
+            user,
+            &mut response.default_configs,
+            transaction_conn,
+        )?;
+        import_contexts(
+            bundle.contexts,
+            user,
+            tenant_config,
+            &mut response.contexts,
+            transaction_conn,
+        )?;
+
+        let mut http_resp = HttpResponse::Ok();
+        if dry_run {
+            diesel::sql_query("ROLLBACK TO import_tenant_savepoint")
+                .execute(transaction_conn)?;
+        } else {
+            let version_id = add_config_version(state, tags, transaction_conn)?;
+            response.version_id = Some(version_id);
+            http_resp.insert_header((
+                AppHeader::XConfigVersion.to_string(),
+                version_id.to_string(),
+            ));
+        }
+        Ok(http_resp.json(response))
+    })
+}
+
+#[post("/import")]
+async fn import_tenant(
+    state: Data<AppState>,
+    custom_headers: CustomHeaders,
+    query: Query<ImportQuery>,
+    req: Json<TenantBundle>,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
+    let tags = parse_config_tags(custom_headers.config_tags)?;
+    import_bundle(
+        &state,
+        tags,
+        query.dry_run,
+        req.into_inner(),
+        &user,
+        &tenant_config,
+        &mut conn,
+    )
+}
+
+fn toml_to_json<T: serde::Serialize>(value: &T) -> superposition::Result<Value> {
+    serde_json::to_value(value).map_err(|err| {
+        log::error!("failed to convert toml value to json: {err}");
+        bad_argument!("Unsupported value in the TOML file: {}", err)
+    })
+}
+
+fn toml_to_map(name: &str, value: Value) -> superposition::Result<Map<String, Value>> {
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(bad_argument!("{} should be a table", name)),
+    }
+}
+
+/*
+  Dimensions get their position in the file as priority, context expressions are
+  converted to JSONLogic conditions and the overrides are taken as is.
+*/
+fn bundle_from_cac_toml(toml_content: &str) -> superposition::Result<TenantBundle> {
+    let cac =
+        ContextAwareConfig::parse_str(toml_content).map_err(|err| bad_argument!(err))?;
+
+    let dimensions = cac
+        .dimensions()
+        .into_iter()
+        .map(|(dimension, priority, schema)| {
+            Ok(DimensionBundle {
+                priority: i32::try_from(priority)
+                    .map_err(|_| bad_argument!("Too many dimensions in the TOML file"))?,
+                schema: toml_to_json(&schema)?,
+                function_name: None,
+                dimension,
+            })
+        })
+        .collect::<superposition::Result<Vec<_>>>()?;
+
+    let default_configs = cac
+        .default_configs()
+        .into_iter()
+        .map(|(key, value, schema)| {
+            Ok(DefaultConfigBundle {
+                value: toml_to_json(&value)?,
+                schema: toml_to_json(&schema)?,
+                function_name: None,
+                key,
+            })
+        })
+        .collect::<superposition::Result<Vec<_>>>()?;
+
+    let contexts = cac
+        .contexts()
+        .map_err(|err| bad_argument!(err))?
+        .into_iter()
+        .map(|(condition, overrides)| {
+            let context = Cac::<Condition>::try_from(toml_to_map("context", condition)?)
+                .map_err(|err| bad_argument!(err))?;
+            let r#override = Cac::<Overrides>::try_from(toml_to_map(
+                "override",
+                toml_to_json(&overrides)?,
+            )?)
+            .map_err(|err| bad_argument!(err))?;
+            Ok(ContextBundle {
+                context,
+                r#override,
+                active_from: None,
+                active_until: None,
+            })
+        })
+        .collect::<superposition::Result<Vec<_>>>()?;
+
+    Ok(TenantBundle {
+        functions: Vec::new(),
+        type_templates: Vec::new(),
+        dimensions,
+        default_configs,
+        contexts,
+    })
+}
+
+#[post("/import/toml")]
+async fn import_tenant_from_toml(
+    state: Data<AppState>,
+    custom_headers: CustomHeaders,
+    query: Query<ImportQuery>,
+    req: String,
+    db_conn: DbConnection,
+    user: User,
+    tenant_config: TenantConfig,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
+    let tags = parse_config_tags(custom_headers.config_tags)?;
+    let bundle = bundle_from_cac_toml(&req)?;
+    import_bundle(
+        &state,
+        tags,
+        query.dry_run,
+        bundle,
+        &user,
+        &tenant_config,
+        &mut conn,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::functions::types::TestOutcome;
+
+    const CAC_TOML: &str = r#"
+[default-config]
+per_km_rate = { "value" = 20.0, "schema" = { "type" = "number" } }
+surge_factor = { "value" = 0.0, "schema" = { "type" = "number" } }
+
+[dimensions]
+city = { schema = { "type" = "string", "enum" = ["Bangalore", "Delhi"] } }
+vehicle_type = { schema = { "type" = "string", "enum" = ["auto", "cab"] } }
+
+[context."$vehicle_type == 'cab'"]
+per_km_rate = 25.0
+
+[context."$city == 'Delhi' && $vehicle_type == 'cab'"]
+surge_factor = 5.0
+"#;
+
+    #[test]
+    fn test_function_to_bundle() {
+        let now = Utc::now().naive_utc();
+        let function = Function {
+            function_name: "positive".to_string(),
+            published_code: Some(
+                BASE64_STANDARD.encode("function validate() { return true; }"),
+            ),
+            draft_code: BASE64_STANDARD.encode("function validate() { return false; }"),
+            function_description: "checks the value is positive".to_string(),
+            published_runtime_version: Some("1.0.0".to_string()),
+            draft_runtime_version: "1.0.0".to_string(),
+            published_at: Some(now),
+            draft_edited_at: now,
+            published_by: Some("user@superposition.io".to_string()),
+            draft_edited_by: "user@superposition.io".to_string(),
+            last_modified_at: now,
+            last_modified_by: "user@superposition.io".to_string(),
+            published_version: Some(1),
+            test_cases: json!([{"key": "rate", "value": 1, "expected": "PASS"}]),
+        };
+
+        let bundle = function_to_bundle(function).expect("function not exported");
+        assert_eq!(
+            bundle.published_code.as_deref(),
+            Some("function validate() { return true; }")
+        );
+        assert_eq!(bundle.draft_code, "function validate() { return false; }");
+        assert_eq!(bundle.test_cases.len(), 1);
+        assert_eq!(bundle.test_cases[0].key, "rate");
+        assert_eq!(bundle.test_cases[0].expected, TestOutcome::Pass);
+    }
+
+    #[test]
+    fn test_context_to_bundle() {
+        let active_until = Utc::now() + chrono::Duration::days(1);
+        let context = Context {
+            id: "context-id".to_string(),
+            value: json!({"==": [{"var": "city"}, "Bangalore"]}),
+            override_id: "override-id".to_string(),
+            created_at: Utc::now(),
+            created_by: "user@superposition.io".to_string(),
+            priority: 1,
+            override_: json!({"per_km_rate": 22.0}),
+            last_modified_at: Utc::now().naive_utc(),
+            last_modified_by: "user@superposition.io".to_string(),
+            function_versions: json!({}),
+            active_from: None,
+            active_until: Some(active_until),
+        };
+
+        let bundle = context_to_bundle(context).expect("context not exported");
+        assert_eq!(
+            json!(bundle),
+            json!({
+                "context": {"==": [{"var": "city"}, "Bangalore"]},
+                "override": {"per_km_rate": 22.0},
+                "active_until": active_until
+            })
+        );
+    }
+
+    #[test]
+    fn test_tenant_bundle_defaults() {
+        let bundle: TenantBundle = serde_json::from_value(json!({
+            "functions": [{
+                "function_name": "positive",
+                "function_description": "checks the value is positive",
+                "draft_code": "function validate() { return true; }",
+                "draft_runtime_version": "1.0.0",
+                "published_code": null,
+                "published_runtime_version": null
+            }]
+        }))
+        .expect("bundle not parsed");
+        assert!(bundle.functions[0].test_cases.is_empty());
+        assert!(bundle.type_templates.is_empty());
+        assert!(bundle.dimensions.is_empty());
+        assert!(bundle.default_configs.is_empty());
+        assert!(bundle.contexts.is_empty());
+    }
+
+    #[test]
+    fn test_bundle_from_cac_toml() {
+        let bundle = bundle_from_cac_toml(CAC_TOML).expect("toml not converted");
+
+        let mut dimensions = bundle
+            .dimensions
+            .iter()
+            .map(|dimension| (dimension.dimension.as_str(), dimension.priority))
+            .collect::<Vec<_>>();
+        dimensions.sort_by_key(|(dimension, _)| *dimension);
+        assert_eq!(
+            dimensions
+                .iter()
+                .map(|(dimension, _)| *dimension)
+                .collect::<Vec<_>>(),
+            vec!["city", "vehicle_type"]
+        );
+        assert!(dimensions.iter().all(|(_, priority)| *priority > 0));
+        assert_ne!(dimensions[0].1, dimensions[1].1);
+
+        let rate = bundle
+            .default_configs
+            .iter()
+            .find(|default_config| default_config.key == "per_km_rate")
+            .expect("default config not converted");
+        assert_eq!(rate.value, json!(20.0));
+        assert_eq!(rate.schema, json!({"type": "number"}));
+
+        let contexts = bundle
+            .contexts
+            .into_iter()
+            .map(|context| (json!(context.context), json!(context.r#override)))
+            .collect::<Vec<_>>();
+        assert_eq!(contexts.len(), 2);
+        assert!(contexts.contains(&(
+            json!({"==": [{"var": "vehicle_type"}, "cab"]}),
+            json!({"per_km_rate": 25.0})
+        )));
+        assert!(bundle.functions.is_empty());
+        assert!(bundle.type_templates.is_empty());
+    }
+
+    #[test]
+    fn test_bundle_from_invalid_cac_toml() {
+        assert!(bundle_from_cac_toml("[default-config]\nrate = 1").is_err());
+        let invalid_expression =
+            format!("{CAC_TOML}\n[context.\"$city == \"]\nper_km_rate = 1.0\n");
+        assert!(bundle_from_cac_toml(&invalid_expression).is_err());
+    }
+
+    #[test]
+    fn test_import_summary() {
+        let mut summary = ImportSummary::default();
+        summary.record("created".to_string(), false);
+        summary.record("updated".to_string(), true);
+        assert_eq!(summary.created, vec!["created".to_string()]);
+        assert_eq!(summary.updated, vec!["updated".to_string()]);
+    }
+}
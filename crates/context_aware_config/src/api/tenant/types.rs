@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use superposition_types::{Cac, Condition, Overrides};
+
+use crate::api::functions::types::FunctionTestCase;
+
+#[derive(Serialize, Deserialize)]
+pub struct FunctionBundle {
+    pub function_name: String,
+    pub function_description: String,
+    pub draft_code: String,
+    pub draft_runtime_version: String,
+    pub published_code: Option<String>,
+    pub published_runtime_version: Option<String>,
+    #[serde(default)]
+    pub test_cases: Vec<FunctionTestCase>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TypeTemplateBundle {
+    pub type_name: String,
+    pub type_schema: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DimensionBundle {
+    pub dimension: String,
+    pub priority: i32,
+    pub schema: Value,
+    pub function_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DefaultConfigBundle {
+    pub key: String,
+    pub value: Value,
+    pub schema: Value,
+    pub function_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ContextBundle {
+    pub context: Cac<Condition>,
+    pub r#override: Cac<Overrides>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TenantBundle {
+    #[serde(default)]
+    pub functions: Vec<FunctionBundle>,
+    #[serde(default)]
+    pub type_templates: Vec<TypeTemplateBundle>,
+    #[serde(default)]
+    pub dimensions: Vec<DimensionBundle>,
+    #[serde(default)]
+    pub default_configs: Vec<DefaultConfigBundle>,
+    #[serde(default)]
+    pub contexts: Vec<ContextBundle>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportSummary {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+impl ImportSummary {
+    pub fn record(&mut self, name: String, exists: bool) {
+        if exists {
+            self.updated.push(name);
+        } else {
+            self.created.push(name);
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportResponse {
+    pub dry_run: bool,
+    pub version_id: Option<i64>,
+    pub functions: ImportSummary,
+    pub type_templates: ImportSummary,
+    pub dimensions: ImportSummary,
+    pub default_configs: ImportSummary,
+    pub contexts: ImportSummary,
+}
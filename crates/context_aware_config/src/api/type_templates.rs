@@ -1,3 +1,4 @@
 mod handlers;
+pub mod helpers;
 pub mod types;
 pub use handlers::endpoints;
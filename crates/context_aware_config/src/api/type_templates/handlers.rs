@@ -1,19 +1,41 @@
-use crate::db::models::TypeTemplates;
-use crate::db::schema::type_templates::{self, dsl};
+use std::collections::HashMap;
+
+use crate::db::models::{Context, TypeTemplates};
+use crate::db::schema::{
+    contexts::dsl::contexts,
+    default_configs,
+    type_templates::{self, dsl},
+};
+use crate::helpers::validate_context_jsonschema;
 use actix_web::web::{Json, Path, Query};
 use actix_web::{delete, get, post, put, HttpResponse, Scope};
 use chrono::Utc;
 
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use diesel::{
+    r2d2::{ConnectionManager, PooledConnection},
+    Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+};
 
-use jsonschema::JSONSchema;
+use jsonschema::{Draft, JSONSchema, ValidationError};
 use serde_json::{json, Value};
-use service_utils::service::types::DbConnection;
-use superposition_macros::{bad_argument, db_error};
+use service_utils::{helpers::validation_err_to_str, service::types::DbConnection};
+use superposition_macros::{bad_argument, db_error, not_found, validation_error};
 use superposition_types::{result as superposition, User};
 
-use crate::api::type_templates::types::{
-    QueryFilters, TypeTemplateName, TypeTemplateRequest,
+use crate::api::{
+    context::{
+        helpers::{
+            find_dimension_violations, find_override_violations, violations_error,
+        },
+        ContextViolation, RevalidationQuery,
+    },
+    dimension::compile_dimension_schema,
+    type_templates::{
+        helpers::{get_type_templates_map, get_type_usages, resolve_type_refs},
+        types::{
+            QueryFilters, TypeTemplateName, TypeTemplateRequest, TypeTemplateUsages,
+        },
+    },
 };
 
 pub fn endpoints() -> Scope {
@@ -22,19 +44,22 @@ pub fn endpoints() -> Scope {
         .service(create_type)
         .service(update_type)
         .service(delete_type)
+        .service(list_type_usages)
 }
 
-#[post("")]
-async fn create_type(
-    request: Json<TypeTemplateRequest>,
-    db_conn: DbConnection,
-    user: User,
-) -> superposition::Result<HttpResponse> {
-    let DbConnection(mut conn) = db_conn;
-    let _ = JSONSchema::compile(&request.type_schema).map_err(|err| {
+// schemas of templates can reference other templates, resolved as if `type_schema` was saved
+fn compile_type_schema(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    type_name: &str,
+    type_schema: &Value,
+) -> superposition::Result<()> {
+    let mut templates = get_type_templates_map(conn)?;
+    templates.insert(type_name.to_string(), type_schema.clone());
+    let resolved_schema = resolve_type_refs(type_schema, &templates)?;
+    let _ = JSONSchema::compile(&resolved_schema).map_err(|err| {
         log::error!(
             "Invalid jsonschema sent in the request, schema: {:?} error: {}",
-            request.type_schema,
+            type_schema,
             err
         );
         bad_argument!(
@@ -42,7 +67,18 @@ async fn create_type(
             err.to_string()
         )
     })?;
+    Ok(())
+}
+
+#[post("")]
+async fn create_type(
+    request: Json<TypeTemplateRequest>,
+    db_conn: DbConnection,
+    user: User,
+) -> superposition::Result<HttpResponse> {
+    let DbConnection(mut conn) = db_conn;
     let type_name: String = request.type_name.clone().into();
+    compile_type_schema(&mut conn, &type_name, &request.type_schema)?;
     let type_template = diesel::insert_into(type_templates::table)
         .values((
             type_templates::type_schema.eq(request.type_schema.clone()),
@@ -62,39 +98,138 @@ async fn create_type(
 async fn update_type(
     request: Json<Value>,
     path: Path<TypeTemplateName>,
+    query: Query<RevalidationQuery>,
     db_conn: DbConnection,
     user: User,
 ) -> superposition::Result<HttpResponse> {
     let DbConnection(mut conn) = db_conn;
-    let _ = JSONSchema::compile(&request).map_err(|err| {
-        log::error!(
-            "Invalid jsonschema sent in the request, schema: {:?} error: {}",
-            request,
-            err
-        );
-        bad_argument!(
-            "Invalid jsonschema sent in the request, validation error is: {}",
-            err.to_string()
-        )
-    })?;
     let type_name: String = path.into_inner().into();
+    compile_type_schema(&mut conn, &type_name, &request)?;
 
     let timestamp = Utc::now().naive_utc();
-    let updated_type = diesel::update(type_templates::table)
-        .filter(type_templates::type_name.eq(type_name))
-        .set((
-            type_templates::type_schema.eq(request.clone()),
-            type_templates::last_modified_at.eq(timestamp),
-            type_templates::last_modified_by.eq(user.email),
-        ))
-        .get_result::<TypeTemplates>(&mut conn)
-        .map_err(|err| {
-            log::error!("failed to insert custom type with error: {}", err);
-            db_error!(err)
+    let updated_type =
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            let updated_type = diesel::update(type_templates::table)
+                .filter(type_templates::type_name.eq(&type_name))
+                .set((
+                    type_templates::type_schema.eq(request.clone()),
+                    type_templates::last_modified_at.eq(timestamp),
+                    type_templates::last_modified_by.eq(user.email.clone()),
+                ))
+                .get_result::<TypeTemplates>(transaction_conn)
+                .map_err(|err| {
+                    log::error!("failed to insert custom type with error: {}", err);
+                    db_error!(err)
+                })?;
+
+            // the users of the type are checked against the updated template
+            let templates = get_type_templates_map(transaction_conn)?;
+            let usages = get_type_usages(transaction_conn, &type_name, &templates)?;
+            let violations = validate_type_usages(transaction_conn, &usages, &templates)?;
+            if !violations.is_empty() {
+                if !query.force {
+                    return Err(violations_error(
+                        format!("Updating type {type_name}"),
+                        &violations,
+                    ));
+                }
+                log::warn!(
+                    "type {type_name} updated by {} with {} context values violating it",
+                    user.email,
+                    violations.len()
+                );
+            }
+            Ok(updated_type)
         })?;
     Ok(HttpResponse::Ok().json(updated_type))
 }
 
+// dimension schemas are compiled the way dimensions compile them, with their formats
+fn compile_resolved(
+    name: &String,
+    schema: &Value,
+    templates: &HashMap<String, Value>,
+    is_dimension: bool,
+) -> superposition::Result<JSONSchema> {
+    let resolved_schema = resolve_type_refs(schema, templates)?;
+    let compiled = if is_dimension {
+        compile_dimension_schema(&resolved_schema)
+    } else {
+        JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .compile(&resolved_schema)
+            .map_err(|err| err.to_string())
+    };
+    compiled.map_err(|err| {
+        log::info!("Failed to compile schema of {name} as a Draft-7 JSON schema: {err}");
+        bad_argument!("Schema of {} fails to compile with the updated type", name)
+    })
+}
+
+/*
+  Default values of keys using the type must keep satisfying their schema, while
+  overrides and dimension values in contexts are returned as violations.
+*/
+fn validate_type_usages(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    usages: &TypeTemplateUsages,
+    templates: &HashMap<String, Value>,
+) -> superposition::Result<Vec<ContextViolation>> {
+    use crate::db::schema::dimensions;
+
+    if usages.default_configs.is_empty() && usages.dimensions.is_empty() {
+        return Ok(Vec::new());
+    }
+    let all_contexts: Vec<Context> = contexts.load(conn)?;
+    let mut violations = Vec::new();
+
+    let default_config_rows: Vec<(String, Value, Value)> =
+        default_configs::dsl::default_configs
+            .filter(default_configs::key.eq_any(usages.default_configs.clone()))
+            .select((
+                default_configs::key,
+                default_configs::value,
+                default_configs::schema,
+            ))
+            .load(conn)?;
+    for (key, value, schema) in default_config_rows {
+        let jschema = compile_resolved(&key, &schema, templates, false)?;
+        let validate = |value: &Value| {
+            jschema.validate(value).map_err(|e| {
+                let verrors = e.collect::<Vec<ValidationError>>();
+                validation_error!(
+                    "schema validation failed for {key}: {}",
+                    validation_err_to_str(verrors)
+                        .first()
+                        .unwrap_or(&String::new())
+                )
+            })
+        };
+        validate(&value)?;
+        violations.extend(find_override_violations(&all_contexts, &key, validate));
+    }
+
+    let dimension_rows: Vec<(String, Value)> = dimensions::dsl::dimensions
+        .filter(dimensions::dimension.eq_any(usages.dimensions.clone()))
+        .select((dimensions::dimension, dimensions::schema))
+        .load(conn)?;
+    for (dimension, schema) in dimension_rows {
+        let jschema = compile_resolved(&dimension, &schema, templates, true)?;
+        violations.extend(find_dimension_violations(
+            &all_contexts,
+            &dimension,
+            |constraint| {
+                validate_context_jsonschema(
+                    &constraint.operator.to_string(),
+                    &constraint.value,
+                    &jschema,
+                )
+            },
+        )?);
+    }
+    Ok(violations)
+}
+
 #[delete("/{type_name}")]
 async fn delete_type(
     path: Path<TypeTemplateName>,
@@ -103,16 +238,36 @@ async fn delete_type(
 ) -> superposition::Result<HttpResponse> {
     let DbConnection(mut conn) = db_conn;
     let type_name: String = path.into_inner().into();
-    diesel::update(dsl::type_templates)
-        .filter(dsl::type_name.eq(type_name.clone()))
-        .set((
-            dsl::last_modified_at.eq(Utc::now().naive_utc()),
-            dsl::last_modified_by.eq(user.email),
-        ))
-        .execute(&mut conn)?;
     let deleted_type =
-        diesel::delete(dsl::type_templates.filter(dsl::type_name.eq(type_name)))
-            .get_result::<TypeTemplates>(&mut conn)?;
+        conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+            dsl::type_templates
+                .filter(dsl::type_name.eq(&type_name))
+                .select(dsl::type_name)
+                .for_update()
+                .first::<String>(transaction_conn)?;
+            let templates = get_type_templates_map(transaction_conn)?;
+            let usages = get_type_usages(transaction_conn, &type_name, &templates)?;
+            if !usages.is_empty() {
+                return Err(bad_argument!(
+                    "Type {} is in use by types: [{}], default configs: [{}], dimensions: [{}]",
+                    type_name,
+                    usages.type_templates.join(", "),
+                    usages.default_configs.join(", "),
+                    usages.dimensions.join(", ")
+                ));
+            }
+            diesel::update(dsl::type_templates)
+                .filter(dsl::type_name.eq(&type_name))
+                .set((
+                    dsl::last_modified_at.eq(Utc::now().naive_utc()),
+                    dsl::last_modified_by.eq(user.email.clone()),
+                ))
+                .execute(transaction_conn)?;
+            Ok(diesel::delete(
+                dsl::type_templates.filter(dsl::type_name.eq(&type_name)),
+            )
+            .get_result::<TypeTemplates>(transaction_conn)?)
+        })?;
     Ok(HttpResponse::Ok().json(deleted_type))
 }
 
@@ -145,3 +300,17 @@ async fn list_types(
     "data": custom_types
     })))
 }
+
+#[get("/{type_name}/usages")]
+async fn list_type_usages(
+    path: Path<TypeTemplateName>,
+    db_conn: DbConnection,
+) -> superposition::Result<Json<TypeTemplateUsages>> {
+    let DbConnection(mut conn) = db_conn;
+    let type_name: String = path.into_inner().into();
+    let templates = get_type_templates_map(&mut conn)?;
+    if !templates.contains_key(&type_name) {
+        return Err(not_found!("Type {} doesn't exist", type_name));
+    }
+    Ok(Json(get_type_usages(&mut conn, &type_name, &templates)?))
+}
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::{
+    r2d2::{ConnectionManager, PooledConnection},
+    PgConnection, QueryDsl, RunQueryDsl,
+};
+use serde_json::{Map, Value};
+use superposition_macros::bad_argument;
+use superposition_types::result as superposition;
+
+use crate::{
+    api::type_templates::types::TypeTemplateUsages,
+    db::schema::{default_configs, dimensions, type_templates::dsl},
+};
+
+const TYPE_REF_PREFIX: &str = "types/";
+
+type DBConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+fn type_ref(schema: &Map<String, Value>) -> Option<&str> {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix(TYPE_REF_PREFIX))
+}
+
+// names of the templates referenced directly in `schema`
+fn collect_type_refs(schema: &Value, refs: &mut HashSet<String>) {
+    match schema {
+        Value::Object(object) => {
+            if let Some(type_name) = type_ref(object) {
+                refs.insert(type_name.to_string());
+            }
+            object
+                .values()
+                .for_each(|value| collect_type_refs(value, refs));
+        }
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_type_refs(value, refs)),
+        _ => (),
+    }
+}
+
+/// Templates `schema` depends on, including the ones referenced by other templates.
+pub fn type_dependencies(
+    schema: &Value,
+    templates: &HashMap<String, Value>,
+) -> HashSet<String> {
+    let mut dependencies = HashSet::new();
+    let mut pending = HashSet::new();
+    collect_type_refs(schema, &mut pending);
+    while let Some(type_name) = pending.iter().next().cloned() {
+        pending.remove(&type_name);
+        if let Some(template) = templates.get(&type_name) {
+            let mut refs = HashSet::new();
+            collect_type_refs(template, &mut refs);
+            pending.extend(refs.difference(&dependencies).cloned());
+        }
+        dependencies.insert(type_name);
+    }
+    dependencies
+}
+
+pub fn get_type_templates_map(
+    conn: &mut DBConnection,
+) -> superposition::Result<HashMap<String, Value>> {
+    let templates: Vec<(String, Value)> = dsl::type_templates
+        .select((dsl::type_name, dsl::type_schema))
+        .load(conn)?;
+    Ok(templates.into_iter().collect())
+}
+
+fn resolve_with_stack(
+    schema: &Value,
+    templates: &HashMap<String, Value>,
+    stack: &mut Vec<String>,
+) -> superposition::Result<Value> {
+    match schema {
+        Value::Object(object) => {
+            if let Some(type_name) = type_ref(object) {
+                if stack.iter().any(|name| name == type_name) {
+                    return Err(bad_argument!(
+                        "Type {} references itself through {}",
+                        type_name,
+                        stack.join(" -> ")
+                    ));
+                }
+                let template = templates
+                    .get(type_name)
+                    .ok_or_else(|| bad_argument!("Type {} doesn't exist", type_name))?;
+                stack.push(type_name.to_string());
+                let resolved = resolve_with_stack(template, templates, stack);
+                stack.pop();
+                return resolved;
+            }
+            object
+                .iter()
+                .map(|(key, value)| {
+                    Ok((key.clone(), resolve_with_stack(value, templates, stack)?))
+                })
+                .collect::<superposition::Result<Map<String, Value>>>()
+                .map(Value::Object)
+        }
+        Value::Array(values) => values
+            .iter()
+            .map(|value| resolve_with_stack(value, templates, stack))
+            .collect::<superposition::Result<Vec<Value>>>()
+            .map(Value::Array),
+        _ => Ok(schema.clone()),
+    }
+}
+
+/*
+  Schemas can reference a type template with `{"$ref": "types/<type_name>"}`,
+  which is replaced by the current schema of the template, so that changes to
+  the template reach every schema using it. Like any other `$ref`, keywords next
+  to it are ignored.
+*/
+pub fn resolve_type_refs(
+    schema: &Value,
+    templates: &HashMap<String, Value>,
+) -> superposition::Result<Value> {
+    resolve_with_stack(schema, templates, &mut Vec::new())
+}
+
+/// Resolves the type references in `schema`, loading templates only when needed.
+pub fn resolve_schema(
+    conn: &mut DBConnection,
+    schema: &Value,
+) -> superposition::Result<Value> {
+    let mut refs = HashSet::new();
+    collect_type_refs(schema, &mut refs);
+    if refs.is_empty() {
+        return Ok(schema.clone());
+    }
+    resolve_type_refs(schema, &get_type_templates_map(conn)?)
+}
+
+pub fn get_type_usages(
+    conn: &mut DBConnection,
+    type_name: &String,
+    templates: &HashMap<String, Value>,
+) -> superposition::Result<TypeTemplateUsages> {
+    let uses_type =
+        |schema: &Value| type_dependencies(schema, templates).contains(type_name);
+    let default_config_schemas: Vec<(String, Value)> =
+        default_configs::dsl::default_configs
+            .select((default_configs::key, default_configs::schema))
+            .load(conn)?;
+    let dimension_schemas: Vec<(String, Value)> = dimensions::dsl::dimensions
+        .select((dimensions::dimension, dimensions::schema))
+        .load(conn)?;
+
+    let users = |schemas: Vec<(String, Value)>| {
+        schemas
+            .into_iter()
+            .filter_map(|(name, schema)| uses_type(&schema).then_some(name))
+            .collect::<Vec<_>>()
+    };
+    Ok(TypeTemplateUsages {
+        type_templates: templates
+            .iter()
+            .filter(|(name, schema)| *name != type_name && uses_type(schema))
+            .map(|(name, _)| name.clone())
+            .collect(),
+        default_configs: users(default_config_schemas),
+        dimensions: users(dimension_schemas),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_type_refs() {
+        let templates = HashMap::from([
+            (
+                "Percentage".to_string(),
+                json!({"type": "integer", "minimum": 0, "maximum": 100}),
+            ),
+            (
+                "Split".to_string(),
+                json!({"type": "array", "items": {"$ref": "types/Percentage"}}),
+            ),
+            ("Loop".to_string(), json!({"$ref": "types/Loop"})),
+        ]);
+
+        assert_eq!(
+            resolve_type_refs(&json!({"$ref": "types/Split"}), &templates).ok(),
+            Some(json!({
+                "type": "array",
+                "items": {"type": "integer", "minimum": 0, "maximum": 100}
+            }))
+        );
+        // references outside of the templates are left as they are
+        assert_eq!(
+            resolve_type_refs(&json!({"$ref": "#/definitions/a"}), &templates).ok(),
+            Some(json!({"$ref": "#/definitions/a"}))
+        );
+        assert!(
+            resolve_type_refs(&json!({"$ref": "types/Missing"}), &templates).is_err()
+        );
+        assert!(resolve_type_refs(&json!({"$ref": "types/Loop"}), &templates).is_err());
+
+        assert_eq!(
+            type_dependencies(&json!({"$ref": "types/Split"}), &templates),
+            HashSet::from(["Split".to_string(), "Percentage".to_string()])
+        );
+    }
+}
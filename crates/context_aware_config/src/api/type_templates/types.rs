@@ -17,6 +17,21 @@ pub struct TypeTemplateResponse {
     pub last_modified: String,
     pub created_by: String,
 }
+#[derive(Serialize, Default)]
+pub struct TypeTemplateUsages {
+    pub type_templates: Vec<String>,
+    pub default_configs: Vec<String>,
+    pub dimensions: Vec<String>,
+}
+
+impl TypeTemplateUsages {
+    pub fn is_empty(&self) -> bool {
+        self.type_templates.is_empty()
+            && self.default_configs.is_empty()
+            && self.dimensions.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct QueryFilters {
     pub count: Option<i64>,
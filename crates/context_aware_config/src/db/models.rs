@@ -1,10 +1,12 @@
 use crate::db::schema::{
-    config_versions, contexts, default_configs, dimensions, event_log, functions,
+    archived_contexts, change_request_comments, change_requests, config_versions,
+    contexts, default_config_aliases, default_config_deprecations, default_configs,
+    dimension_aliases, dimensions, event_log, function_versions, functions,
     type_templates,
 };
 use chrono::{offset::Utc, DateTime, NaiveDateTime};
-use diesel::{AsChangeset, Insertable, Queryable, Selectable};
-use serde::Serialize;
+use diesel::{query_builder::QueryId, AsChangeset, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Serialize, Debug)]
@@ -21,6 +23,30 @@ pub struct Context {
     pub override_: Value,
     pub last_modified_at: NaiveDateTime,
     pub last_modified_by: String,
+    pub function_versions: Value,
+    pub active_from: Option<DateTime<Utc>>,
+    pub active_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Serialize, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(id, archived_at))]
+pub struct ArchivedContext {
+    pub id: String,
+    pub value: Value,
+    pub override_id: String,
+    pub created_at: DateTime<Utc>,
+    pub created_by: String,
+    pub priority: i32,
+    #[serde(rename(serialize = "override"))]
+    pub override_: Value,
+    pub last_modified_at: NaiveDateTime,
+    pub last_modified_by: String,
+    pub function_versions: Value,
+    pub active_from: Option<DateTime<Utc>>,
+    pub active_until: Option<DateTime<Utc>>,
+    pub archived_at: NaiveDateTime,
+    pub archived_by: String,
 }
 
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Serialize)]
@@ -38,6 +64,18 @@ pub struct Dimension {
     pub last_modified_by: String,
 }
 
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = dimension_aliases)]
+#[diesel(primary_key(alias))]
+pub struct DimensionAlias {
+    pub alias: String,
+    pub dimension: String,
+    pub expires_at: NaiveDateTime,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+}
+
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Serialize, Clone)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[diesel(primary_key(key))]
@@ -53,6 +91,30 @@ pub struct DefaultConfig {
     pub last_modified_by: String,
 }
 
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(table_name = default_config_aliases)]
+#[diesel(primary_key(alias))]
+pub struct DefaultConfigAlias {
+    pub alias: String,
+    pub key: String,
+    pub expires_at: NaiveDateTime,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Serialize, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(key))]
+#[diesel(treat_none_as_null = true)]
+pub struct DefaultConfigDeprecation {
+    pub key: String,
+    pub sunset_at: NaiveDateTime,
+    pub replacement_key: Option<String>,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+}
+
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Serialize, Clone, Debug)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 #[diesel(primary_key(name))]
@@ -69,6 +131,21 @@ pub struct Function {
     pub draft_edited_by: String,
     pub last_modified_at: NaiveDateTime,
     pub last_modified_by: String,
+    pub published_version: Option<i32>,
+    pub test_cases: Value,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(function_name, version))]
+pub struct FunctionVersion {
+    pub function_name: String,
+    pub version: i32,
+    pub code: String,
+    pub runtime_version: String,
+    pub description: String,
+    pub published_by: String,
+    pub published_at: NaiveDateTime,
 }
 
 #[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
@@ -109,3 +186,50 @@ pub struct TypeTemplates {
     pub last_modified_at: NaiveDateTime,
     pub last_modified_by: String,
 }
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    diesel_derive_enum::DbEnum,
+    QueryId,
+)]
+#[DbValueStyle = "UPPERCASE"]
+#[ExistingTypePath = "crate::db::schema::sql_types::ChangeRequestStatus"]
+pub enum ChangeRequestStatus {
+    PENDING,
+    APPLIED,
+    REJECTED,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(id))]
+pub struct ChangeRequest {
+    pub id: i64,
+    pub status: ChangeRequestStatus,
+    pub change: Value,
+    pub diff: Value,
+    pub config_tags: Option<Vec<String>>,
+    pub reviewers: Vec<String>,
+    pub author: Value,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub config_version: Option<i64>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Clone, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(id))]
+pub struct ChangeRequestComment {
+    pub id: i64,
+    pub change_request_id: i64,
+    pub comment: String,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+}
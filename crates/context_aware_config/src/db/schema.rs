@@ -1,5 +1,62 @@
 // @generated automatically by Diesel CLI.
 
+pub mod sql_types {
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "change_request_status"))]
+    pub struct ChangeRequestStatus;
+}
+
+diesel::table! {
+    archived_contexts (id, archived_at) {
+        id -> Varchar,
+        value -> Json,
+        override_id -> Varchar,
+        created_at -> Timestamptz,
+        created_by -> Varchar,
+        priority -> Int4,
+        #[sql_name = "override"]
+        override_ -> Json,
+        last_modified_at -> Timestamp,
+        #[max_length = 200]
+        last_modified_by -> Varchar,
+        function_versions -> Json,
+        active_from -> Nullable<Timestamptz>,
+        active_until -> Nullable<Timestamptz>,
+        archived_at -> Timestamp,
+        archived_by -> Varchar,
+    }
+}
+
+diesel::table! {
+    change_request_comments (id) {
+        id -> Int8,
+        change_request_id -> Int8,
+        comment -> Text,
+        created_by -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ChangeRequestStatus;
+
+    change_requests (id) {
+        id -> Int8,
+        status -> ChangeRequestStatus,
+        change -> Json,
+        diff -> Json,
+        config_tags -> Nullable<Array<Varchar>>,
+        reviewers -> Array<Varchar>,
+        author -> Json,
+        created_by -> Varchar,
+        created_at -> Timestamp,
+        reviewed_by -> Nullable<Varchar>,
+        reviewed_at -> Nullable<Timestamp>,
+        config_version -> Nullable<Int8>,
+    }
+}
+
 diesel::table! {
     config_versions (id) {
         id -> Int8,
@@ -23,6 +80,29 @@ diesel::table! {
         last_modified_at -> Timestamp,
         #[max_length = 200]
         last_modified_by -> Varchar,
+        function_versions -> Json,
+        active_from -> Nullable<Timestamptz>,
+        active_until -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    default_config_aliases (alias) {
+        alias -> Varchar,
+        key -> Varchar,
+        expires_at -> Timestamp,
+        created_by -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    default_config_deprecations (key) {
+        key -> Varchar,
+        sunset_at -> Timestamp,
+        replacement_key -> Nullable<Varchar>,
+        created_by -> Varchar,
+        created_at -> Timestamp,
     }
 }
 
@@ -40,6 +120,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    dimension_aliases (alias) {
+        alias -> Varchar,
+        dimension -> Varchar,
+        expires_at -> Timestamp,
+        created_by -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     dimensions (dimension) {
         dimension -> Varchar,
@@ -617,6 +707,21 @@ diesel::table! {
         last_modified_at -> Timestamp,
         #[max_length = 200]
         last_modified_by -> Varchar,
+        published_version -> Nullable<Int4>,
+        test_cases -> Json,
+    }
+}
+
+diesel::table! {
+    function_versions (function_name, version) {
+        function_name -> Text,
+        version -> Int4,
+        code -> Text,
+        #[max_length = 16]
+        runtime_version -> Varchar,
+        description -> Text,
+        published_by -> Text,
+        published_at -> Timestamp,
     }
 }
 
@@ -632,13 +737,23 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(change_request_comments -> change_requests (change_request_id));
+diesel::joinable!(default_config_aliases -> default_configs (key));
 diesel::joinable!(default_configs -> functions (function_name));
+diesel::joinable!(dimension_aliases -> dimensions (dimension));
 diesel::joinable!(dimensions -> functions (function_name));
+diesel::joinable!(function_versions -> functions (function_name));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    archived_contexts,
+    change_request_comments,
+    change_requests,
     config_versions,
     contexts,
+    default_config_aliases,
+    default_config_deprecations,
     default_configs,
+    dimension_aliases,
     dimensions,
     event_log,
     event_log_y2023m08,
@@ -682,6 +797,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     event_log_y2026m10,
     event_log_y2026m11,
     event_log_y2026m12,
+    function_versions,
     functions,
     type_templates,
 );
@@ -1,5 +1,8 @@
 use crate::{
-    api::config::types::{Config, Context},
+    api::{
+        config::types::{Config, Context},
+        default_config::get_deprecated_keys,
+    },
     db::{
         models::ConfigVersion,
         schema::{
@@ -10,25 +13,31 @@ use crate::{
     },
 };
 use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
-use actix_web::web::Data;
-use chrono::Utc;
+use actix_web::rt::time::interval;
+use actix_web::web::{self, Data};
+use actix_web::HttpRequest;
+use chrono::{DateTime, Utc};
 use diesel::{
     r2d2::{ConnectionManager, PooledConnection},
-    ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, RunQueryDsl,
 };
 
+use cac_client::MergeStrategy;
 use itertools::{self, Itertools};
 use jsonschema::{Draft, JSONSchema, ValidationError};
 use serde_json::{json, Map, Value};
 use service_utils::{
     helpers::{generate_snowflake_id, validation_err_to_str},
-    service::types::AppState,
+    service::types::{AppExecutionNamespace, AppScope, AppState, Tenant},
 };
 
 use superposition_macros::{db_error, unexpected_error, validation_error};
 use superposition_types::{result as superposition, Cac, Condition, Overrides};
 
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::watch;
+
+const CONFIG_VERSION_WATCH_INTERVAL: Duration = Duration::from_secs(1);
 
 pub fn parse_headermap_safe(headermap: &HeaderMap) -> HashMap<String, String> {
     let mut req_headers = HashMap::new();
@@ -66,6 +75,37 @@ pub fn get_meta_schema() -> JSONSchema {
         .expect("Error encountered: Failed to compile 'context_dimension_schema_value'. Ensure it adheres to the correct format and data type.")
 }
 
+// experiments of the tenant live in the namespace of the experimentation service
+pub fn experimentation_namespace(
+    state: &AppState,
+    tenant: Option<&Tenant>,
+) -> superposition::Result<AppExecutionNamespace> {
+    AppExecutionNamespace::for_scope(state, tenant, AppScope::EXPERIMENTATION).ok_or_else(
+        || {
+            log::error!("experimentation_namespace: Tenant not set for the request");
+            unexpected_error!("Something went wrong, failed to find the experiments")
+        },
+    )
+}
+
+/// The CAC namespace of every tenant along with its experimentation namespace.
+pub fn tenant_namespaces(state: &AppState) -> Vec<(String, String)> {
+    if state.enable_tenant_and_scope {
+        state
+            .tenants
+            .iter()
+            .map(|tenant| {
+                (
+                    format!("{}_{}", tenant, AppScope::CAC),
+                    format!("{}_{}", tenant, AppScope::EXPERIMENTATION),
+                )
+            })
+            .collect()
+    } else {
+        vec![(String::from("cac_v1"), String::from("cac_v1"))]
+    }
+}
+
 pub fn validate_context_jsonschema(
     object_key: &str,
     dimension_value: &Value,
@@ -185,7 +225,7 @@ pub fn json_to_sorted_string(v: &Value) -> String {
 pub fn calculate_context_priority(
     _object_key: &str,
     cond: &Value,
-    dimension_schema_map: &HashMap<String, (JSONSchema, i32)>,
+    dimension_schema_map: &HashMap<String, (JSONSchema, i32, bool)>,
 ) -> Result<i32, String> {
     let get_priority = |key: &str, val: &Value| -> Result<i32, String> {
         if key == "var" {
@@ -193,7 +233,7 @@ pub fn calculate_context_priority(
                 val.as_str().ok_or("failed to decode dimension as str")?;
             dimension_schema_map
                 .get(dimension_name)
-                .map(|(_, priority)| priority)
+                .map(|(_, priority, _)| priority)
                 .ok_or(String::from(
                     "No matching `dimension` found in dimension table",
                 ))
@@ -215,9 +255,36 @@ pub fn calculate_context_priority(
     }
 }
 
+/// Whether a context with the given active window is live at `now`, the window
+/// is open at `active_from` and closed at `active_until`.
+pub fn is_active_at(
+    active_from: Option<DateTime<Utc>>,
+    active_until: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> bool {
+    active_from.map_or(true, |active_from| active_from <= now)
+        && active_until.map_or(true, |active_until| active_until > now)
+}
+
+type ContextRow = (
+    String,
+    Value,
+    i32,
+    String,
+    Value,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+);
+
+/*
+  Only contexts whose active window covers the current time make it into the
+  config, the scheduler generates a new version whenever a window opens or
+  closes.
+*/
 pub fn generate_cac(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
 ) -> superposition::Result<Config> {
+    let now = Utc::now();
     let contexts_vec = ctxt::contexts
         .select((
             ctxt::id,
@@ -225,9 +292,11 @@ pub fn generate_cac(
             ctxt::priority,
             ctxt::override_id,
             ctxt::override_,
+            ctxt::active_from,
+            ctxt::active_until,
         ))
         .order_by((ctxt::priority.asc(), ctxt::created_at.asc()))
-        .load::<(String, Value, i32, String, Value)>(conn)
+        .load::<ContextRow>(conn)
         .map_err(|err| {
             log::error!("failed to fetch contexts with error: {}", err);
             db_error!(err)
@@ -236,7 +305,12 @@ pub fn generate_cac(
     let mut contexts = Vec::new();
     let mut overrides: HashMap<String, Overrides> = HashMap::new();
 
-    for (id, condition, priority_, override_id, override_) in contexts_vec.iter() {
+    for (id, condition, priority_, override_id, override_, ..) in contexts_vec
+        .iter()
+        .filter(|(.., active_from, active_until)| {
+            is_active_at(*active_from, *active_until, now)
+        })
+    {
         let condition = Cac::<Condition>::try_from_db(
             condition.as_object().unwrap_or(&Map::new()).clone(),
         )
@@ -287,6 +361,34 @@ pub fn generate_cac(
     })
 }
 
+pub fn to_cac_client_config(
+    config: &Config,
+) -> (Vec<cac_client::Context>, Map<String, Value>) {
+    let cac_client_contexts = config
+        .contexts
+        .iter()
+        .map(|val| cac_client::Context {
+            id: val.id.clone(),
+            condition: json!(val.condition),
+            override_with_keys: val.override_with_keys.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut override_map = Map::new();
+    for (key, val) in config.overrides.iter() {
+        override_map.insert(key.to_owned(), json!(val));
+    }
+    (cac_client_contexts, override_map)
+}
+
+pub fn get_merge_strategy(req: &HttpRequest) -> MergeStrategy {
+    req.headers()
+        .get("x-merge-strategy")
+        .and_then(|header_value: &HeaderValue| header_value.to_str().ok())
+        .and_then(|val| MergeStrategy::from_str(val).ok())
+        .unwrap_or_default()
+}
+
 pub fn add_config_version(
     state: &Data<AppState>,
     tags: Option<Vec<String>>,
@@ -296,7 +398,14 @@ pub fn add_config_version(
     let version_id = generate_snowflake_id(state)?;
     let config = generate_cac(db_conn)?;
     let json_config = json!(config);
-    let config_hash = blake3::hash(json_config.to_string().as_bytes()).to_string();
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(json_config.to_string().as_bytes());
+    // deprecations are served along with the config, so they change its hash too
+    let deprecations = get_deprecated_keys(db_conn)?;
+    if !deprecations.is_empty() {
+        hasher.update(json!(deprecations).to_string().as_bytes());
+    }
+    let config_hash = hasher.finalize().to_string();
     let config_version = ConfigVersion {
         id: version_id,
         config: json_config,
@@ -310,6 +419,112 @@ pub fn add_config_version(
     Ok(version_id)
 }
 
+pub fn get_latest_config_version_id(
+    db_conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Option<i64>> {
+    config_versions::table
+        .select(config_versions::id)
+        .order(config_versions::created_at.desc())
+        .first::<i64>(db_conn)
+        .optional()
+        .map_err(|err| {
+            log::error!("failed to fetch latest config version with error: {err}");
+            db_error!(err)
+        })
+}
+
+// diesel is blocking, the query runs on the blocking thread pool
+async fn fetch_latest_config_version_id(
+    state: &Data<AppState>,
+    namespace: &str,
+) -> superposition::Result<Option<i64>> {
+    let state = state.clone();
+    let namespace = namespace.to_string();
+    web::block(move || {
+        let mut db_conn = state.db_pool.get_conn(namespace.clone()).map_err(|err| {
+            log::error!("failed to get db connection for {namespace}: {err}");
+            unexpected_error!("Something went wrong")
+        })?;
+        get_latest_config_version_id(&mut db_conn)
+    })
+    .await
+    .map_err(|err| {
+        log::error!("failed to fetch latest config version: {err}");
+        unexpected_error!("Something went wrong")
+    })?
+}
+
+/*
+  Config versions are watched per namespace by polling the latest committed
+  version, so that subscribers are notified of changes made by any instance.
+  A single watcher is shared by all subscribers of a namespace and stops once
+  the last subscriber goes away.
+*/
+pub async fn subscribe_to_config_versions(
+    state: &Data<AppState>,
+    namespace: String,
+) -> superposition::Result<watch::Receiver<Option<i64>>> {
+    let lock_watchers = || {
+        state.config_version_watchers.lock().map_err(|err| {
+            log::error!("failed to acquire config version watchers lock: {err}");
+            unexpected_error!("Something went wrong")
+        })
+    };
+
+    if let Some(sender) = lock_watchers()?.get(&namespace) {
+        return Ok(sender.subscribe());
+    }
+
+    // the lock is not held while the database is queried
+    let latest_version = fetch_latest_config_version_id(state, &namespace).await?;
+    let mut watchers = lock_watchers()?;
+    // another subscriber could have started the watcher in the meantime
+    if let Some(sender) = watchers.get(&namespace) {
+        return Ok(sender.subscribe());
+    }
+    let (sender, receiver) = watch::channel(latest_version);
+    let sender = Arc::new(sender);
+    watchers.insert(namespace.clone(), sender.clone());
+    actix_web::rt::spawn(watch_config_versions(state.clone(), namespace, sender));
+    Ok(receiver)
+}
+
+async fn watch_config_versions(
+    state: Data<AppState>,
+    namespace: String,
+    sender: Arc<watch::Sender<Option<i64>>>,
+) {
+    let mut ticker = interval(CONFIG_VERSION_WATCH_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if sender.receiver_count() == 0 {
+            let Ok(mut watchers) = state.config_version_watchers.lock() else {
+                continue;
+            };
+            // a subscriber could have joined before the lock was acquired
+            if sender.receiver_count() == 0 {
+                watchers.remove(&namespace);
+                log::info!("stopped watching config versions for {namespace}");
+                break;
+            }
+            continue;
+        }
+
+        match fetch_latest_config_version_id(&state, &namespace).await {
+            Ok(latest_version) => {
+                sender.send_if_modified(|current_version| {
+                    let modified = *current_version != latest_version;
+                    *current_version = latest_version;
+                    modified
+                });
+            }
+            Err(err) => {
+                log::error!("failed to watch config versions for {namespace}: {err}")
+            }
+        }
+    }
+}
+
 // ************ Tests *************
 
 #[cfg(test)]
@@ -421,4 +636,19 @@ mod tests {
         assert!(err_arr_context);
         assert!(ok_arr_context.is_ok());
     }
+
+    #[test]
+    fn test_is_active_at() {
+        let now = Utc::now();
+        let earlier = now - chrono::Duration::hours(1);
+        let later = now + chrono::Duration::hours(1);
+
+        assert!(is_active_at(None, None, now));
+        assert!(is_active_at(Some(earlier), None, now));
+        assert!(is_active_at(Some(now), Some(later), now));
+        assert!(is_active_at(None, Some(later), now));
+        assert!(!is_active_at(Some(later), None, now));
+        assert!(!is_active_at(Some(earlier), Some(now), now));
+        assert!(!is_active_at(None, Some(earlier), now));
+    }
 }
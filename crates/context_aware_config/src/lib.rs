@@ -3,4 +3,5 @@ pub mod api;
 pub mod db;
 pub mod helpers;
 pub mod middlewares;
+pub mod scheduler;
 pub mod validation_functions;
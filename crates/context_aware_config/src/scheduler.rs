@@ -0,0 +1,403 @@
+use std::{collections::HashSet, time::Duration};
+
+use actix_web::{
+    rt::time::interval,
+    web::{self, Data},
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::{
+    r2d2::{ConnectionManager, PooledConnection},
+    sql_types::{Bool, Json, Text},
+    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension,
+    PgConnection, QueryDsl, QueryableByName, RunQueryDsl,
+};
+use itertools::Itertools;
+use serde_json::{json, Value};
+use service_utils::service::types::AppState;
+use superposition_macros::unexpected_error;
+use superposition_types::result as superposition;
+
+use crate::{
+    db::{
+        models::{ArchivedContext, Context, EventLog},
+        schema::{archived_contexts, config_versions, contexts, event_log},
+    },
+    helpers::{add_config_version, is_active_at, tenant_namespaces},
+};
+
+const CONTEXT_SCHEDULER_INTERVAL: Duration = Duration::from_secs(10);
+const CONTEXT_SCHEDULER_USER: &str = "context_scheduler";
+const CONTEXT_SCHEDULER_TAG: &str = "scheduled";
+
+#[derive(QueryableByName)]
+struct SchedulerLock {
+    #[diesel(sql_type = Bool)]
+    locked: bool,
+}
+
+#[derive(QueryableByName)]
+struct ExperimentVariants {
+    #[diesel(sql_type = Json)]
+    variants: Value,
+}
+
+fn variant_context_ids(variants: &Value) -> impl Iterator<Item = String> + '_ {
+    variants
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|variant| variant.get("context_id")?.as_str())
+        .map(String::from)
+}
+
+/// Ids of the contexts created for the variants of experiments that are not concluded.
+fn running_experiment_contexts(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    exp_namespace: &str,
+) -> superposition::Result<HashSet<String>> {
+    let experiments = diesel::sql_query(format!(
+        r#"SELECT variants FROM "{exp_namespace}".experiments WHERE status IN ('CREATED', 'INPROGRESS')"#
+    ))
+    .load::<ExperimentVariants>(conn)?;
+    Ok(experiments
+        .iter()
+        .flat_map(|experiment| variant_context_ids(&experiment.variants))
+        .collect())
+}
+
+#[derive(Default)]
+struct ContextSchedules {
+    activated: Vec<Context>,
+    expired: Vec<Context>,
+    held: Vec<Context>,
+}
+
+/*
+  A context is activated once its window opens after the latest config version,
+  versions created later already include it. Expired contexts are archived,
+  unless a running experiment still points at them, concluding or discarding
+  that experiment cleans them up instead. Such a context is held only once,
+  when its window closes after the latest config version, as it leaves the
+  config without being archived.
+*/
+fn select_context_schedules(
+    contexts: Vec<Context>,
+    latest_version_at: Option<DateTime<Utc>>,
+    experiment_contexts: &HashSet<String>,
+    now: DateTime<Utc>,
+) -> ContextSchedules {
+    let mut schedules = ContextSchedules::default();
+    for context in contexts {
+        if let Some(active_until) = context
+            .active_until
+            .filter(|active_until| *active_until <= now)
+        {
+            if !experiment_contexts.contains(&context.id) {
+                schedules.expired.push(context);
+            } else if latest_version_at
+                .map_or(true, |latest_version_at| active_until > latest_version_at)
+            {
+                schedules.held.push(context);
+            }
+        } else if context.active_from.is_some_and(|active_from| {
+            latest_version_at
+                .map_or(true, |latest_version_at| active_from > latest_version_at)
+        }) && is_active_at(context.active_from, context.active_until, now)
+        {
+            schedules.activated.push(context);
+        }
+    }
+    schedules
+}
+
+fn scheduler_event(
+    action: &str,
+    original_data: Option<Value>,
+    new_data: Option<Value>,
+    query: String,
+) -> EventLog {
+    EventLog {
+        id: uuid::Uuid::new_v4(),
+        table_name: String::from("contexts"),
+        user_name: CONTEXT_SCHEDULER_USER.to_string(),
+        timestamp: Utc::now().naive_utc(),
+        action: action.to_string(),
+        original_data,
+        new_data,
+        query,
+    }
+}
+
+fn archive(context: Context) -> ArchivedContext {
+    ArchivedContext {
+        id: context.id,
+        value: context.value,
+        override_id: context.override_id,
+        created_at: context.created_at,
+        created_by: context.created_by,
+        priority: context.priority,
+        override_: context.override_,
+        last_modified_at: context.last_modified_at,
+        last_modified_by: context.last_modified_by,
+        function_versions: context.function_versions,
+        active_from: context.active_from,
+        active_until: context.active_until,
+        archived_at: Utc::now().naive_utc(),
+        archived_by: CONTEXT_SCHEDULER_USER.to_string(),
+    }
+}
+
+/*
+  The namespace is locked for the transaction so that only one instance acts on
+  a boundary.
+*/
+fn apply_context_schedules(
+    state: &Data<AppState>,
+    namespace: &str,
+    exp_namespace: &str,
+) -> superposition::Result<()> {
+    let mut conn = state
+        .db_pool
+        .get_conn(namespace.to_string())
+        .map_err(|err| {
+            log::error!("failed to get db connection for {namespace}: {err}");
+            unexpected_error!("Something went wrong")
+        })?;
+
+    conn.transaction::<_, superposition::AppError, _>(|transaction_conn| {
+        let lock = diesel::sql_query(
+            "SELECT pg_try_advisory_xact_lock(hashtext($1)) AS locked",
+        )
+        .bind::<Text, _>(format!("context_scheduler_{namespace}"))
+        .get_result::<SchedulerLock>(transaction_conn)?;
+        if !lock.locked {
+            return Ok(());
+        }
+
+        let latest_version_at = config_versions::table
+            .select(config_versions::created_at)
+            .order(config_versions::created_at.desc())
+            .first::<NaiveDateTime>(transaction_conn)
+            .optional()?;
+        let scheduled: Vec<Context> = contexts::table
+            .filter(
+                contexts::active_from
+                    .is_not_null()
+                    .or(contexts::active_until.is_not_null()),
+            )
+            .load(transaction_conn)?;
+        if scheduled.is_empty() {
+            return Ok(());
+        }
+        let experiment_contexts =
+            running_experiment_contexts(transaction_conn, exp_namespace)?;
+        let ContextSchedules {
+            activated,
+            expired,
+            held,
+        } = select_context_schedules(
+            scheduled,
+            latest_version_at.map(|created_at| created_at.and_utc()),
+            &experiment_contexts,
+            Utc::now(),
+        );
+        if !held.is_empty() {
+            log::warn!(
+                "{namespace}: not archiving expired contexts {} of running experiments",
+                held.iter().map(|context| context.id.as_str()).join(",")
+            );
+        }
+        if activated.is_empty() && expired.is_empty() && held.is_empty() {
+            return Ok(());
+        }
+
+        let mut events = activated
+            .iter()
+            .map(|context| {
+                scheduler_event(
+                    "ACTIVATE",
+                    None,
+                    Some(json!(context)),
+                    format!("scheduled activation of context {}", context.id),
+                )
+            })
+            .collect::<Vec<_>>();
+        events.extend(expired.iter().map(|context| {
+            scheduler_event(
+                "EXPIRE",
+                Some(json!(context)),
+                None,
+                format!("scheduled expiry of context {}", context.id),
+            )
+        }));
+        events.extend(held.iter().map(|context| {
+            scheduler_event(
+                "EXPIRE",
+                Some(json!(context)),
+                Some(json!(context)),
+                format!(
+                    "scheduled expiry of context {}, kept for its running experiment",
+                    context.id
+                ),
+            )
+        }));
+
+        let expired_ids = expired
+            .iter()
+            .map(|context| context.id.clone())
+            .collect::<Vec<_>>();
+        if !expired.is_empty() {
+            diesel::insert_into(archived_contexts::table)
+                .values(expired.into_iter().map(archive).collect::<Vec<_>>())
+                .execute(transaction_conn)?;
+            diesel::delete(contexts::table)
+                .filter(contexts::id.eq_any(&expired_ids))
+                .execute(transaction_conn)?;
+        }
+        diesel::insert_into(event_log::table)
+            .values(&events)
+            .execute(transaction_conn)?;
+
+        let version_id = add_config_version(
+            state,
+            Some(vec![CONTEXT_SCHEDULER_TAG.to_string()]),
+            transaction_conn,
+        )?;
+        log::info!(
+            "{namespace}: activated {}, archived {} and held {} contexts, config version {version_id}",
+            activated.len(),
+            expired_ids.len(),
+            held.len()
+        );
+        Ok(())
+    })
+}
+
+/// Applies the activation and expiry of scheduled contexts in every CAC namespace.
+pub fn start_context_scheduler(state: Data<AppState>) {
+    actix_web::rt::spawn(async move {
+        let mut ticker = interval(CONTEXT_SCHEDULER_INTERVAL);
+        loop {
+            ticker.tick().await;
+            for (namespace, exp_namespace) in tenant_namespaces(&state) {
+                let block_state = state.clone();
+                let block_namespace = namespace.clone();
+                let result = web::block(move || {
+                    apply_context_schedules(
+                        &block_state,
+                        &block_namespace,
+                        &exp_namespace,
+                    )
+                })
+                .await;
+                match result {
+                    Ok(Ok(())) => (),
+                    Ok(Err(err)) => log::error!(
+                        "failed to apply context schedules for {namespace}: {err}"
+                    ),
+                    Err(err) => log::error!(
+                        "context scheduler for {namespace} did not complete: {err}"
+                    ),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn scheduled_context(
+        id: &str,
+        active_from: Option<DateTime<Utc>>,
+        active_until: Option<DateTime<Utc>>,
+    ) -> Context {
+        Context {
+            id: id.to_string(),
+            value: json!({"==": [{"var": "city"}, "Bangalore"]}),
+            override_id: format!("{id}_override"),
+            created_at: Utc::now(),
+            created_by: String::from("user@superposition.io"),
+            priority: 1,
+            override_: json!({"key": "value"}),
+            last_modified_at: Utc::now().naive_utc(),
+            last_modified_by: String::from("user@superposition.io"),
+            function_versions: json!({}),
+            active_from,
+            active_until,
+        }
+    }
+
+    fn ids(contexts: &[Context]) -> Vec<&str> {
+        contexts.iter().map(|context| context.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_select_context_schedules() {
+        let now = Utc::now();
+        let latest_version_at = now - ChronoDuration::minutes(5);
+        let contexts = vec![
+            scheduled_context("opened", Some(now - ChronoDuration::minutes(1)), None),
+            scheduled_context(
+                "already_served",
+                Some(now - ChronoDuration::minutes(10)),
+                Some(now + ChronoDuration::minutes(10)),
+            ),
+            scheduled_context("upcoming", Some(now + ChronoDuration::minutes(1)), None),
+            scheduled_context("closed", None, Some(now - ChronoDuration::minutes(1))),
+            scheduled_context(
+                "closing_now",
+                Some(now - ChronoDuration::minutes(1)),
+                Some(now),
+            ),
+            scheduled_context("experiment", None, Some(now - ChronoDuration::minutes(1))),
+            scheduled_context(
+                "already_held",
+                None,
+                Some(now - ChronoDuration::minutes(10)),
+            ),
+        ];
+        let experiment_contexts =
+            HashSet::from([String::from("experiment"), String::from("already_held")]);
+
+        let schedules = select_context_schedules(
+            contexts,
+            Some(latest_version_at),
+            &experiment_contexts,
+            now,
+        );
+        assert_eq!(ids(&schedules.activated), vec!["opened"]);
+        assert_eq!(ids(&schedules.expired), vec!["closed", "closing_now"]);
+        assert_eq!(ids(&schedules.held), vec!["experiment"]);
+    }
+
+    #[test]
+    fn test_select_context_schedules_without_config_versions() {
+        let now = Utc::now();
+        let contexts = vec![
+            scheduled_context("opened", Some(now - ChronoDuration::days(1)), None),
+            scheduled_context("unbounded", None, Some(now + ChronoDuration::days(1))),
+        ];
+
+        let schedules = select_context_schedules(contexts, None, &HashSet::new(), now);
+        assert_eq!(ids(&schedules.activated), vec!["opened"]);
+        assert!(schedules.expired.is_empty());
+        assert!(schedules.held.is_empty());
+    }
+
+    #[test]
+    fn test_variant_context_ids() {
+        let variants = json!([
+            {"id": "exp-control", "variant_type": "CONTROL", "context_id": "ctx_1"},
+            {"id": "exp-test", "variant_type": "EXPERIMENTAL", "context_id": null},
+            {"id": "exp-test-2", "variant_type": "EXPERIMENTAL", "context_id": "ctx_2"}
+        ]);
+        assert_eq!(
+            variant_context_ids(&variants).collect::<Vec<_>>(),
+            vec![String::from("ctx_1"), String::from("ctx_2")]
+        );
+        assert_eq!(variant_context_ids(&json!({})).count(), 0);
+    }
+}
@@ -1,176 +1,431 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use rquickjs::{
+    prelude::{Coerced, Func, Rest},
+    Context, Ctx, Error as JsError, Function, Object, Persistent, Runtime,
+    Value as JsValue,
+};
 use serde_json::Value;
-use std::process::Command;
-use std::str;
 use superposition_macros::{unexpected_error, validation_error};
 use superposition_types::result as superposition;
 
-static FUNCTION_ENV_VARIABLES: &str =
-    "HTTP_PROXY,HTTPS_PROXY,HTTP_PROXY_HOST,HTTP_PROXY_PORT,NO_PROXY";
+const FUNCTION_TIME_LIMIT: Duration = Duration::from_millis(1500);
+const FUNCTION_MEMORY_LIMIT: usize = 32 * 1024 * 1024;
+const FUNCTION_STACK_SIZE: usize = 1024 * 1024;
+// shared by all worker threads, at most 16 * 32 MiB is held by cached functions
+const FUNCTION_CACHE_SIZE: usize = 16;
 
-fn type_check_validate(code_str: &str) -> String {
-    format!(
-        r#"const vm = require("node:vm")
-        const axios = require("./target/node_modules/axios/dist/node/axios.cjs")
-        const script = new vm.Script(\`
+static CACHED_FUNCTIONS: AtomicUsize = AtomicUsize::new(0);
 
-        {}
+// functions used to receive axios, which has no place in a sandbox without network access
+const AXIOS_ERROR: &str =
+    "axios is not available to functions, network calls are not supported";
+const AXIOS_SHIM: &str = r#"
+(message) => {
+    globalThis.axios = new Proxy(function () {}, {
+        get() { throw new Error(message); },
+        apply() { throw new Error(message); },
+    });
+}
+"#;
 
-        if(typeof(validate)!="function")
-        {{
-            throw Error("validate is not of function type")
-        }}\`);
+type Output = Rc<RefCell<Vec<String>>>;
 
-        script.runInNewContext({{axios,console}}, {{ timeout: 1500}});
-        "#,
-        code_str
-    )
+struct CompiledFunction {
+    // persisted values have to be dropped before the runtime that owns them
+    validate: Persistent<Function<'static>>,
+    output: Output,
+    context: Context,
+    runtime: Runtime,
 }
 
-fn execute_validate_fun(code_str: &str, key: String, value: Value) -> String {
-    format!(
-        r#"
-        const vm = require("node:vm")
-        const axios = require("./target/node_modules/axios/dist/node/axios.cjs")
-        const script = new vm.Script(\`
-
-        {}
-        Promise.resolve(validate({}, {})).then((output) => {{
-
-            if(output!=true){{
-                throw new Error("The function did not return true as expected. Check the conditions or logic inside the function.")
-            }}
-            return output;
-        }}).catch((err)=> {{
-            throw new Error(err)
-        }});\`);
-
-        script.runInNewContext({{axios,console}}, {{ timeout: 1500}});
-        "#,
-        code_str, key, value
-    )
+/*
+  Functions run inside an embedded QuickJS runtime, which has no access to the
+  file system, the network or the process environment. Every compiled function
+  owns a runtime with its own memory limit, and a deadline shared by the worker
+  thread is checked by the interpreter while a function runs. Compiled
+  functions are cached per worker thread by name and code, so drafts, published
+  code and older versions of a function do not evict each other. The size of the
+  cache is bounded across all worker threads, a thread drops its least recently
+  used functions to make room, and runs functions without caching them when it
+  has none left to drop.
+*/
+struct Engine {
+    functions: LruCache<(String, blake3::Hash), CompiledFunction>,
+    deadline: Rc<Cell<Option<Instant>>>,
 }
 
-fn generate_code(code_str: &str) -> String {
-    format!(
-        r#"
-    const {{ Worker, isMainThread, threadId }} =  require("node:worker_threads");
-    if (isMainThread) {{
-        let function_env_variables = "{}"
-        let variablesToKeep = []
-        variablesToKeep = function_env_variables.split(',').map(variable => variable.trim());
-        for (const key in process.env) {{
-            if (!variablesToKeep.includes(key)) {{
-                delete process.env[key];
-            }}
-        }}
-
-
-    // starting worker thread , making separated from the main thread
-    function runService() {{
-        return new Promise((resolve, reject) => {{
-        const worker = new Worker(
-            `{}`,{{eval:true}}
-        );
-        worker.on("message", (msg) => {{
-            console.log(msg);
-        }});
-        worker.on("error", (err) => {{
-            clearTimeout(tl);
-            console.error(err.message);
-            process.exit(1);
-        }});
-        worker.on("exit", (code) => {{
-            clearTimeout(tl);
-            if (code !== 0) {{
-                console.error(`Script stopped with exit code ${{code}}`);
-                process.exit(code);
-            }} else {{
-                worker.terminate();
-            }}
-        }});
-
-        function timelimit() {{
-            worker.terminate();
-            throw new Error("time limit exceeded");
-        }}
-
-        // terminate worker thread if execution time exceed 2 secs
-
-        var tl = setTimeout(timelimit, 10000);
-        }});
-    }}
-
-    async function run() {{
-        const result = await runService();
-        console.log("result output: ", result);
-    }}
-    run().catch((err) => console.error(err));
-    }}
-
-    "#,
-        FUNCTION_ENV_VARIABLES, code_str
-    )
+thread_local! {
+    static ENGINE: RefCell<Engine> = RefCell::new(Engine::new());
 }
 
-pub fn execute_fn(
-    code_str: &str,
+impl Engine {
+    fn new() -> Self {
+        Self {
+            functions: LruCache::unbounded(),
+            deadline: Rc::new(Cell::new(None)),
+        }
+    }
+
+    fn new_runtime(&self) -> Result<Runtime, String> {
+        let runtime = Runtime::new()
+            .map_err(|err| format!("failed to start the function runtime: {err}"))?;
+        runtime.set_memory_limit(FUNCTION_MEMORY_LIMIT);
+        runtime.set_max_stack_size(FUNCTION_STACK_SIZE);
+
+        let deadline = self.deadline.clone();
+        runtime.set_interrupt_handler(Some(Box::new(move || {
+            deadline
+                .get()
+                .map_or(false, |deadline| Instant::now() > deadline)
+        })));
+        Ok(runtime)
+    }
+
+    fn new_context(&self, runtime: &Runtime, output: &Output) -> Result<Context, String> {
+        let context = Context::full(runtime)
+            .map_err(|err| format!("failed to create a function context: {err}"))?;
+        context
+            .with(|ctx| {
+                ctx.eval::<Function, _>(AXIOS_SHIM)?
+                    .call::<_, ()>((AXIOS_ERROR,))?;
+                let console = Object::new(ctx.clone())?;
+                for method in ["log", "info", "warn", "error", "debug"] {
+                    let output = output.clone();
+                    console.set(
+                        method,
+                        Func::from(move |args: Rest<Coerced<String>>| {
+                            let line = args
+                                .0
+                                .into_iter()
+                                .map(|arg| arg.0)
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            output.borrow_mut().push(line);
+                        }),
+                    )?;
+                }
+                ctx.globals().set("console", console)
+            })
+            .map_err(|err| format!("failed to create a function context: {err}"))?;
+        Ok(context)
+    }
+
+    fn compile(&self, runtime: Runtime, code: &str) -> Result<CompiledFunction, String> {
+        let output: Output = Rc::new(RefCell::new(Vec::new()));
+        let context = self.new_context(&runtime, &output)?;
+        let validate = with_time_limit(&self.deadline, || {
+            context.with(|ctx| {
+                ctx.eval::<(), _>(code)
+                    .map_err(|err| exception_message(&ctx, err))?;
+                let validate: JsValue = ctx
+                    .globals()
+                    .get("validate")
+                    .map_err(|err| exception_message(&ctx, err))?;
+                match validate.into_function() {
+                    Some(validate) => Ok(Persistent::save(&ctx, validate)),
+                    None => Err("validate is not of function type".to_string()),
+                }
+            })
+        })?;
+        Ok(CompiledFunction {
+            validate,
+            output,
+            context,
+            runtime,
+        })
+    }
+
+    fn execute(
+        &mut self,
+        function_name: &str,
+        code: &str,
+        key: &str,
+        value: &Value,
+    ) -> Result<String, (String, Option<String>)> {
+        let cache_key = (function_name.to_string(), blake3::hash(code.as_bytes()));
+        if !self.functions.contains(&cache_key) {
+            let function = self
+                .new_runtime()
+                .and_then(|runtime| self.compile(runtime, code))
+                .map_err(|err| (err, None))?;
+            if !self.reserve_cache_slot() {
+                return run(&self.deadline, &function, key, value);
+            }
+            self.functions.put(cache_key.clone(), function);
+        }
+
+        let deadline = self.deadline.clone();
+        let Some(function) = self.functions.get(&cache_key) else {
+            return Err(("function could not be compiled".to_string(), None));
+        };
+        run(&deadline, function, key, value)
+    }
+
+    fn reserve_cache_slot(&mut self) -> bool {
+        loop {
+            let reserved = CACHED_FUNCTIONS
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |cached| {
+                    (cached < FUNCTION_CACHE_SIZE).then_some(cached + 1)
+                })
+                .is_ok();
+            if reserved {
+                return true;
+            }
+            if self.functions.pop_lru().is_none() {
+                return false;
+            }
+            CACHED_FUNCTIONS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        CACHED_FUNCTIONS.fetch_sub(self.functions.len(), Ordering::SeqCst);
+    }
+}
+
+fn run(
+    deadline: &Cell<Option<Instant>>,
+    function: &CompiledFunction,
     key: &str,
-    value: Value,
+    value: &Value,
 ) -> Result<String, (String, Option<String>)> {
-    let exec_code = execute_validate_fun(code_str, format!(r#""{key}""#), value);
-    let output = Command::new("node")
-        .arg("-e")
-        .arg(generate_code(&exec_code))
-        .output();
-    log::trace!("{}", format!("validation function output : {:?}", output));
-    match output {
-        Ok(val) => {
-            let stdout = str::from_utf8(&val.stdout)
-                .unwrap_or("[Invalid UTF-8 in stdout]")
-                .to_owned();
-            if !(val.status.success()) {
-                let stderr = str::from_utf8(&val.stderr)
-                    .unwrap_or("[Invalid UTF-8 in stderr]")
-                    .to_owned();
-                log::error!(
-                    "{}",
-                    format!("validation function output error: {:?}", stderr)
-                );
-                Err((stderr, Some(stdout)))
+    function.output.borrow_mut().clear();
+    let result = with_time_limit(deadline, || {
+        function.context.with(|ctx| {
+            let validate = function
+                .validate
+                .clone()
+                .restore(&ctx)
+                .map_err(|err| exception_message(&ctx, err))?;
+            let value = ctx
+                .json_parse(value.to_string())
+                .map_err(|err| exception_message(&ctx, err))?;
+            let output: JsValue = validate
+                .call((key, value))
+                .map_err(|err| exception_message(&ctx, err))?;
+            let output = match output.as_promise() {
+                Some(promise) => promise
+                    .finish::<JsValue>()
+                    .map_err(|err| exception_message(&ctx, err))?,
+                None => output,
+            };
+            if output.as_bool() == Some(true) {
+                Ok(())
             } else {
-                Ok(stdout)
+                Err("The function did not return true as expected. Check the conditions or logic inside the function.".to_string())
+            }
+        })
+    });
+    let stdout = function.output.borrow().join("\n");
+    function.runtime.run_gc();
+    result
+        .map(|_| stdout.clone())
+        .map_err(|err| (err, Some(stdout)))
+}
+
+// runs `f` with the deadline armed, turning interruptions into a timeout error
+fn with_time_limit<T>(
+    deadline: &Cell<Option<Instant>>,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let limit = Instant::now() + FUNCTION_TIME_LIMIT;
+    deadline.set(Some(limit));
+    let result = f();
+    deadline.set(None);
+    result.map_err(|err| {
+        if Instant::now() > limit {
+            format!(
+                "time limit of {}ms exceeded",
+                FUNCTION_TIME_LIMIT.as_millis()
+            )
+        } else {
+            err
+        }
+    })
+}
+
+pub fn uses_axios(code: &str) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    code.match_indices("axios").any(|(at, name)| {
+        !code[..at].chars().next_back().map_or(false, is_identifier)
+            && !code[at + name.len()..]
+                .chars()
+                .next()
+                .map_or(false, is_identifier)
+    })
+}
+
+fn exception_message(ctx: &Ctx, err: JsError) -> String {
+    match err {
+        JsError::Exception => {
+            let exception = ctx.catch();
+            match exception.as_exception() {
+                Some(exception) => exception
+                    .message()
+                    .unwrap_or_else(|| "unknown exception".to_string()),
+                None => exception
+                    .get::<Coerced<String>>()
+                    .map(|message| message.0)
+                    .unwrap_or_else(|_| "unknown exception".to_string()),
             }
         }
-        Err(e) => {
-            log::error!("js_eval error: {}", e);
-            Err((format!("js_eval error: {}", e), None))
+        JsError::WouldBlock => {
+            "validate returned a promise that never settles".to_string()
         }
+        err => err.to_string(),
     }
 }
 
+fn with_engine<T>(f: impl FnOnce(&mut Engine) -> T) -> T {
+    ENGINE.with(|engine| f(&mut engine.borrow_mut()))
+}
+
+pub fn execute_fn(
+    function_name: &str,
+    code_str: &str,
+    key: &str,
+    value: Value,
+) -> Result<String, (String, Option<String>)> {
+    let result =
+        with_engine(|engine| engine.execute(function_name, code_str, key, &value));
+    log::trace!("validation function output : {:?}", result);
+    if let Err((err, _)) = &result {
+        log::error!("validation function {function_name} failed with error: {err}");
+    }
+    result
+}
+
 pub fn compile_fn(code_str: &str) -> superposition::Result<()> {
-    let type_check_code = type_check_validate(code_str);
-    let output = Command::new("node")
-        .arg("-e")
-        .arg(generate_code(&type_check_code))
-        .output();
-
-    log::trace!("{}", format!("validation function output : {:?}", output));
-    match output {
-        Ok(val) => {
-            if !(val.status.success()) {
-                let stderr = str::from_utf8(&val.stderr)
-                    .unwrap_or("[Invalid UTF-8 in stderr]")
-                    .to_owned();
-                log::error!("{}", format!("eslint check output error: {:?}", stderr));
-                Err(validation_error!(stderr))
-            } else {
-                Ok(())
+    if uses_axios(code_str) {
+        log::error!("function compilation failed with error: {AXIOS_ERROR}");
+        return Err(validation_error!(
+            "{}, remove the use of axios from the function",
+            AXIOS_ERROR
+        ));
+    }
+    with_engine(|engine| {
+        let runtime = engine.new_runtime().map_err(|err| {
+            log::error!("function runtime error: {err}");
+            unexpected_error!(err)
+        })?;
+        engine
+            .compile(runtime, code_str)
+            .map(|_| ())
+            .map_err(|err| {
+                log::error!("function compilation failed with error: {err}");
+                validation_error!(err)
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_execute_fn_passes_when_validate_returns_true() {
+        let code = r#"
+            function validate(key, value) {
+                console.log("checking", key);
+                return key === "max_retries" && value.count < 5;
             }
+        "#;
+        let result = execute_fn("retries", code, "max_retries", json!({"count": 3}));
+        assert_eq!(result, Ok("checking max_retries".to_string()));
+    }
+
+    #[test]
+    fn test_execute_fn_fails_when_validate_does_not_return_true() {
+        let code = r#"
+            async function validate(key, value) {
+                console.log("value", value);
+                return value > 10 ? true : "too small";
+            }
+        "#;
+        let (err, stdout) =
+            execute_fn("threshold", code, "threshold", json!(4)).unwrap_err();
+        assert!(err.contains("did not return true"), "{err}");
+        assert_eq!(stdout, Some("value 4".to_string()));
+
+        let (err, _) = execute_fn(
+            "throws",
+            "function validate() { throw new Error('bad value'); }",
+            "key",
+            json!(null),
+        )
+        .unwrap_err();
+        assert_eq!(err, "bad value");
+    }
+
+    #[test]
+    fn test_execute_fn_uses_the_code_it_is_given() {
+        let accept = "function validate() { return true; }";
+        let reject = "function validate() { return false; }";
+        assert!(execute_fn("versioned", accept, "key", json!(1)).is_ok());
+        assert!(execute_fn("versioned", reject, "key", json!(1)).is_err());
+        assert!(execute_fn("versioned", accept, "key", json!(1)).is_ok());
+    }
+
+    #[test]
+    fn test_execute_fn_keeps_the_cache_within_its_size() {
+        for i in 0..FUNCTION_CACHE_SIZE + 4 {
+            let code =
+                format!("function validate(key, value) {{ return value === {i}; }}");
+            assert!(execute_fn(&format!("cached_{i}"), &code, "key", json!(i)).is_ok());
+            assert!(CACHED_FUNCTIONS.load(Ordering::SeqCst) <= FUNCTION_CACHE_SIZE);
         }
-        Err(e) => {
-            log::error!("eslint check error: {}", e);
-            Err(unexpected_error!("js_eval error: {}", e))
-        }
+    }
+
+    #[test]
+    fn test_execute_fn_enforces_the_time_limit() {
+        let code = "function validate() { while (true) {} }";
+        let (err, _) = execute_fn("loops", code, "key", json!(1)).unwrap_err();
+        assert!(err.contains("time limit"), "{err}");
+
+        let err = compile_fn("while (true) {}").unwrap_err().to_string();
+        assert!(err.contains("time limit"), "{err}");
+    }
+
+    #[test]
+    fn test_execute_fn_enforces_the_memory_limit() {
+        let code = r#"
+            function validate() {
+                const chunks = [];
+                while (true) { chunks.push("x".repeat(1024 * 1024)); }
+            }
+        "#;
+        let (err, _) = execute_fn("allocates", code, "key", json!(1)).unwrap_err();
+        assert_eq!(err, "out of memory");
+
+        let code = "function validate() { return true; }";
+        assert!(execute_fn("small", code, "key", json!(1)).is_ok());
+    }
+
+    #[test]
+    fn test_axios_is_rejected_with_a_clear_error() {
+        let code = r#"
+            async function validate(key, value) {
+                const response = await axios.get("https://example.com");
+                return response.status === 200;
+            }
+        "#;
+        let err = compile_fn(code).unwrap_err().to_string();
+        assert!(err.contains("axios is not available"), "{err}");
+
+        let (err, _) = execute_fn("fetches", code, "key", json!(1)).unwrap_err();
+        assert_eq!(err, AXIOS_ERROR);
+
+        assert!(compile_fn("function validate() { return noaxios_flag; }").is_ok());
     }
 }
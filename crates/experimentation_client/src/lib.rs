@@ -10,6 +10,7 @@ use std::{
 use chrono::{DateTime, TimeZone, Utc};
 use derive_more::{Deref, DerefMut};
 use serde_json::{Map, Value};
+use superposition_types::semantic_version::resolve_version_comparisons;
 use tokio::{
     sync::RwLock,
     time::{self, Duration},
@@ -109,7 +110,10 @@ impl Client {
                     .as_object()
                     .map_or(false, |context| context.is_empty());
                 is_empty
-                    || jsonlogic::apply(&exp.context, context) == Ok(Value::Bool(true))
+                    || jsonlogic::apply(
+                        &resolve_version_comparisons(&exp.context, context),
+                        context,
+                    ) == Ok(Value::Bool(true))
             })
             .map(|(_, exp)| exp.clone())
             .collect::<Experiments>();
@@ -141,7 +145,8 @@ impl Client {
                 if is_empty {
                     Some(exp.clone())
                 } else {
-                    match jsonlogic::partial_apply(&exp.context, context) {
+                    let condition = resolve_version_comparisons(&exp.context, context);
+                    match jsonlogic::partial_apply(&condition, context) {
                         Ok(jsonlogic::PartialApplyOutcome::Resolved(Value::Bool(
                             true,
                         )))
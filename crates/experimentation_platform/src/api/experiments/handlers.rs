@@ -23,7 +23,10 @@ use service_utils::service::types::{
     AppHeader, AppState, CustomHeaders, DbConnection, Tenant,
 };
 use superposition_macros::{bad_argument, response_error, unexpected_error};
-use superposition_types::{result as superposition, Condition, Exp, Overrides, User};
+use superposition_types::{
+    result as superposition, semantic_version::resolve_version_comparisons, Condition,
+    Exp, Overrides, TenantConfig, User,
+};
 
 use super::{
     helpers::{
@@ -47,6 +50,7 @@ use crate::{
     },
 };
 
+use serde::de::DeserializeOwned;
 use serde_json::{json, Map, Value};
 
 pub fn endpoints(scope: Scope) -> Scope {
@@ -113,9 +117,9 @@ async fn parse_error_response(
     Ok((status_code, error_response))
 }
 
-async fn process_cac_http_response(
+async fn process_cac_http_response<T: DeserializeOwned>(
     response: Result<Response, reqwest::Error>,
-) -> superposition::Result<(Vec<ContextBulkResponse>, Option<String>)> {
+) -> superposition::Result<(T, Option<String>)> {
     let internal_server_error = unexpected_error!("Something went wrong.");
     match response {
         Ok(res) if res.status().is_success() => {
@@ -123,14 +127,11 @@ async fn process_cac_http_response(
                 .headers()
                 .get("x-config-version")
                 .and_then(|val| val.to_str().map_or(None, |v| Some(v.to_string())));
-            let bulk_resp =
-                res.json::<Vec<ContextBulkResponse>>()
-                    .await
-                    .map_err(|err| {
-                        log::error!("failed to parse JSON response with error: {}", err);
-                        internal_server_error
-                    })?;
-            Ok((bulk_resp, config_version))
+            let resp = res.json::<T>().await.map_err(|err| {
+                log::error!("failed to parse JSON response with error: {}", err);
+                internal_server_error
+            })?;
+            Ok((resp, config_version))
         }
         Ok(res) => {
             log::error!("http call to CAC failed with status_code {}", res.status());
@@ -272,7 +273,8 @@ async fn create(
         .await;
 
     // directly return an error response if not a 200 response
-    let (resp_contexts, config_version_id) = process_cac_http_response(response).await?;
+    let (resp_contexts, config_version_id) =
+        process_cac_http_response::<Vec<ContextBulkResponse>>(response).await?;
     let created_contexts = resp_contexts
         .into_iter()
         .filter_map(|item| match item {
@@ -319,6 +321,7 @@ async fn create(
 }
 
 #[patch("/{experiment_id}/conclude")]
+#[allow(clippy::too_many_arguments)]
 async fn conclude_handler(
     state: Data<AppState>,
     path: web::Path<i64>,
@@ -327,8 +330,23 @@ async fn conclude_handler(
     db_conn: DbConnection,
     tenant: Tenant,
     user: User,
+    tenant_config: TenantConfig,
 ) -> superposition::Result<HttpResponse> {
     let DbConnection(conn) = db_conn;
+    // the winning variant changes the config of the tenant, which has to be reviewed
+    if tenant_config.require_change_requests {
+        let change_request = submit_conclusion(
+            state,
+            path.into_inner(),
+            custom_headers,
+            req.into_inner(),
+            conn,
+            tenant,
+            user,
+        )
+        .await?;
+        return Ok(HttpResponse::Accepted().json(change_request));
+    }
     let (response, config_version_id) = conclude(
         state,
         path.into_inner(),
@@ -344,22 +362,13 @@ async fn conclude_handler(
     Ok(http_resp.json(ExperimentResponse::from(response)))
 }
 
-pub async fn conclude(
-    state: Data<AppState>,
+fn fetch_concludable_experiment(
     experiment_id: i64,
-    config_tags: Option<String>,
-    req: ConcludeExperimentRequest,
-    mut conn: PooledConnection<ConnectionManager<PgConnection>>,
-    tenant: Tenant,
-    user: User,
-) -> superposition::Result<(Experiment, Option<String>)> {
-    use crate::db::schema::experiments::dsl;
-
-    let winner_variant_id: String = req.chosen_variant.to_owned();
-
-    let experiment: Experiment = dsl::experiments
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> superposition::Result<Experiment> {
+    let experiment: Experiment = experiments::experiments
         .find(experiment_id)
-        .get_result::<Experiment>(&mut conn)?;
+        .get_result::<Experiment>(conn)?;
 
     if matches!(experiment.status, ExperimentStatusType::CONCLUDED) {
         return Err(bad_argument!(
@@ -367,12 +376,25 @@ pub async fn conclude(
             experiment_id
         ));
     }
+    Ok(experiment)
+}
 
+/*
+  The config changes concluding an experiment: the overrides of the winning
+  variant become default values when the experiment has no context, otherwise
+  its context moves to the context of the experiment. The contexts of the
+  other variants are deleted.
+*/
+fn conclusion_changes(
+    experiment: Experiment,
+    winner_variant_id: &str,
+) -> superposition::Result<(Vec<(String, Value)>, Vec<ContextAction>)> {
     let experiment_context = experiment.context.as_object().ok_or_else(|| {
         log::error!("could not convert the context read from DB to JSON object");
         unexpected_error!("Something went wrong, failed to conclude experiment")
     })?;
 
+    let mut default_config_updates = vec![];
     let mut operations: Vec<ContextAction> = vec![];
     let experiment_variants: Vec<Variant> = serde_json::from_value(experiment.variants)
         .map_err(|err| {
@@ -397,40 +419,7 @@ pub async fn conclude(
                 };
                 operations.push(ContextAction::MOVE((context_id, context_move_req)));
             } else {
-                let user_str = serde_json::to_string(&user).map_err(|err| {
-                    log::error!(
-                        "Something went wrong, failed to stringify user data {err}"
-                    );
-                    unexpected_error!(
-                        "Something went wrong, failed to stringify user data {}",
-                        err
-                    )
-                })?;
-
-                for (key, val) in variant.overrides.into_inner() {
-                    let create_req = HashMap::from([("value", val)]);
-
-                    let url = format!("{}/default-config/{}", state.cac_host, key);
-
-                    let headers = construct_request_headers(&[
-                        ("x-tenant", tenant.as_str()),
-                        (
-                            "Authorization",
-                            &format!("Internal {}", state.superposition_token),
-                        ),
-                        ("x-user", user_str.as_str()),
-                    ])
-                    .map_err(|err| {
-                        superposition::AppError::UnexpectedError(anyhow!(err))
-                    })?;
-
-                    let _ =
-                        request::<_, Value>(url, Method::PUT, Some(create_req), headers)
-                            .await
-                            .map_err(|err| {
-                                superposition::AppError::UnexpectedError(anyhow!(err))
-                            })?;
-                }
+                default_config_updates.extend(variant.overrides.into_inner());
                 operations.push(ContextAction::DELETE(context_id));
             }
 
@@ -446,10 +435,48 @@ pub async fn conclude(
             "winner variant not found. A wrong variant id may have been sent, check and try again"
         ));
     }
+    Ok((default_config_updates, operations))
+}
+
+/*
+  While the tenant requires change requests, the changes concluding the
+  experiment are submitted as one and the experiment is concluded once the
+  change request is approved and applied.
+*/
+async fn submit_conclusion(
+    state: Data<AppState>,
+    experiment_id: i64,
+    custom_headers: CustomHeaders,
+    req: ConcludeExperimentRequest,
+    mut conn: PooledConnection<ConnectionManager<PgConnection>>,
+    tenant: Tenant,
+    user: User,
+) -> superposition::Result<Value> {
+    let experiment = fetch_concludable_experiment(experiment_id, &mut conn)?;
+    let (default_config_updates, operations) =
+        conclusion_changes(experiment, &req.chosen_variant)?;
+
+    let mut changes = default_config_updates
+        .into_iter()
+        .map(|(key, value)| {
+            json!({
+                "operation": "PUT_DEFAULT_CONFIG",
+                "payload": {"key": key, "request": {"value": value}, "force": false}
+            })
+        })
+        .collect::<Vec<_>>();
+    changes.push(json!({"operation": "BULK_OPERATIONS", "payload": operations}));
+    let change = json!({
+        "operation": "CONCLUDE_EXPERIMENT",
+        "payload": {
+            "experiment_id": experiment_id,
+            "chosen_variant": req.chosen_variant,
+            "changes": changes
+        }
+    });
 
-    // calling CAC bulk api with operations as payload
     let http_client = reqwest::Client::new();
-    let url = state.cac_host.clone() + "/context/bulk-operations";
+    let url = state.cac_host.clone() + "/change-requests";
     let user_str = serde_json::to_string(&user).map_err(|err| {
         log::error!("Something went wrong, failed to stringify user data {err}");
         unexpected_error!(
@@ -457,6 +484,78 @@ pub async fn conclude(
             err
         )
     })?;
+    let extra_headers = vec![
+        ("x-user", Some(user_str)),
+        ("x-config-tags", custom_headers.config_tags),
+        ("x-reviewers", custom_headers.reviewers),
+    ]
+    .into_iter()
+    .filter_map(|(key, val)| val.map(|v| (key, v)))
+    .collect::<Vec<_>>();
+
+    let headers_map = construct_header_map(tenant.as_str(), extra_headers)?;
+
+    let response = http_client
+        .post(&url)
+        .headers(headers_map.into())
+        .header(
+            header::AUTHORIZATION,
+            format!("Internal {}", state.superposition_token),
+        )
+        .json(&change)
+        .send()
+        .await;
+
+    let (change_request, _) = process_cac_http_response::<Value>(response).await?;
+    Ok(change_request)
+}
+
+pub async fn conclude(
+    state: Data<AppState>,
+    experiment_id: i64,
+    config_tags: Option<String>,
+    req: ConcludeExperimentRequest,
+    mut conn: PooledConnection<ConnectionManager<PgConnection>>,
+    tenant: Tenant,
+    user: User,
+) -> superposition::Result<(Experiment, Option<String>)> {
+    use crate::db::schema::experiments::dsl;
+
+    let winner_variant_id: String = req.chosen_variant.to_owned();
+    let experiment = fetch_concludable_experiment(experiment_id, &mut conn)?;
+    let (default_config_updates, operations) =
+        conclusion_changes(experiment, &winner_variant_id)?;
+
+    let user_str = serde_json::to_string(&user).map_err(|err| {
+        log::error!("Something went wrong, failed to stringify user data {err}");
+        unexpected_error!(
+            "Something went wrong, failed to stringify user data {}",
+            err
+        )
+    })?;
+    for (key, val) in default_config_updates {
+        let create_req = HashMap::from([("value", val)]);
+
+        let url = format!("{}/default-config/{}", state.cac_host, key);
+
+        let headers = construct_request_headers(&[
+            ("x-tenant", tenant.as_str()),
+            (
+                "Authorization",
+                &format!("Internal {}", state.superposition_token),
+            ),
+            ("x-user", user_str.as_str()),
+        ])
+        .map_err(|err| superposition::AppError::UnexpectedError(anyhow!(err)))?;
+
+        let _ = request::<_, Value>(url, Method::PUT, Some(create_req), headers)
+            .await
+            .map_err(|err| superposition::AppError::UnexpectedError(anyhow!(err)))?;
+    }
+
+    // calling CAC bulk api with operations as payload
+    let http_client = reqwest::Client::new();
+    let url = state.cac_host.clone() + "/context/bulk-operations";
     let extra_headers = vec![("x-user", Some(user_str)), ("x-config-tags", config_tags)]
         .into_iter()
         .filter_map(|(key, val)| val.map(|v| (key, v)))
@@ -475,7 +574,8 @@ pub async fn conclude(
         .send()
         .await;
 
-    let (_, config_version_id) = process_cac_http_response(response).await?;
+    let (_, config_version_id) =
+        process_cac_http_response::<Vec<ContextBulkResponse>>(response).await?;
 
     // updating experiment status in db
     let updated_experiment = diesel::update(dsl::experiments)
@@ -503,14 +603,17 @@ async fn get_applicable_variants(
         .filter(experiments::status.ne(ExperimentStatusType::CONCLUDED))
         .load::<Experiment>(&mut conn)?;
 
+    let context = Value::Object(query_data.context.clone());
     let experiments = experiments.into_iter().filter(|exp| {
         let is_empty = exp
             .context
             .as_object()
             .map_or(false, |context| context.is_empty());
         is_empty
-            || jsonlogic::apply(&exp.context, &Value::Object(query_data.context.clone()))
-                == Ok(Value::Bool(true))
+            || jsonlogic::apply(
+                &resolve_version_comparisons(&exp.context, &context),
+                &context,
+            ) == Ok(Value::Bool(true))
     });
 
     let mut variants = Vec::new();
@@ -868,7 +971,8 @@ async fn update_overrides(
         .await;
 
     // directly return an error response if not a 200 response
-    let (resp_contexts, config_version_id) = process_cac_http_response(response).await?;
+    let (resp_contexts, config_version_id) =
+        process_cac_http_response::<Vec<ContextBulkResponse>>(response).await?;
     let created_contexts = resp_contexts
         .into_iter()
         .filter_map(|item| match item {
@@ -3,7 +3,9 @@ use crate::db::models::{Experiment, ExperimentStatusType};
 use diesel::pg::PgConnection;
 use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
 use serde_json::{Map, Value};
-use service_utils::helpers::extract_dimensions;
+use service_utils::helpers::{
+    are_constraints_satisfiable, extract_dimension_constraints,
+};
 use service_utils::service::types::ExperimentationFlags;
 use std::collections::HashSet;
 use superposition_macros::{bad_argument, unexpected_error};
@@ -50,32 +52,35 @@ pub fn validate_override_keys(override_keys: &Vec<String>) -> superposition::Res
     Ok(())
 }
 
+/*
+  Contexts overlap when every dimension of the context with fewer dimensions is
+  also constrained by the other one, and some value of it satisfies the
+  constraints of both contexts.
+*/
 pub fn are_overlapping_contexts(
     context_a: &Condition,
     context_b: &Condition,
 ) -> superposition::Result<bool> {
-    let dimensions_a = extract_dimensions(context_a)?;
-    let dimensions_b = extract_dimensions(context_b)?;
+    let dimensions_a = extract_dimension_constraints(context_a)?;
+    let dimensions_b = extract_dimension_constraints(context_b)?;
 
-    let dim_a_keys = dimensions_a.keys();
-    let dim_b_keys = dimensions_b.keys();
-
-    let ref_keys = if dim_a_keys.len() > dim_b_keys.len() {
-        dim_b_keys
+    let (ref_dimensions, other_dimensions) = if dimensions_a.len() > dimensions_b.len() {
+        (&dimensions_b, &dimensions_a)
     } else {
-        dim_a_keys
+        (&dimensions_a, &dimensions_b)
     };
 
-    let mut is_overlapping = true;
-    for key in ref_keys {
-        let test = (dimensions_a.contains_key(key) && dimensions_b.contains_key(key))
-            && (dimensions_a[key] == dimensions_b[key]);
-        is_overlapping = is_overlapping && test;
-
-        if !test {
-            break;
+    let is_overlapping = ref_dimensions.iter().all(|(dimension, constraints)| {
+        match other_dimensions.get(dimension) {
+            Some(other_constraints) => are_constraints_satisfiable(
+                &constraints
+                    .iter()
+                    .chain(other_constraints)
+                    .collect::<Vec<_>>(),
+            ),
+            None => false,
         }
-    }
+    });
 
     Ok(is_overlapping)
 }
@@ -159,6 +159,45 @@ fn test_are_overlapping_contexts() -> Result<(), superposition::AppError> {
     Ok(())
 }
 
+#[test]
+fn test_are_overlapping_contexts_with_operators() -> Result<(), superposition::AppError> {
+    let to_condition = |context: Value| {
+        Exp::<Condition>::try_from(context.as_object().cloned().unwrap_or_default())
+            .map(|condition| condition.into_inner())
+            .map_err(superposition::AppError::BadArgument)
+    };
+
+    let cities = to_condition(json!({"in": [{"var": "city"}, ["Delhi", "Mumbai"]]}))?;
+    let delhi = to_condition(json!({"==": [{"var": "city"}, "Delhi"]}))?;
+    let chennai = to_condition(json!({"==": [{"var": "city"}, "Chennai"]}))?;
+    let not_delhi = to_condition(json!({"!=": [{"var": "city"}, "Delhi"]}))?;
+    let evening = to_condition(json!({"and": [
+        {">=": [{"var": "hour_of_day"}, 18]},
+        {"<": [{"var": "hour_of_day"}, 22]}
+    ]}))?;
+    let night = to_condition(json!({"<=": [22, {"var": "hour_of_day"}, 23]}))?;
+    let late_evening = to_condition(json!({">": [{"var": "hour_of_day"}, 20]}))?;
+
+    // value lists overlap with the values they contain
+    assert!(helpers::are_overlapping_contexts(&cities, &delhi)?);
+    assert!(!(helpers::are_overlapping_contexts(&cities, &chennai)?));
+    // inequality excludes a single value
+    assert!(!(helpers::are_overlapping_contexts(&delhi, &not_delhi)?));
+    assert!(helpers::are_overlapping_contexts(&cities, &not_delhi)?);
+    // ranges overlap only when they intersect
+    assert!(!(helpers::are_overlapping_contexts(&evening, &night)?));
+    assert!(helpers::are_overlapping_contexts(&evening, &late_evening)?);
+    assert!(helpers::are_overlapping_contexts(&night, &late_evening)?);
+
+    // app versions are ordered by semantic version precedence
+    let recent_app = to_condition(json!({">=": [{"var": "app_version"}, "1.9.0"]}))?;
+    let old_app = to_condition(json!({"<": [{"var": "app_version"}, "1.10.0"]}))?;
+    let legacy_app = to_condition(json!({"<": [{"var": "app_version"}, "1.9"]}))?;
+    assert!(helpers::are_overlapping_contexts(&recent_app, &old_app)?);
+    assert!(!(helpers::are_overlapping_contexts(&recent_app, &legacy_app)?));
+    Ok(())
+}
+
 #[test]
 fn test_check_variants_override_coverage() -> Result<(), superposition::AppError> {
     let override_keys = vec!["key1".to_string(), "key2".to_string()];
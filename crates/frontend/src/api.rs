@@ -2,8 +2,9 @@ use leptos::ServerFnError;
 
 use crate::{
     types::{
-        Config, DefaultConfig, Dimension, ExperimentResponse, ExperimentsResponse,
-        FetchTypeTemplateResponse, FunctionResponse, ListFilters,
+        ChangeRequestFilters, ChangeRequestResponse, ChangeRequestsResponse, Config,
+        ContextAnalysis, DefaultConfig, Dimension, ExperimentResponse,
+        ExperimentsResponse, FetchTypeTemplateResponse, FunctionResponse, ListFilters,
     },
     utils::{
         construct_request_headers, get_host, parse_json_response, request,
@@ -177,6 +178,26 @@ pub async fn fetch_config(tenant: String) -> Result<Config, ServerFnError> {
     }
 }
 
+pub async fn fetch_context_analysis(
+    tenant: String,
+) -> Result<ContextAnalysis, ServerFnError> {
+    let client = reqwest::Client::new();
+    let host = use_host_server();
+
+    let url = format!("{}/context/analysis", host);
+    let response: ContextAnalysis = client
+        .get(url)
+        .header("x-tenant", tenant)
+        .send()
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    Ok(response)
+}
+
 // #[server(GetExperiment, "/fxn", "GetJson")]
 pub async fn fetch_experiment(
     exp_id: String,
@@ -248,3 +269,50 @@ pub async fn fetch_types(
         .await
         .map_err(err_handler)
 }
+
+pub async fn fetch_change_requests(
+    filters: ChangeRequestFilters,
+    tenant: String,
+) -> Result<ChangeRequestsResponse, ServerFnError> {
+    let host = use_host_server();
+    let mut query_params = vec![
+        format!("page={}", filters.page),
+        format!("count={}", filters.count),
+    ];
+    if let Some(status) = filters.status {
+        query_params.push(format!("status={}", status));
+    }
+    let url = format!("{host}/change-requests?{}", query_params.join("&"));
+    let err_handler = |e: String| ServerFnError::new(e.to_string());
+    let response = request::<()>(
+        url,
+        reqwest::Method::GET,
+        None,
+        construct_request_headers(&[("x-tenant", &tenant)]).map_err(err_handler)?,
+    )
+    .await
+    .map_err(err_handler)?;
+    parse_json_response::<ChangeRequestsResponse>(response)
+        .await
+        .map_err(err_handler)
+}
+
+pub async fn fetch_change_request(
+    id: String,
+    tenant: String,
+) -> Result<ChangeRequestResponse, ServerFnError> {
+    let host = use_host_server();
+    let url = format!("{host}/change-requests/{id}");
+    let err_handler = |e: String| ServerFnError::new(e.to_string());
+    let response = request::<()>(
+        url,
+        reqwest::Method::GET,
+        None,
+        construct_request_headers(&[("x-tenant", &tenant)]).map_err(err_handler)?,
+    )
+    .await
+    .map_err(err_handler)?;
+    parse_json_response::<ChangeRequestResponse>(response)
+        .await
+        .map_err(err_handler)
+}
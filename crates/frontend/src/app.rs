@@ -4,6 +4,8 @@ use leptos_router::*;
 use serde_json::json;
 
 use crate::hoc::layout::Layout;
+use crate::pages::change_request::ChangeRequestPage;
+use crate::pages::change_request_list::ChangeRequestList;
 use crate::pages::dimensions::Dimensions;
 use crate::pages::experiment_list::ExperimentList;
 use crate::pages::function::{
@@ -199,6 +201,30 @@ pub fn app(app_envs: Envs) -> impl IntoView {
                                 }
                             />
 
+                            <Route
+                                ssr=SsrMode::Async
+                                path="/admin/:tenant/change-requests"
+                                view=move || {
+                                    view! {
+                                        <Layout>
+                                            <ChangeRequestList/>
+                                        </Layout>
+                                    }
+                                }
+                            />
+
+                            <Route
+                                ssr=SsrMode::Async
+                                path="/admin/:tenant/change-requests/:id"
+                                view=move || {
+                                    view! {
+                                        <Layout>
+                                            <ChangeRequestPage/>
+                                        </Layout>
+                                    }
+                                }
+                            />
+
                             <Route
                                 ssr=SsrMode::Async
                                 path="/admin/:tenant/types"
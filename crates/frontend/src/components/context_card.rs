@@ -24,6 +24,7 @@ pub fn context_card(
         (),
     >,
     #[prop(default=Callback::new(|_| {}))] handle_delete: Callback<String, ()>,
+    #[prop(default = vec![])] warnings: Vec<String>,
 ) -> impl IntoView {
     let conditions: Vec<Condition> = (&context).try_into().unwrap_or(vec![]);
 
@@ -102,6 +103,25 @@ pub fn context_card(
                 </Show>
             </div>
 
+            {(!warnings.is_empty())
+                .then(|| {
+                    view! {
+                        <div class="alert alert-warning flex flex-col items-start gap-1 text-sm">
+                            {warnings
+                                .into_iter()
+                                .map(|warning| {
+                                    view! {
+                                        <span>
+                                            <i class="ri-error-warning-line mr-2"></i>
+                                            {warning}
+                                        </span>
+                                    }
+                                })
+                                .collect_view()}
+                        </div>
+                    }
+                })}
+
             <div class="pl-5">
                 <ConditionComponent
                     // Clone only once before reusing in multiple closures
@@ -133,7 +133,7 @@ where
                                                 condition.left_operand.to_string(),
                                             );
                                             let schema: Map<String, Value> = serde_json::from_value(
-                                                    dimensions_map.get(&dimension_label).unwrap().schema.clone(),
+                                                    dimensions_map.get(&dimension_label).unwrap().resolved_schema.clone(),
                                                 )
                                                 .unwrap();
                                             let dimension_type = get_key_type(&schema);
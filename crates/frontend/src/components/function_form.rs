@@ -1,10 +1,12 @@
 pub mod types;
 pub mod utils;
 
-use self::utils::{create_function, test_function, update_function};
+use self::utils::{
+    create_function, run_test_cases, save_test_cases, test_function, update_function,
+};
 use crate::{
     components::{button::Button, monaco_editor::MonacoEditor},
-    types::FunctionTestResponse,
+    types::{FunctionTestCase, FunctionTestResponse, TestOutcome, TestSuiteResult},
 };
 use leptos::*;
 use serde_json::{from_str, json, Value};
@@ -319,3 +321,265 @@ pub fn test_form(function_name: String, stage: String) -> impl IntoView {
             </div>
     }
 }
+
+#[component]
+pub fn test_cases<NF>(
+    function_name: String,
+    stage: String,
+    test_cases: Vec<FunctionTestCase>,
+    handle_save: NF,
+) -> impl IntoView
+where
+    NF: Fn() + 'static + Clone,
+{
+    let tenant_rs = use_context::<ReadSignal<String>>().unwrap();
+    let (test_cases_rs, test_cases_ws) = create_signal(test_cases);
+    let (results_rs, results_ws) = create_signal::<Option<TestSuiteResult>>(None);
+    let (error_message, set_error_message) = create_signal(String::new());
+    let (key, set_key) = create_signal(String::new());
+    let (val, set_val) = create_signal(json!(""));
+    let (expected, set_expected) = create_signal(TestOutcome::Pass);
+    let (req_inprogess_rs, req_inprogress_ws) = create_signal(false);
+    let function_name = store_value(function_name);
+    let stage = store_value(stage);
+
+    let on_add = move |event: MouseEvent| {
+        event.prevent_default();
+        test_cases_ws.update(|test_cases| {
+            test_cases.push(FunctionTestCase {
+                key: key.get(),
+                value: val.get(),
+                expected: expected.get(),
+            })
+        });
+        results_ws.set(None);
+    };
+
+    let on_save = move |event: MouseEvent| {
+        event.prevent_default();
+        req_inprogress_ws.set(true);
+        let tenant = tenant_rs.get();
+        let handle_save = handle_save.clone();
+        spawn_local(async move {
+            let result = save_test_cases(
+                function_name.get_value(),
+                test_cases_rs.get_untracked(),
+                tenant,
+            )
+            .await;
+            match result {
+                Ok(_) => {
+                    set_error_message.set(String::new());
+                    handle_save();
+                }
+                Err(e) => set_error_message.set(e),
+            }
+            req_inprogress_ws.set(false);
+        });
+    };
+
+    let on_run = move |event: MouseEvent| {
+        event.prevent_default();
+        req_inprogress_ws.set(true);
+        let tenant = tenant_rs.get();
+        spawn_local(async move {
+            let result =
+                run_test_cases(function_name.get_value(), stage.get_value(), tenant)
+                    .await;
+            match result {
+                Ok(resp) => {
+                    set_error_message.set(String::new());
+                    results_ws.set(Some(resp));
+                }
+                Err(e) => {
+                    results_ws.set(None);
+                    set_error_message.set(e);
+                }
+            }
+            req_inprogress_ws.set(false);
+        });
+    };
+
+    view! {
+        <div class="card bg-base-100 shadow m-5 p-5">
+            <h2 class="card-title">Saved Test Cases</h2>
+            <p class="text-sm text-gray-500">
+                "Publishing is blocked while any saved test case fails against the draft code. Run them against the saved code, unsaved changes are not run."
+            </p>
+            <table class="table table-zebra mt-4">
+                <thead>
+                    <tr>
+                        <th>Key</th>
+                        <th>Value</th>
+                        <th>Expected</th>
+                        <th>Result</th>
+                        <th></th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || {
+                        let results = results_rs.get();
+                        test_cases_rs
+                            .get()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(idx, test_case)| {
+                                let result = results
+                                    .as_ref()
+                                    .and_then(|results| results.results.get(idx).cloned());
+                                view! {
+                                    <tr>
+                                        <td>{test_case.key}</td>
+                                        <td class="font-mono">{test_case.value.to_string()}</td>
+                                        <td>{test_case.expected.to_string()}</td>
+                                        <td>
+                                            {match result {
+                                                Some(result) if result.passed => {
+                                                    view! {
+                                                        <span class="badge badge-success text-white">
+                                                            {result.actual.to_string()}
+                                                        </span>
+                                                    }
+                                                        .into_view()
+                                                }
+                                                Some(result) => {
+                                                    view! {
+                                                        <span
+                                                            class="badge badge-error text-white"
+                                                            title=result.message
+                                                        >
+                                                            {result.actual.to_string()}
+                                                        </span>
+                                                    }
+                                                        .into_view()
+                                                }
+                                                None => view! { <span>"-"</span> }.into_view(),
+                                            }}
+
+                                        </td>
+                                        <td>
+                                            <button
+                                                class="btn btn-ghost btn-xs"
+                                                on:click=move |event| {
+                                                    event.prevent_default();
+                                                    test_cases_ws
+                                                        .update(|test_cases| {
+                                                            test_cases.remove(idx);
+                                                        });
+                                                    results_ws.set(None);
+                                                }
+                                            >
+
+                                                <i class="ri-delete-bin-5-line"></i>
+                                            </button>
+                                        </td>
+                                    </tr>
+                                }
+                            })
+                            .collect_view()
+                    }}
+
+                </tbody>
+            </table>
+
+            <div class="flex flex-row gap-3 items-end mt-4">
+                <div class="form-control">
+                    <label class="label">
+                        <span class="label-text">Key Name</span>
+                    </label>
+                    <input
+                        value=move || key.get()
+                        on:input=move |ev| set_key.set(event_target_value(&ev))
+                        type="text"
+                        placeholder="key"
+                        class="input input-bordered"
+                    />
+                </div>
+                <div class="form-control">
+                    <label class="label">
+                        <span class="label-text">Value</span>
+                    </label>
+                    <input
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_val.set(from_str::<Value>(&value).unwrap_or(json!(value)));
+                        }
+
+                        type="text"
+                        placeholder="value"
+                        class="input input-bordered"
+                    />
+                </div>
+                <div class="form-control">
+                    <label class="label">
+                        <span class="label-text">Expected</span>
+                    </label>
+                    <select
+                        class="select select-bordered"
+                        on:change=move |ev| {
+                            let outcome = match event_target_value(&ev).as_str() {
+                                "FAIL" => TestOutcome::Fail,
+                                _ => TestOutcome::Pass,
+                            };
+                            set_expected.set(outcome);
+                        }
+                    >
+
+                        <option value="PASS" selected=true>
+                            PASS
+                        </option>
+                        <option value="FAIL">FAIL</option>
+                    </select>
+                </div>
+                <button class="btn btn-outline" on:click=on_add>
+                    <i class="ri-add-line"></i>
+                    Add
+                </button>
+            </div>
+
+            <div class="flex justify-end gap-3 mt-8">
+                {move || {
+                    let loading = req_inprogess_rs.get();
+                    view! {
+                        <Button
+                            class="w-36 h-12".to_string()
+                            text="Save".to_string()
+                            on_click=on_save.clone()
+                            loading
+                        />
+                        <Button
+                            class="w-36 h-12".to_string()
+                            text="Run".to_string()
+                            on_click=on_run.clone()
+                            loading
+                        />
+                    }
+                }}
+
+            </div>
+
+            <div class="mt-4">
+                <p class="text-red-500">{move || error_message.get()}</p>
+                {move || {
+                    results_rs
+                        .get()
+                        .map(|results| {
+                            let failed = results.results.iter().filter(|r| !r.passed).count();
+                            if results.passed {
+                                view! {
+                                    <p class="text-green-700">"All test cases passed"</p>
+                                }
+                            } else {
+                                view! {
+                                    <p class="text-red-500">
+                                        {format!("{failed} test case(s) failed")}
+                                    </p>
+                                }
+                            }
+                        })
+                }}
+
+            </div>
+        </div>
+    }
+}
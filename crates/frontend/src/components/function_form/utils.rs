@@ -1,6 +1,6 @@
 use super::types::{FunctionCreateRequest, FunctionUpdateRequest};
 use crate::{
-    types::{FunctionResponse, FunctionTestResponse},
+    types::{FunctionResponse, FunctionTestCase, FunctionTestResponse, TestSuiteResult},
     utils::{construct_request_headers, get_host, parse_json_response, request},
 };
 use serde_json::Value;
@@ -77,3 +77,39 @@ pub async fn test_function(
 
     parse_json_response(response).await
 }
+
+pub async fn save_test_cases(
+    function_name: String,
+    test_cases: Vec<FunctionTestCase>,
+    tenant: String,
+) -> Result<FunctionResponse, String> {
+    let host = get_host();
+    let url = format!("{host}/function/{function_name}/test-cases");
+
+    let response = request(
+        url,
+        reqwest::Method::PUT,
+        Some(test_cases),
+        construct_request_headers(&[("x-tenant", &tenant)])?,
+    )
+    .await?;
+    parse_json_response(response).await
+}
+
+pub async fn run_test_cases(
+    function_name: String,
+    stage: String,
+    tenant: String,
+) -> Result<TestSuiteResult, String> {
+    let host = get_host();
+    let url = format!("{host}/function/{function_name}/{stage}/test-cases/run");
+
+    let response = request(
+        url,
+        reqwest::Method::PUT,
+        None::<Value>,
+        construct_request_headers(&[("x-tenant", &tenant)])?,
+    )
+    .await?;
+    parse_json_response(response).await
+}
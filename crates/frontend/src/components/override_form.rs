@@ -141,7 +141,7 @@ where
     let handle_config_key_select = Callback::new(move |default_config: DefaultConfig| {
         let config_key = default_config.key;
 
-        if let Ok(config_type) = SchemaType::try_from(default_config.schema) {
+        if let Ok(config_type) = SchemaType::try_from(default_config.resolved_schema) {
             let def_value = config_type.default_value();
             set_overrides.update(|value| {
                 value.push((config_key.clone(), def_value));
@@ -229,14 +229,14 @@ where
                                     default_config_map
                                         .get(&config_key.clone())
                                         .unwrap()
-                                        .schema
+                                        .resolved_schema
                                         .clone(),
                                 );
                                 let enum_variants = EnumVariants::try_from(
                                     default_config_map
                                         .get(&config_key.clone())
                                         .unwrap()
-                                        .schema
+                                        .resolved_schema
                                         .clone(),
                                 );
                                 view! {
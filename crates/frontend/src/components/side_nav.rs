@@ -40,6 +40,12 @@ fn create_routes(tenant: &str) -> Vec<AppRoute> {
             icon: "ri-guide-fill".to_string(),
             label: "Overrides".to_string(),
         },
+        AppRoute {
+            key: format!("{base}/admin/{tenant}/change-requests"),
+            path: format!("{base}/admin/{tenant}/change-requests"),
+            icon: "ri-git-pull-request-fill".to_string(),
+            label: "Change Requests".to_string(),
+        },
         AppRoute {
             key: format!("{base}/admin/{tenant}/resolve"),
             path: format!("{base}/admin/{tenant}/resolve"),
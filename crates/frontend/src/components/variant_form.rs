@@ -49,7 +49,12 @@ where
     let key_to_type = StoredValue::new(
         default_config
             .iter()
-            .map(|d| (d.key.clone(), SchemaType::try_from(d.schema.clone()).ok()))
+            .map(|d| {
+                (
+                    d.key.clone(),
+                    SchemaType::try_from(d.resolved_schema.clone()).ok(),
+                )
+            })
             .collect::<HashMap<String, Option<SchemaType>>>(),
     );
     let default_config = StoredValue::new(default_config);
@@ -96,7 +101,7 @@ where
 
     let on_key_select = Callback::new(move |default_config: DefaultConfig| {
         let config_key = default_config.key;
-        if let Ok(config_type) = SchemaType::try_from(default_config.schema) {
+        if let Ok(config_type) = SchemaType::try_from(default_config.resolved_schema) {
             let def_value = config_type.default_value();
 
             set_variants.update(|current_variants: &mut Vec<(String, VariantFormT)>| {
@@ -1,3 +1,5 @@
+pub mod change_request;
+pub mod change_request_list;
 pub mod context_override;
 pub mod custom_types;
 pub mod default_config;
@@ -0,0 +1,248 @@
+pub mod utils;
+
+use leptos::*;
+use leptos_router::use_params_map;
+use serde_json::Value;
+
+use crate::{
+    api::fetch_change_request,
+    components::{
+        alert::AlertType,
+        skeleton::{Skeleton, SkeletonVariant},
+    },
+    pages::change_request_list::utils::status_badge_class,
+    providers::alert_provider::enqueue_alert,
+    schema::HtmlDisplay,
+    types::{ChangeRequestResponse, ChangeRequestStatus},
+};
+
+use self::utils::{add_comment, approve_change_request, reject_change_request};
+
+#[component]
+fn json_view(heading: &'static str, value: Value) -> impl IntoView {
+    view! {
+        <div class="card bg-base-100 shadow">
+            <div class="card-body">
+                <h2 class="card-title">{heading}</h2>
+                <andypf-json-viewer
+                    indent="4"
+                    expanded="true"
+                    theme="default-light"
+                    show-data-types="false"
+                    show-toolbar="true"
+                    expand-icon-type="arrow"
+                    show-copy="true"
+                    show-size="false"
+                    data=value.html_display()
+                ></andypf-json-viewer>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn change_request_page() -> impl IntoView {
+    let params = use_params_map();
+    let tenant_rs = use_context::<ReadSignal<String>>().unwrap();
+    let (comment_rs, comment_ws) = create_signal(String::new());
+    let source = move || {
+        let id = params.with(|params| params.get("id").cloned().unwrap_or_default());
+        (id, tenant_rs.get())
+    };
+
+    let change_request_resource: Resource<
+        (String, String),
+        Option<ChangeRequestResponse>,
+    > = create_blocking_resource(source, |(id, tenant)| async move {
+        fetch_change_request(id, tenant).await.ok()
+    });
+
+    let comment = move || {
+        let comment = comment_rs.get_untracked();
+        (!comment.trim().is_empty()).then_some(comment)
+    };
+    let on_success = move |message: &str| {
+        enqueue_alert(message.to_string(), AlertType::Success, 5000);
+        comment_ws.set(String::new());
+        change_request_resource.refetch();
+    };
+
+    let handle_approve = move |id: String| {
+        spawn_local(async move {
+            let tenant = tenant_rs.get_untracked();
+            if approve_change_request(&id, comment(), &tenant)
+                .await
+                .is_ok()
+            {
+                on_success("Change request approved and applied");
+            }
+        })
+    };
+    let handle_reject = move |id: String| {
+        spawn_local(async move {
+            let tenant = tenant_rs.get_untracked();
+            if reject_change_request(&id, comment(), &tenant).await.is_ok() {
+                on_success("Change request rejected");
+            }
+        })
+    };
+    let handle_comment = move |id: String| {
+        spawn_local(async move {
+            let tenant = tenant_rs.get_untracked();
+            if let Some(comment) = comment() {
+                if add_comment(&id, comment, &tenant).await.is_ok() {
+                    on_success("Comment added");
+                }
+            }
+        })
+    };
+
+    view! {
+        <Suspense fallback=move || {
+            view! {
+                <div class="m-4">
+                    <Skeleton variant=SkeletonVariant::DetailPage/>
+                </div>
+            }
+        }>
+            {move || {
+                let ChangeRequestResponse { change_request, comments } = match change_request_resource
+                    .get()
+                    .flatten()
+                {
+                    Some(res) => res,
+                    None => {
+                        return view! { <h1>Error fetching change request</h1> }.into_view();
+                    }
+                };
+                let id = change_request.id.to_string();
+                let (approve_id, reject_id, comment_id) = (id.clone(), id.clone(), id.clone());
+                let is_pending = change_request.status == ChangeRequestStatus::PENDING;
+                let status = change_request.status.to_string();
+                let operation = change_request
+                    .change
+                    .get("operation")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                let reviewers = if change_request.reviewers.is_empty() {
+                    String::from("anyone")
+                } else {
+                    change_request.reviewers.join(", ")
+                };
+                view! {
+                    <div class="flex flex-col gap-4 p-8">
+                        <div class="flex justify-between">
+                            <h1 class="text-2xl font-bold">
+                                {format!("Change Request {id}")}
+                                <span class=format!("{} ml-4", status_badge_class(&status))>
+                                    <span class="text-white font-semibold text-xs">{status}</span>
+                                </span>
+                            </h1>
+                            <Show when=move || is_pending>
+                                <div class="join">
+                                    <button
+                                        class="btn join-item btn-success text-white"
+                                        on:click={
+                                            let id = approve_id.clone();
+                                            move |_| handle_approve(id.clone())
+                                        }
+                                    >
+                                        Approve
+                                        <i class="ri-check-line ml-2"></i>
+                                    </button>
+                                    <button
+                                        class="btn join-item btn-error text-white"
+                                        on:click={
+                                            let id = reject_id.clone();
+                                            move |_| handle_reject(id.clone())
+                                        }
+                                    >
+                                        Reject
+                                        <i class="ri-close-line ml-2"></i>
+                                    </button>
+                                </div>
+                            </Show>
+                        </div>
+                        <div class="stats shadow">
+                            <div class="stat">
+                                <div class="stat-title">Operation</div>
+                                <div class="stat-value text-sm">{operation}</div>
+                            </div>
+                            <div class="stat">
+                                <div class="stat-title">Author</div>
+                                <div class="stat-value text-sm">{change_request.created_by}</div>
+                            </div>
+                            <div class="stat">
+                                <div class="stat-title">Reviewers</div>
+                                <div class="stat-value text-sm">{reviewers}</div>
+                            </div>
+                            <div class="stat">
+                                <div class="stat-title">Created At</div>
+                                <div class="stat-value text-sm">
+                                    {change_request.created_at.format("%v %T").to_string()}
+                                </div>
+                            </div>
+                            <div class="stat">
+                                <div class="stat-title">Reviewed By</div>
+                                <div class="stat-value text-sm">
+                                    {change_request.reviewed_by.unwrap_or("-".to_string())}
+                                </div>
+                            </div>
+                            <div class="stat">
+                                <div class="stat-title">Config Version</div>
+                                <div class="stat-value text-sm">
+                                    {change_request
+                                        .config_version
+                                        .map(|version| version.to_string())
+                                        .unwrap_or("-".to_string())}
+                                </div>
+                            </div>
+                        </div>
+                        <JsonView heading="Config Diff" value=change_request.diff/>
+                        <JsonView heading="Change" value=change_request.change/>
+                        <div class="card bg-base-100 shadow">
+                            <div class="card-body">
+                                <h2 class="card-title">Comments</h2>
+                                {comments
+                                    .into_iter()
+                                    .map(|comment| {
+                                        view! {
+                                            <div class="border-b py-2">
+                                                <div class="text-xs text-gray-500">
+                                                    {format!(
+                                                        "{} on {}",
+                                                        comment.created_by,
+                                                        comment.created_at.format("%v %T"),
+                                                    )}
+                                                </div>
+                                                <div class="whitespace-pre-wrap">{comment.comment}</div>
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()}
+                                <textarea
+                                    class="textarea textarea-bordered w-full mt-2"
+                                    placeholder="Leave a comment, it is also attached when approving or rejecting"
+                                    prop:value=move || comment_rs.get()
+                                    on:input=move |ev| comment_ws.set(event_target_value(&ev))
+                                ></textarea>
+                                <div class="card-actions justify-end">
+                                    <button
+                                        class="btn btn-purple"
+                                        on:click=move |_| handle_comment(comment_id.clone())
+                                    >
+                                        Comment
+                                        <i class="ri-chat-3-line ml-2"></i>
+                                    </button>
+                                </div>
+                            </div>
+                        </div>
+                    </div>
+                }
+                    .into_view()
+            }}
+
+        </Suspense>
+    }
+}
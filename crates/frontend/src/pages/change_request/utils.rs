@@ -0,0 +1,56 @@
+use serde_json::{json, Value};
+
+use crate::utils::{construct_request_headers, get_host, request};
+
+async fn review_change_request(
+    id: &String,
+    action: &str,
+    comment: Option<String>,
+    tenant: &String,
+) -> Result<Value, String> {
+    let host = get_host();
+    let url = format!("{host}/change-requests/{id}/{action}");
+
+    let response = request(
+        url,
+        reqwest::Method::POST,
+        Some(json!({ "comment": comment })),
+        construct_request_headers(&[("x-tenant", tenant)])?,
+    )
+    .await?;
+    response.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn approve_change_request(
+    id: &String,
+    comment: Option<String>,
+    tenant: &String,
+) -> Result<Value, String> {
+    review_change_request(id, "approve", comment, tenant).await
+}
+
+pub async fn reject_change_request(
+    id: &String,
+    comment: Option<String>,
+    tenant: &String,
+) -> Result<Value, String> {
+    review_change_request(id, "reject", comment, tenant).await
+}
+
+pub async fn add_comment(
+    id: &String,
+    comment: String,
+    tenant: &String,
+) -> Result<Value, String> {
+    let host = get_host();
+    let url = format!("{host}/change-requests/{id}/comments");
+
+    let response = request(
+        url,
+        reqwest::Method::POST,
+        Some(json!({ "comment": comment })),
+        construct_request_headers(&[("x-tenant", tenant)])?,
+    )
+    .await?;
+    response.json().await.map_err(|e| e.to_string())
+}
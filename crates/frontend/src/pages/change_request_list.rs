@@ -0,0 +1,162 @@
+pub mod utils;
+
+use leptos::*;
+use serde_json::{json, Map, Value};
+use web_sys::Event;
+
+use crate::api::fetch_change_requests;
+use crate::components::skeleton::Skeleton;
+use crate::components::stat::Stat;
+use crate::components::table::{types::TablePaginationProps, Table};
+use crate::types::{ChangeRequestFilters, ChangeRequestStatus, ChangeRequestsResponse};
+
+use self::utils::change_request_table_columns;
+
+#[component]
+pub fn change_request_list() -> impl IntoView {
+    let tenant_rs = use_context::<ReadSignal<String>>().unwrap();
+    let (filters, set_filters) = create_signal(ChangeRequestFilters {
+        status: Some(ChangeRequestStatus::PENDING),
+        page: 1,
+        count: 10,
+    });
+    let table_columns = store_value(change_request_table_columns());
+
+    let change_requests_resource = create_blocking_resource(
+        move || (tenant_rs.get(), filters.get()),
+        |(tenant, filters)| async move {
+            fetch_change_requests(filters, tenant).await.unwrap_or(
+                ChangeRequestsResponse {
+                    total_items: 0,
+                    total_pages: 0,
+                    data: vec![],
+                },
+            )
+        },
+    );
+
+    let handle_next_click = Callback::new(move |total_pages: i64| {
+        set_filters.update(|f| {
+            if f.page < total_pages {
+                f.page += 1;
+            }
+        });
+    });
+
+    let handle_prev_click = Callback::new(move |_| {
+        set_filters.update(|f| {
+            if f.page > 1 {
+                f.page -= 1;
+            }
+        });
+    });
+
+    let handle_status_change = move |event: Event| {
+        let status = match event_target_value(&event).as_str() {
+            "PENDING" => Some(ChangeRequestStatus::PENDING),
+            "APPLIED" => Some(ChangeRequestStatus::APPLIED),
+            "REJECTED" => Some(ChangeRequestStatus::REJECTED),
+            _ => None,
+        };
+        set_filters.update(|f| {
+            f.status = status;
+            f.page = 1;
+        });
+    };
+
+    view! {
+        <div class="p-8">
+            <Suspense fallback=move || view! { <Skeleton/> }>
+                <div class="pb-4">
+
+                    {move || {
+                        let total_items = change_requests_resource
+                            .get()
+                            .map(|v| v.total_items)
+                            .unwrap_or_default()
+                            .to_string();
+                        view! {
+                            <Stat
+                                heading="Change Requests"
+                                icon="ri-git-pull-request-fill"
+                                number=total_items
+                            />
+                        }
+                    }}
+
+                </div>
+                <div class="card rounded-xl w-full bg-base-100 shadow">
+                    <div class="card-body">
+                        <div class="flex justify-between">
+                            <h2 class="card-title">Change Requests</h2>
+                            <select
+                                class="select select-bordered select-sm"
+                                on:change=handle_status_change
+                            >
+                                <option value="PENDING" selected=true>
+                                    Pending
+                                </option>
+                                <option value="APPLIED">Applied</option>
+                                <option value="REJECTED">Rejected</option>
+                                <option value="ALL">All</option>
+                            </select>
+                        </div>
+                        {move || {
+                            let filters = filters.get();
+                            match change_requests_resource.get() {
+                                Some(v) => {
+                                    let data = v
+                                        .data
+                                        .iter()
+                                        .map(|ele| {
+                                            let mut ele_map = json!(ele)
+                                                .as_object()
+                                                .unwrap()
+                                                .to_owned();
+                                            ele_map.insert("id".to_string(), json!(ele.id.to_string()));
+                                            ele_map
+                                                .insert(
+                                                    "operation".to_string(),
+                                                    ele.change.get("operation").cloned().unwrap_or_default(),
+                                                );
+                                            ele_map
+                                                .insert(
+                                                    "created_at".to_string(),
+                                                    json!(ele.created_at.format("%v").to_string()),
+                                                );
+                                            ele_map
+                                                .insert(
+                                                    "reviewed_by".to_string(),
+                                                    json!(ele.reviewed_by.clone().unwrap_or("-".to_string())),
+                                                );
+                                            ele_map
+                                        })
+                                        .collect::<Vec<Map<String, Value>>>();
+                                    let pagination_props = TablePaginationProps {
+                                        enabled: true,
+                                        count: filters.count,
+                                        current_page: filters.page,
+                                        total_pages: v.total_pages,
+                                        on_next: handle_next_click,
+                                        on_prev: handle_prev_click,
+                                    };
+                                    view! {
+                                        <Table
+                                            cell_class="min-w-48 font-mono".to_string()
+                                            rows=data
+                                            key_column="id".to_string()
+                                            columns=table_columns.get_value()
+                                            pagination=pagination_props
+                                        />
+                                    }
+                                }
+                                None => view! { <div>Loading....</div> }.into_view(),
+                            }
+                        }}
+
+                    </div>
+                </div>
+            </Suspense>
+        </div>
+    }
+}
@@ -0,0 +1,53 @@
+use leptos::*;
+use leptos_router::A;
+use serde_json::{Map, Value};
+
+use crate::components::table::types::Column;
+
+pub fn status_badge_class(status: &str) -> &'static str {
+    match status {
+        "PENDING" => "badge badge-warning",
+        "APPLIED" => "badge badge-success",
+        "REJECTED" => "badge badge-error",
+        _ => "badge badge-info",
+    }
+}
+
+pub fn change_request_table_columns() -> Vec<Column> {
+    vec![
+        Column::new("id".to_string(), None, |value: &str, _| {
+            view! {
+                <A href=value.to_string() class="btn-link">
+                    {value.to_string()}
+                </A>
+            }
+            .into_view()
+        }),
+        Column::new("operation".to_string(), None, |value: &str, _| {
+            view! { <span class="font-semibold">{value.to_string()}</span> }.into_view()
+        }),
+        Column::new("status".to_string(), None, |value: &str, _| {
+            view! {
+                <div class=status_badge_class(value)>
+                    <span class="text-white font-semibold text-xs">{value.to_string()}</span>
+                </div>
+            }
+            .into_view()
+        }),
+        Column::default("created_by".to_string()),
+        Column::new(
+            "reviewers".to_string(),
+            None,
+            |value: &str, _: &Map<String, Value>| {
+                let reviewers = if value.is_empty() {
+                    String::from("anyone")
+                } else {
+                    value.replace(',', ", ")
+                };
+                view! { <span>{reviewers}</span> }.into_view()
+            },
+        ),
+        Column::default("created_at".to_string()),
+        Column::default("reviewed_by".to_string()),
+    ]
+}
@@ -1,5 +1,7 @@
 use crate::api::fetch_config;
-use crate::api::{delete_context, fetch_default_config, fetch_dimensions};
+use crate::api::{
+    delete_context, fetch_context_analysis, fetch_default_config, fetch_dimensions,
+};
 use crate::components::alert::AlertType;
 use crate::components::button::Button;
 use crate::components::condition_pills::types::{Condition, ConditionOperator};
@@ -14,7 +16,7 @@ use crate::components::skeleton::{Skeleton, SkeletonVariant};
 use crate::providers::alert_provider::enqueue_alert;
 use crate::providers::condition_collapse_provider::ConditionCollapseProvider;
 use crate::providers::editor_provider::EditorProvider;
-use crate::types::{Config, Context, DefaultConfig, Dimension};
+use crate::types::{Config, Context, ContextAnalysis, DefaultConfig, Dimension};
 use futures::join;
 use leptos::*;
 use serde::{Deserialize, Serialize};
@@ -31,6 +33,7 @@ struct PageResource {
     config: Config,
     dimensions: Vec<Dimension>,
     default_config: Vec<DefaultConfig>,
+    analysis: ContextAnalysis,
 }
 
 #[derive(Debug, Clone)]
@@ -144,10 +147,16 @@ pub fn context_override() -> impl IntoView {
     let page_resource: Resource<String, PageResource> = create_blocking_resource(
         move || tenant_rs.get().clone(),
         |current_tenant| async move {
-            let (config_result, dimensions_result, default_config_result) = join!(
+            let (
+                config_result,
+                dimensions_result,
+                default_config_result,
+                analysis_result,
+            ) = join!(
                 fetch_config(current_tenant.to_string()),
                 fetch_dimensions(current_tenant.to_string()),
-                fetch_default_config(current_tenant.to_string())
+                fetch_default_config(current_tenant.to_string()),
+                fetch_context_analysis(current_tenant.to_string())
             );
             PageResource {
                 config: config_result.unwrap_or_default(),
@@ -157,6 +166,7 @@ pub fn context_override() -> impl IntoView {
                     .filter(|d| d.dimension != "variantIds")
                     .collect(),
                 default_config: default_config_result.unwrap_or_default(),
+                analysis: analysis_result.unwrap_or_default(),
             }
         },
     );
@@ -264,7 +274,7 @@ pub fn context_override() -> impl IntoView {
                 <div class="space-y-6">
 
                     {move || {
-                        let PageResource { config: _, dimensions, default_config } = page_resource
+                        let PageResource { dimensions, default_config, .. } = page_resource
                             .get()
                             .unwrap_or_default();
                         let data = selected_data.get();
@@ -329,7 +339,9 @@ pub fn context_override() -> impl IntoView {
                         }
                     }}
                     {move || {
-                        let config = page_resource.get().map(|v| v.config).unwrap_or_default();
+                        let PageResource { config, analysis, .. } = page_resource
+                            .get()
+                            .unwrap_or_default();
                         let ctx_n_overrides = config
                             .contexts
                             .into_iter()
@@ -373,10 +385,12 @@ pub fn context_override() -> impl IntoView {
                                     ctx_n_overrides
                                         .into_iter()
                                         .map(|(context, overrides)| {
+                                            let warnings = analysis.warnings_for(&context.id);
                                             view! {
                                                 <ContextCard
                                                     context=context
                                                     overrides=overrides
+                                                    warnings=warnings
                                                     handle_edit=handle_context_edit
                                                     handle_clone=handle_context_clone
                                                     handle_delete=handle_context_delete
@@ -22,7 +22,7 @@ use crate::utils::get_element_by_id;
 use web_sys::HtmlButtonElement;
 
 use crate::components::{
-    function_form::{FunctionEditor, TestForm},
+    function_form::{FunctionEditor, TestCases, TestForm},
     monaco_editor::MonacoEditor,
 };
 
@@ -396,6 +396,12 @@ pub fn function_page() -> impl IntoView {
                                                                             />
 
                                                                         </div>
+                                                                        <TestCases
+                                                                            function_name=function_rs.get().function_name
+                                                                            stage="PUBLISHED".to_string()
+                                                                            test_cases=function_rs.get().test_cases
+                                                                            handle_save=move || combined_resource.refetch()
+                                                                        />
 
                                                                     </Show>
                                                                 }
@@ -463,6 +469,12 @@ pub fn function_page() -> impl IntoView {
                                                                             />
                                                                         </div>
                                                                     </div>
+                                                                    <TestCases
+                                                                        function_name=function_rs.get().function_name
+                                                                        stage="DRAFT".to_string()
+                                                                        test_cases=function_rs.get().test_cases
+                                                                        handle_save=move || combined_resource.refetch()
+                                                                    />
                                                                     </Show>
                                                                 }
                                                                     .into_view()
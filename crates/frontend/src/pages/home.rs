@@ -309,7 +309,11 @@ pub fn home() -> impl IntoView {
                 .await
                 .unwrap()
             {
-                Value::Object(m) => m,
+                // the resolved config comes alongside its trace when reasoning is requested
+                Value::Object(mut m) => match m.remove("config") {
+                    Some(Value::Object(config)) => config,
+                    _ => Map::new(),
+                },
                 _ => Map::new(),
             };
             logging::log!("resolved config {:#?}", config);
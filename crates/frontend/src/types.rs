@@ -64,6 +64,8 @@ pub struct FunctionResponse {
     pub draft_edited_at: NaiveDateTime,
     pub published_by: Option<String>,
     pub draft_edited_by: String,
+    #[serde(default)]
+    pub test_cases: Vec<FunctionTestCase>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -72,6 +74,39 @@ pub struct FunctionTestResponse {
     pub stdout: String,
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, strum_macros::Display,
+)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum TestOutcome {
+    Pass,
+    Fail,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FunctionTestCase {
+    pub key: String,
+    pub value: Value,
+    pub expected: TestOutcome,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TestCaseResult {
+    pub key: String,
+    pub value: Value,
+    pub expected: TestOutcome,
+    pub actual: TestOutcome,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TestSuiteResult {
+    pub passed: bool,
+    pub results: Vec<TestCaseResult>,
+}
+
 /*********************** Experimentation Types ****************************************/
 
 #[derive(
@@ -221,6 +256,8 @@ pub struct Dimension {
     pub created_at: DateTime<Utc>,
     pub created_by: String,
     pub schema: Value,
+    // the schema with its type references replaced by the templates
+    pub resolved_schema: Value,
     pub function_name: Option<String>,
     pub mandatory: bool,
 }
@@ -241,6 +278,8 @@ pub struct DefaultConfig {
     pub created_at: DateTime<Utc>,
     pub created_by: String,
     pub schema: Value,
+    // the schema with its type references replaced by the templates
+    pub resolved_schema: Value,
     pub function_name: Option<String>,
 }
 
@@ -260,6 +299,68 @@ pub struct Context {
     pub override_with_keys: [String; 1],
 }
 
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct OverrideConflict {
+    pub key: String,
+    pub winning_context_id: String,
+    pub winning_value: Value,
+    pub losing_context_id: String,
+    pub losing_value: Value,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ShadowedContext {
+    pub context_id: String,
+    pub shadowed_by: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RedundantOverride {
+    pub context_id: String,
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct ContextAnalysis {
+    pub conflicts: Vec<OverrideConflict>,
+    pub shadowed_contexts: Vec<ShadowedContext>,
+    pub redundant_overrides: Vec<RedundantOverride>,
+}
+
+impl ContextAnalysis {
+    pub fn warnings_for(&self, context_id: &str) -> Vec<String> {
+        let conflicts = self
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.losing_context_id == context_id)
+            .map(|conflict| {
+                format!(
+                    "{} is overridden with {} by context {} for requests matching both",
+                    conflict.key, conflict.winning_value, conflict.winning_context_id
+                )
+            });
+        let shadowed = self
+            .shadowed_contexts
+            .iter()
+            .filter(|shadowed| shadowed.context_id == context_id)
+            .map(|shadowed| {
+                format!(
+                    "Never applied, always overridden by {}",
+                    shadowed.shadowed_by.join(", ")
+                )
+            });
+        let redundant = self
+            .redundant_overrides
+            .iter()
+            .filter(|redundant| redundant.context_id == context_id)
+            .map(|redundant| {
+                format!("{} is the same as its default value", redundant.key)
+            });
+        shadowed.chain(conflicts).chain(redundant).collect()
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, Default)]
 pub struct Config {
     pub contexts: Vec<Context>,
@@ -314,3 +415,59 @@ pub struct FetchTypeTemplateResponse {
     pub total_pages: i64,
     pub data: Vec<TypeTemplate>,
 }
+
+/*********************** Change Request Types ****************************************/
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Deserialize, Serialize, strum_macros::Display,
+)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum ChangeRequestStatus {
+    PENDING,
+    APPLIED,
+    REJECTED,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChangeRequest {
+    pub id: i64,
+    pub status: ChangeRequestStatus,
+    pub change: Value,
+    pub diff: Value,
+    pub config_tags: Option<Vec<String>>,
+    pub reviewers: Vec<String>,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub config_version: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChangeRequestComment {
+    pub id: i64,
+    pub comment: String,
+    pub created_by: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChangeRequestResponse {
+    #[serde(flatten)]
+    pub change_request: ChangeRequest,
+    pub comments: Vec<ChangeRequestComment>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChangeRequestsResponse {
+    pub total_items: i64,
+    pub total_pages: i64,
+    pub data: Vec<ChangeRequest>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeRequestFilters {
+    pub status: Option<ChangeRequestStatus>,
+    pub page: i64,
+    pub count: i64,
+}
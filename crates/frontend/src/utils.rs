@@ -249,13 +249,15 @@ pub fn get_config_type(
     };
 
     config.and_then(|config| match config {
-        ConfigType::DefaultConfig(default_conf) => {
-            default_conf.schema.get("type").map(|t| types_mapping(t))
-        }
-
-        ConfigType::Dimension(dimension) => {
-            dimension.schema.get("type").map(|t| types_mapping(t))
-        }
+        ConfigType::DefaultConfig(default_conf) => default_conf
+            .resolved_schema
+            .get("type")
+            .map(|t| types_mapping(t)),
+
+        ConfigType::Dimension(dimension) => dimension
+            .resolved_schema
+            .get("type")
+            .map(|t| types_mapping(t)),
     })
 }
 
@@ -8,11 +8,13 @@ use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::de::{self, IntoDeserializer};
 use serde_json::{Map, Value};
 use std::{
+    cmp::Ordering,
+    collections::HashMap,
     env::VarError,
     fmt::{self, Display},
     str::FromStr,
 };
-use superposition_types::{result, Condition};
+use superposition_types::{result, semantic_version::compare_versions, Condition};
 
 const CONFIG_TAG_REGEX: &str = "^[a-zA-Z0-9_-]{1,64}$";
 
@@ -129,71 +131,432 @@ pub fn get_pod_info() -> (String, String) {
     (pod_id, deployment_id)
 }
 
-pub fn extract_dimensions(context: &Condition) -> result::Result<Map<String, Value>> {
-    // Assuming max 2-level nesting in context json logic
-
-    let conditions: Vec<Value> = match (*context).get("and") {
-        Some(conditions_json) => conditions_json
-            .as_array()
-            .ok_or(result::AppError::BadArgument("Error extracting dimensions, failed parsing conditions as an array. Ensure the context provided obeys the rules of JSON logic".into()))?
-            .clone(),
-        None => vec![Value::Object(context.to_owned().into())],
-    };
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConditionOperator {
+    Equals,
+    NotEquals,
+    In,
+    // `{"in": [value, {"var": dimension}]}`, the dimension holds a list of values
+    Contains,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
 
-    let mut dimension_tuples = Vec::new();
-    for condition in &conditions {
-        let condition_obj =
-            condition
-                .as_object()
-                .ok_or(result::AppError::BadArgument(
-                    "Failed to parse condition as an object. Ensure the context provided obeys the rules of JSON logic".to_string()
-                ))?;
-        let operators = condition_obj.keys();
-
-        for operator in operators {
-            let operands = condition_obj[operator].as_array().ok_or(result::AppError::BadArgument(
-                    "Failed to parse operands as an arrays. Ensure the context provided obeys the rules of JSON logic"
-                            .into()
-            ))?;
+impl ConditionOperator {
+    fn from_jsonlogic(operator: &str) -> Option<Self> {
+        match operator {
+            "==" => Some(Self::Equals),
+            "!=" => Some(Self::NotEquals),
+            "in" => Some(Self::In),
+            "<" => Some(Self::LessThan),
+            "<=" => Some(Self::LessThanOrEqual),
+            ">" => Some(Self::GreaterThan),
+            ">=" => Some(Self::GreaterThanOrEqual),
+            _ => None,
+        }
+    }
+
+    // the operator to use when the operands are swapped, `5 < $x` is `$x > 5`
+    fn flip(self) -> Self {
+        match self {
+            Self::LessThan => Self::GreaterThan,
+            Self::LessThanOrEqual => Self::GreaterThanOrEqual,
+            Self::GreaterThan => Self::LessThan,
+            Self::GreaterThanOrEqual => Self::LessThanOrEqual,
+            operator => operator,
+        }
+    }
+}
+
+impl Display for ConditionOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            Self::Equals => "==",
+            Self::NotEquals => "!=",
+            Self::In | Self::Contains => "in",
+            Self::LessThan => "<",
+            Self::LessThanOrEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterThanOrEqual => ">=",
+        };
+        write!(f, "{operator}")
+    }
+}
 
-            let (variable_name, variable_value) = get_variable_name_and_value(operands)?;
+#[derive(Debug, Clone, PartialEq)]
+pub struct DimensionConstraint {
+    pub operator: ConditionOperator,
+    pub value: Value,
+}
 
-            dimension_tuples.push((String::from(variable_name), variable_value.clone()));
+fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => {
+            compare_versions(a, b).or_else(|| Some(a.cmp(b)))
         }
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+    a == b || compare_values(a, b) == Some(Ordering::Equal)
+}
+
+impl DimensionConstraint {
+    /// Values the dimension is compared against, with `in` lists expanded.
+    pub fn values(&self) -> Vec<&Value> {
+        match (&self.operator, &self.value) {
+            (ConditionOperator::In, Value::Array(values)) => values.iter().collect(),
+            (_, value) => vec![value],
+        }
+    }
+
+    /// Whether the constraint orders the dimension against a value.
+    pub fn is_ordering(&self) -> bool {
+        self.is_lower_bound() || self.is_upper_bound()
     }
 
-    Ok(Map::from_iter(dimension_tuples))
+    fn is_lower_bound(&self) -> bool {
+        matches!(
+            self.operator,
+            ConditionOperator::GreaterThan | ConditionOperator::GreaterThanOrEqual
+        )
+    }
+
+    fn is_upper_bound(&self) -> bool {
+        matches!(
+            self.operator,
+            ConditionOperator::LessThan | ConditionOperator::LessThanOrEqual
+        )
+    }
+
+    fn is_inclusive(&self) -> bool {
+        matches!(
+            self.operator,
+            ConditionOperator::LessThanOrEqual | ConditionOperator::GreaterThanOrEqual
+        )
+    }
+
+    /// Whether `value` satisfies the constraint, `None` when it cannot be decided.
+    pub fn matches(&self, value: &Value) -> Option<bool> {
+        let ordering = || compare_values(value, &self.value);
+        match self.operator {
+            ConditionOperator::Equals => Some(values_equal(value, &self.value)),
+            ConditionOperator::NotEquals => Some(!values_equal(value, &self.value)),
+            ConditionOperator::In => Some(
+                self.values()
+                    .into_iter()
+                    .any(|candidate| values_equal(value, candidate)),
+            ),
+            ConditionOperator::Contains => None,
+            ConditionOperator::LessThan => ordering().map(|o| o == Ordering::Less),
+            ConditionOperator::LessThanOrEqual => {
+                ordering().map(|o| o != Ordering::Greater)
+            }
+            ConditionOperator::GreaterThan => ordering().map(|o| o == Ordering::Greater),
+            ConditionOperator::GreaterThanOrEqual => {
+                ordering().map(|o| o != Ordering::Less)
+            }
+        }
+    }
 }
 
-pub fn get_variable_name_and_value(operands: &[Value]) -> result::Result<(&str, &Value)> {
-    let (obj_pos, variable_obj) = operands
+// values allowed by the `==` and `in` constraints, if there are any
+fn candidate_values<'a>(
+    constraints: &[&'a DimensionConstraint],
+) -> Option<Vec<&'a Value>> {
+    constraints
         .iter()
-        .enumerate()
-        .find(|(_, operand)| {
-            operand.is_object() && operand.as_object().unwrap().get("var").is_some()
+        .filter(|constraint| {
+            matches!(
+                constraint.operator,
+                ConditionOperator::Equals | ConditionOperator::In
+            )
         })
-        .ok_or(result::AppError::BadArgument(
-            "Failed to get variable name from operands list. Ensure the context provided obeys the rules of JSON logic"
-                .into()
-        ))?;
+        .fold(None, |candidates: Option<Vec<&Value>>, constraint| {
+            let values = constraint.values();
+            Some(match candidates {
+                None => values,
+                Some(candidates) => candidates
+                    .into_iter()
+                    .filter(|candidate| {
+                        values.iter().any(|value| values_equal(candidate, value))
+                    })
+                    .collect(),
+            })
+        })
+}
+
+/*
+  Checks whether a single value of a dimension can satisfy all the constraints
+  together. `==` and `in` narrow the dimension down to a set of candidates, which
+  are checked against the rest of the constraints. Without candidates the lower and
+  upper bounds have to leave a non empty range, values are treated as dense so
+  `$x > 1 && $x < 2` is satisfiable even for integer dimensions. Constraints that
+  cannot be compared are assumed to be satisfiable.
+*/
+pub fn are_constraints_satisfiable(constraints: &[&DimensionConstraint]) -> bool {
+    let satisfies_all = |value: &Value| {
+        constraints
+            .iter()
+            .all(|constraint| constraint.matches(value).unwrap_or(true))
+    };
 
-    let variable_name = variable_obj
-        .as_object().and_then(|obj| obj.get("var")).and_then(|value| value.as_str())
+    if let Some(candidates) = candidate_values(constraints) {
+        return candidates.into_iter().any(satisfies_all);
+    }
+
+    let lower_bounds = constraints.iter().filter(|c| c.is_lower_bound());
+    for lower in lower_bounds {
+        for upper in constraints.iter().filter(|c| c.is_upper_bound()) {
+            match compare_values(&lower.value, &upper.value) {
+                Some(Ordering::Greater) => return false,
+                Some(Ordering::Equal)
+                    if !(lower.is_inclusive()
+                        && upper.is_inclusive()
+                        && satisfies_all(&lower.value)) =>
+                {
+                    return false
+                }
+                _ => (),
+            }
+        }
+    }
+    true
+}
+
+// whether every value within `bound` is also within `implied`, both bounding the same side
+fn is_bound_within(bound: &DimensionConstraint, implied: &DimensionConstraint) -> bool {
+    let tighter = if implied.is_lower_bound() {
+        Ordering::Greater
+    } else {
+        Ordering::Less
+    };
+    match compare_values(&bound.value, &implied.value) {
+        Some(ordering) if ordering == tighter => true,
+        Some(Ordering::Equal) => implied.is_inclusive() || !bound.is_inclusive(),
+        _ => false,
+    }
+}
+
+/*
+  Checks whether every value satisfying `constraints` also satisfies `implied`.
+  When that cannot be decided the constraints are taken as not implying it.
+*/
+pub fn constraints_imply(
+    constraints: &[&DimensionConstraint],
+    implied: &[&DimensionConstraint],
+) -> bool {
+    if let Some(candidates) = candidate_values(constraints) {
+        return candidates
+            .into_iter()
+            .filter(|candidate| {
+                constraints
+                    .iter()
+                    .all(|constraint| constraint.matches(candidate).unwrap_or(true))
+            })
+            .all(|candidate| {
+                implied
+                    .iter()
+                    .all(|constraint| constraint.matches(candidate) == Some(true))
+            });
+    }
+
+    implied.iter().all(|implied| match implied.operator {
+        ConditionOperator::NotEquals => {
+            let excluded = DimensionConstraint {
+                operator: ConditionOperator::Equals,
+                value: implied.value.clone(),
+            };
+            let mut with_excluded = constraints.to_vec();
+            with_excluded.push(&excluded);
+            !are_constraints_satisfiable(&with_excluded)
+        }
+        ConditionOperator::LessThan
+        | ConditionOperator::LessThanOrEqual
+        | ConditionOperator::GreaterThan
+        | ConditionOperator::GreaterThanOrEqual => constraints.iter().any(|bound| {
+            bound.is_lower_bound() == implied.is_lower_bound()
+                && bound.is_ordering()
+                && is_bound_within(bound, implied)
+        }),
+        _ => constraints.contains(implied),
+    })
+}
+
+fn is_variable(operand: &Value) -> bool {
+    operand
+        .as_object()
+        .map_or(false, |operand| operand.contains_key("var"))
+}
+
+fn get_variable_name(operand: &Value) -> result::Result<String> {
+    operand
+        .get("var")
+        .and_then(Value::as_str)
+        .map(String::from)
         .ok_or(result::AppError::BadArgument(
             "Failed to get variable name as string. Ensure the context provided obeys the rules of JSON logic"
                 .into()
-        ))?;
+        ))
+}
 
-    let value_pos = (obj_pos + 1) % 2;
-    let variable_value =
-        operands
-            .get(value_pos)
+fn collect_dimension_constraints(
+    condition: &Value,
+    constraints: &mut HashMap<String, Vec<DimensionConstraint>>,
+) -> result::Result<()> {
+    let condition_obj =
+        condition
+            .as_object()
             .ok_or(result::AppError::BadArgument(
-                "Failed to get variable value from operands list. Ensure the context provided obeys the rules of JSON logic"
-                    .into()
+                "Failed to parse condition as an object. Ensure the context provided obeys the rules of JSON logic".to_string()
             ))?;
 
-    Ok((variable_name, variable_value))
+    for (operator, operands) in condition_obj {
+        let operands = operands.as_array().ok_or(result::AppError::BadArgument(
+                "Failed to parse operands as an arrays. Ensure the context provided obeys the rules of JSON logic"
+                        .into()
+        ))?;
+
+        if operator == "and" {
+            for operand in operands {
+                collect_dimension_constraints(operand, constraints)?;
+            }
+            continue;
+        }
+
+        let condition_operator = ConditionOperator::from_jsonlogic(operator).ok_or(
+            result::AppError::BadArgument(format!(
+                "Unsupported operator {operator} in context, supported operators are and, ==, !=, in, <, <=, > and >="
+            )),
+        )?;
+
+        let mut dimension_constraints = Vec::new();
+        match (condition_operator, operands.as_slice()) {
+            (ConditionOperator::In, [variable, Value::Array(values)])
+                if is_variable(variable) =>
+            {
+                dimension_constraints.push((
+                    get_variable_name(variable)?,
+                    DimensionConstraint {
+                        operator: ConditionOperator::In,
+                        value: Value::Array(values.to_owned()),
+                    },
+                ));
+            }
+            (ConditionOperator::In, [value, variable]) if is_variable(variable) => {
+                dimension_constraints.push((
+                    get_variable_name(variable)?,
+                    DimensionConstraint {
+                        operator: ConditionOperator::Contains,
+                        value: value.to_owned(),
+                    },
+                ));
+            }
+            (ConditionOperator::In, _) => {
+                return Err(result::AppError::BadArgument(
+                    "Operands of in should be a dimension and a list of values, or a value and a dimension".into(),
+                ));
+            }
+            (
+                ConditionOperator::LessThan | ConditionOperator::LessThanOrEqual,
+                [lower, variable, upper],
+            ) if is_variable(variable) => {
+                let variable_name = get_variable_name(variable)?;
+                dimension_constraints.push((
+                    variable_name.clone(),
+                    DimensionConstraint {
+                        operator: condition_operator.flip(),
+                        value: lower.to_owned(),
+                    },
+                ));
+                dimension_constraints.push((
+                    variable_name,
+                    DimensionConstraint {
+                        operator: condition_operator,
+                        value: upper.to_owned(),
+                    },
+                ));
+            }
+            (_, [variable, value]) if is_variable(variable) => {
+                dimension_constraints.push((
+                    get_variable_name(variable)?,
+                    DimensionConstraint {
+                        operator: condition_operator,
+                        value: value.to_owned(),
+                    },
+                ));
+            }
+            (_, [value, variable]) if is_variable(variable) => {
+                dimension_constraints.push((
+                    get_variable_name(variable)?,
+                    DimensionConstraint {
+                        operator: condition_operator.flip(),
+                        value: value.to_owned(),
+                    },
+                ));
+            }
+            _ => {
+                return Err(result::AppError::BadArgument(
+                    "Failed to get variable name from operands list. Ensure the context provided obeys the rules of JSON logic"
+                        .into()
+                ));
+            }
+        }
+
+        for (dimension, constraint) in dimension_constraints {
+            constraints.entry(dimension).or_default().push(constraint);
+        }
+    }
+    Ok(())
+}
+
+/*
+  A context is an `and` of comparisons, each between a single dimension and a
+  value. The constraints on each dimension are returned in the order they appear.
+*/
+pub fn extract_dimension_constraints(
+    context: &Map<String, Value>,
+) -> result::Result<HashMap<String, Vec<DimensionConstraint>>> {
+    let mut constraints = HashMap::new();
+    collect_dimension_constraints(&Value::Object(context.to_owned()), &mut constraints)?;
+    Ok(constraints)
+}
+
+/*
+  Dimensions compared with `==` map to the value they are compared against,
+  other dimensions map to a list of `{operator: value}` objects so that contexts
+  with different constraints on a dimension never look the same.
+*/
+pub fn extract_dimensions(context: &Condition) -> result::Result<Map<String, Value>> {
+    let dimensions = extract_dimension_constraints(context)?.into_iter().map(
+        |(dimension, constraints)| {
+            let value = match constraints.as_slice() {
+                [DimensionConstraint {
+                    operator: ConditionOperator::Equals | ConditionOperator::Contains,
+                    value,
+                }] => value.to_owned(),
+                _ => Value::Array(
+                    constraints
+                        .into_iter()
+                        .map(|constraint| {
+                            Value::Object(Map::from_iter([(
+                                constraint.operator.to_string(),
+                                constraint.value,
+                            )]))
+                        })
+                        .collect(),
+                ),
+            };
+            (dimension, value)
+        },
+    );
+
+    Ok(Map::from_iter(dimensions))
 }
 
 pub fn validation_err_to_str(errors: Vec<ValidationError>) -> Vec<String> {
@@ -375,29 +738,32 @@ pub fn generate_snowflake_id(state: &Data<AppState>) -> result::Result<i64> {
     Ok(id)
 }
 
-pub fn parse_config_tags(
-    config_tags: Option<String>,
-) -> result::Result<Option<Vec<String>>> {
+pub fn validate_config_tags(tags: Vec<String>) -> result::Result<Vec<String>> {
     let regex = Regex::new(CONFIG_TAG_REGEX).map_err(|err| {
         log::error!("regex match failed for tags {}", err);
         result::AppError::UnexpectedError(anyhow!("Something went wrong"))
     })?;
+    tags.into_iter()
+        .map(|tag| {
+            if !regex.is_match(&tag) {
+                Err(result::AppError::BadArgument(
+                    "Invalid config_tags value".to_string(),
+                ))
+            } else {
+                Ok(tag)
+            }
+        })
+        .collect()
+}
+
+pub fn parse_config_tags(
+    config_tags: Option<String>,
+) -> result::Result<Option<Vec<String>>> {
     match config_tags {
         None => Ok(None),
         Some(val) => {
-            let tags = val
-                .split(',')
-                .map(|s| {
-                    if !regex.is_match(s) {
-                        Err(result::AppError::BadArgument(
-                            "Invalid config_tags value".to_string(),
-                        ))
-                    } else {
-                        Ok(s.to_owned())
-                    }
-                })
-                .collect::<result::Result<Vec<String>>>()?;
-            Ok(Some(tags))
+            let tags = val.split(',').map(String::from).collect();
+            validate_config_tags(tags).map(Some)
         }
     }
 }
@@ -12,6 +12,7 @@ use jsonschema::JSONSchema;
 use serde_json::json;
 use snowflake::SnowflakeIdGenerator;
 use superposition_types::TenantConfig;
+use tokio::sync::watch;
 
 use crate::db::pgschema_manager::{PgSchemaConnection, PgSchemaManager};
 
@@ -35,6 +36,7 @@ pub enum AppHeader {
     XConfigVersion,
     XAuditId,
     LastModified,
+    XDeprecatedKeys,
 }
 
 pub struct AppState {
@@ -51,6 +53,7 @@ pub struct AppState {
     pub service_prefix: String,
     pub tenant_configs: HashMap<String, TenantConfig>,
     pub superposition_token: String,
+    pub config_version_watchers: Mutex<HashMap<String, Arc<watch::Sender<Option<i64>>>>>,
 }
 
 impl FromStr for AppEnv {
@@ -92,6 +95,22 @@ impl FromRequest for AppScope {
 #[derive(Deref, DerefMut, Clone, Debug)]
 pub struct AppExecutionNamespace(pub String);
 impl AppExecutionNamespace {
+    /// Namespace holding the tables of `scope` for `tenant`, `None` when tenants are
+    /// enabled and no tenant is given.
+    pub fn for_scope(
+        app_state: &AppState,
+        tenant: Option<&Tenant>,
+        scope: AppScope,
+    ) -> Option<Self> {
+        match (app_state.enable_tenant_and_scope, tenant) {
+            (false, _) => Some(AppExecutionNamespace("cac_v1".to_string())),
+            (true, Some(t)) => {
+                Some(AppExecutionNamespace(format!("{}_{}", t.as_str(), scope)))
+            }
+            (true, None) => None,
+        }
+    }
+
     pub fn from_request_sync(req: &actix_web::HttpRequest) -> Result<Self, Error> {
         let app_state = match req.app_data::<Data<AppState>>() {
             Some(val) => val,
@@ -104,28 +123,25 @@ impl AppExecutionNamespace {
         let tenant = req.extensions().get::<Tenant>().cloned();
         let scope = req.extensions().get::<AppScope>().cloned();
 
-        match (
-            app_state.enable_tenant_and_scope,
-            app_state.app_env,
-            tenant,
-            scope,
-        ) {
-            (false, _, _, _) => Ok(AppExecutionNamespace("cac_v1".to_string())),
-            (true, _, Some(t), Some(s)) => {
-                Ok(AppExecutionNamespace(format!("{}_{}", t.as_str(), s)))
-            }
-            (true, _, None, _) => {
+        match (app_state.enable_tenant_and_scope, &tenant, scope) {
+            (true, None, _) => {
                 log::error!(
                     "get_app_execution_namespace: Tenant not set in request extensions"
                 );
                 Err(error::ErrorInternalServerError(""))
             }
-            (true, _, _, None) => {
+            (true, _, None) => {
                 log::error!(
                     "get_app_execution_namespace: AppScope not set in request extensions"
                 );
                 Err(error::ErrorInternalServerError(""))
             }
+            (_, _, scope) => Self::for_scope(
+                app_state,
+                tenant.as_ref(),
+                scope.unwrap_or(AppScope::CAC),
+            )
+            .ok_or_else(|| error::ErrorInternalServerError("")),
         }
     }
 }
@@ -178,6 +194,10 @@ impl FromRequest for Tenant {
     }
 }
 
+/// Marks requests authenticated with the internal token, sent by superposition itself.
+#[derive(Clone, Copy)]
+pub struct InternalRequest;
+
 #[derive(Deref, DerefMut)]
 pub struct DbConnection(pub PgSchemaConnection);
 impl FromRequest for DbConnection {
@@ -219,6 +239,7 @@ impl FromRequest for DbConnection {
 
 pub struct CustomHeaders {
     pub config_tags: Option<String>,
+    pub reviewers: Option<String>,
 }
 impl FromRequest for CustomHeaders {
     type Error = Error;
@@ -233,6 +254,9 @@ impl FromRequest for CustomHeaders {
             config_tags: header_val.get("x-config-tags").and_then(|header_val| {
                 header_val.to_str().map_or(None, |v| Some(v.to_string()))
             }),
+            reviewers: header_val.get("x-reviewers").and_then(|header_val| {
+                header_val.to_str().map_or(None, |v| Some(v.to_string()))
+            }),
         };
         ready(Ok(val))
     }
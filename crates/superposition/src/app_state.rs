@@ -93,5 +93,6 @@ pub async fn get(
         service_prefix,
         tenant_configs,
         superposition_token: get_superposition_token(&kms_client, &app_env).await,
+        config_version_watchers: Mutex::new(HashMap::new()),
     }
 }
@@ -11,7 +11,9 @@ use actix_web::{
     web::{self, get, scope, Data, PathConfig},
     App, HttpMessage, HttpResponse, HttpServer,
 };
+use context_aware_config::api::functions::helpers::report_functions_using_axios;
 use context_aware_config::api::*;
+use context_aware_config::scheduler::start_context_scheduler;
 use experimentation_platform::api::*;
 use frontend::app::*;
 use frontend::types::Envs as UIEnvs;
@@ -22,7 +24,7 @@ use service_utils::{
     middlewares::{
         app_scope::AppExecutionScopeMiddlewareFactory, tenant::TenantMiddlewareFactory,
     },
-    service::types::{AppScope, AppState},
+    service::types::{AppScope, AppState, InternalRequest},
 };
 use superposition_types::User;
 
@@ -91,6 +93,8 @@ async fn main() -> Result<()> {
 
     let app_state =
         Data::new(app_state::get(service_prefix_str.to_owned(), &base, &tenants).await);
+    report_functions_using_axios(&app_state);
+    start_context_scheduler(app_state.clone());
 
     HttpServer::new(move || {
         let leptos_options = &conf.leptos_options;
@@ -104,10 +108,12 @@ async fn main() -> Result<()> {
                 let user = req.headers().get(header::AUTHORIZATION).and_then(|auth| auth.to_str().ok()).and_then(|auth| {
                     let mut token = auth.split(' ').into_iter();
                     match (token.next(), token.next()) {
-                        (Some("Internal"), Some(token)) if token == state.superposition_token => 
+                        (Some("Internal"), Some(token)) if token == state.superposition_token => {
+                            req.extensions_mut().insert(InternalRequest);
                             req.headers().get("x-user").and_then(|auth| auth.to_str().ok()).and_then(|user_str| {
                                 serde_json::from_str::<User>(user_str).ok()
-                            }),
+                            })
+                        }
                         (_, _) => None
                     }
                 }).unwrap_or_default();
@@ -174,6 +180,16 @@ async fn main() -> Result<()> {
                             .wrap(AppExecutionScopeMiddlewareFactory::new(AppScope::CAC))
                             .service(type_templates::endpoints()),
                     )
+                    .service(
+                        scope("/tenant")
+                            .wrap(AppExecutionScopeMiddlewareFactory::new(AppScope::CAC))
+                            .service(tenant::endpoints()),
+                    )
+                    .service(
+                        scope("/change-requests")
+                            .wrap(AppExecutionScopeMiddlewareFactory::new(AppScope::CAC))
+                            .service(change_requests::endpoints()),
+                    )
                     .service(
                         experiments::endpoints(scope("/experiments")).wrap(
                             AppExecutionScopeMiddlewareFactory::new(AppScope::EXPERIMENTATION),
@@ -2,6 +2,7 @@
 pub mod custom_query;
 #[cfg(feature = "result")]
 pub mod result;
+pub mod semantic_version;
 
 use std::fmt::Display;
 use std::future::{ready, Ready};
@@ -246,6 +247,8 @@ impl Display for RegexEnum {
 #[derive(Clone, Deserialize)]
 pub struct TenantConfig {
     pub mandatory_dimensions: Vec<String>,
+    #[serde(default)]
+    pub require_change_requests: bool,
 }
 
 impl FromRequest for TenantConfig {
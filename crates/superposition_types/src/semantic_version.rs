@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+
+use semver::Version;
+use serde_json::{Map, Value};
+
+/*
+  Versions are compared using semantic version precedence, so `1.10.0` is greater
+  than `1.9.0` and pre-releases sort before their release. Versions with fewer
+  components like `1.9` are read as `1.9.0`, a single number is not treated as a
+  version.
+*/
+pub fn parse_version(value: &str) -> Option<Version> {
+    let core_end = value.find(['-', '+']).unwrap_or(value.len());
+    let (core, suffix) = value.split_at(core_end);
+    let components = core.split('.').collect::<Vec<_>>();
+    if components.len() < 2
+        || components.len() > 3
+        || components
+            .iter()
+            .any(|c| c.is_empty() || !c.chars().all(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+    let padding = ".0".repeat(3 - components.len());
+    Version::parse(&format!("{core}{padding}{suffix}")).ok()
+}
+
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    Some(parse_version(a)?.cmp_precedence(&parse_version(b)?))
+}
+
+fn version_operand(operand: &Value, data: &Value) -> Option<Version> {
+    match operand {
+        Value::String(value) => parse_version(value),
+        Value::Object(variable) => variable
+            .get("var")
+            .and_then(Value::as_str)
+            .and_then(|name| data.get(name))
+            .and_then(Value::as_str)
+            .and_then(parse_version),
+        _ => None,
+    }
+}
+
+fn compare_version_operands(
+    operator: &str,
+    operands: &[Value],
+    data: &Value,
+) -> Option<bool> {
+    let versions = operands
+        .iter()
+        .map(|operand| version_operand(operand, data))
+        .collect::<Option<Vec<_>>>()?;
+    let orderings = versions
+        .windows(2)
+        .map(|pair| pair[0].cmp_precedence(&pair[1]))
+        .collect::<Vec<_>>();
+    match (operator, orderings.as_slice()) {
+        ("==", [ordering]) => Some(ordering.is_eq()),
+        ("!=", [ordering]) => Some(ordering.is_ne()),
+        (">", [ordering]) => Some(ordering.is_gt()),
+        (">=", [ordering]) => Some(ordering.is_ge()),
+        ("<", [_] | [_, _]) => Some(orderings.iter().all(|o| o.is_lt())),
+        ("<=", [_] | [_, _]) => Some(orderings.iter().all(|o| o.is_le())),
+        _ => None,
+    }
+}
+
+/*
+  jsonlogic compares strings lexicographically, so comparisons where every operand
+  is a version, either a literal or a dimension whose value in `data` is one, are
+  replaced by their outcome before the condition is handed over to jsonlogic.
+  Comparisons on dimensions missing from `data` are left as they are.
+*/
+pub fn resolve_version_comparisons(condition: &Value, data: &Value) -> Value {
+    match condition {
+        Value::Object(expression) if expression.len() == 1 => {
+            let Some((operator, operands)) = expression.iter().next() else {
+                return condition.clone();
+            };
+            let Value::Array(operands) = operands else {
+                return condition.clone();
+            };
+            if let Some(outcome) = compare_version_operands(operator, operands, data) {
+                return Value::Bool(outcome);
+            }
+            let operands = operands
+                .iter()
+                .map(|operand| resolve_version_comparisons(operand, data))
+                .collect();
+            Value::Object(Map::from_iter([(
+                operator.to_owned(),
+                Value::Array(operands),
+            )]))
+        }
+        _ => condition.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("1.9", "1.9.0"), Some(Ordering::Equal));
+        assert_eq!(
+            compare_versions("2.0.0-beta.1", "2.0.0"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(compare_versions("2.0.0+42", "2.0.0"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("12", "1.9.0"), None);
+        assert_eq!(compare_versions("android", "1.9.0"), None);
+    }
+
+    #[test]
+    fn test_resolve_version_comparisons() {
+        let condition = json!({"and": [
+            {"==": [{"var": "os"}, "android"]},
+            {">=": [{"var": "app_version"}, "1.9.0"]},
+            {"<": ["1.0", {"var": "app_version"}, "2.0.0"]}
+        ]});
+
+        assert_eq!(
+            resolve_version_comparisons(
+                &condition,
+                &json!({"os": "android", "app_version": "1.10.0"})
+            ),
+            json!({"and": [
+                {"==": [{"var": "os"}, "android"]},
+                true,
+                true
+            ]})
+        );
+        assert_eq!(
+            resolve_version_comparisons(
+                &condition,
+                &json!({"os": "android", "app_version": "2.1.0"})
+            ),
+            json!({"and": [
+                {"==": [{"var": "os"}, "android"]},
+                true,
+                false
+            ]})
+        );
+        // dimensions without a value are left for jsonlogic to deal with
+        assert_eq!(
+            resolve_version_comparisons(&condition, &json!({"os": "android"})),
+            condition
+        );
+    }
+}
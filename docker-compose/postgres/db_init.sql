@@ -4936,7 +4936,7 @@ foo.foo	1	2024-08-22 12:13:24.43122+00	user@superposition.io	{"type":"integer"}
 foo.bar	2	2024-08-22 12:13:37.788173+00	user@superposition.io	{"type":"number"}	\N	2024-08-22 12:13:37.788184	user@superposition.io
 hello_message	"Hello World !!!"	2024-08-22 12:14:02.505322+00	user@superposition.io	{"pattern":".*","type":"string"}	\N	2024-08-22 12:14:02.505334	user@superposition.io
 hello_message_color	"black"	2024-08-22 12:14:27.222544+00	user@superposition.io	{"enum":["black","red","blue","green","pink"],"type":"string"}	\N	2024-08-22 12:14:27.222551	user@superposition.io
-logo	"https://cdn1.vectorstock.com/i/1000x1000/03/20/big-city-logo-vector-20480320.jpg"	2024-08-22 12:15:08.701643+00	user@superposition.io	{"pattern":"https://.*","type":"string"}	axios_validator_example	2024-08-22 12:15:08.701648	user@superposition.io
+logo	"https://cdn1.vectorstock.com/i/1000x1000/03/20/big-city-logo-vector-20480320.jpg"	2024-08-22 12:15:08.701643+00	user@superposition.io	{"pattern":"https://.*","type":"string"}	url_validator_example	2024-08-22 12:15:08.701648	user@superposition.io
 \.
 
 
@@ -5305,7 +5305,7 @@ COPY dev_cac.event_log_y2026m12 (id, table_name, user_name, "timestamp", action,
 --
 
 COPY dev_cac.functions (function_name, published_code, draft_code, function_description, published_runtime_version, draft_runtime_version, published_at, draft_edited_at, published_by, draft_edited_by, last_modified_at, last_modified_by) FROM stdin;
-axios_validator_example	YXN5bmMgZnVuY3Rpb24gdmFsaWRhdGUoa2V5LCB2YWx1ZSkgewogICAgcmV0dXJuIGF4aW9zLmdldCh2YWx1ZSkudGhlbihyZXNwb25zZSA9PiByZXNwb25zZS5zdGF0dXMgPT0gJzIwMCcpCiAgICAuY2F0Y2goZXJyID0+IHsKICAgICAgICBjb25zb2xlLmxvZyhlcnIpOwogICAgICAgIHJldHVybiBmYWxzZTsKICAgIH0pOwp9Cg==	YXN5bmMgZnVuY3Rpb24gdmFsaWRhdGUoa2V5LCB2YWx1ZSkgewogICAgcmV0dXJuIGF4aW9zLmdldCh2YWx1ZSkudGhlbihyZXNwb25zZSA9PiByZXNwb25zZS5zdGF0dXMgPT0gJzIwMCcpCiAgICAuY2F0Y2goZXJyID0+IHsKICAgICAgICBjb25zb2xlLmxvZyhlcnIpOwogICAgICAgIHJldHVybiBmYWxzZTsKICAgIH0pOwp9Cg==	An example function that shows off validator functions	1.0.0	1.0.0	2024-08-22 12:10:48.787527	2024-08-22 12:10:44.416185	user@superposition.io	user@superposition.io	2024-08-22 12:10:44.416187	user@superposition.io
+url_validator_example	Ly8gRnVuY3Rpb25zIGhhdmUgbm8gbmV0d29yayBhY2Nlc3MsIHNvIHRoZSB1cmwgaXMgdmFsaWRhdGVkIGJ5IGl0cyBzaGFwZS4KLy8gVGhpcyBmdW5jdGlvbiBzaG91bGQgcmV0dXJuIGEgYm9vbGVhbiwgdHJ1ZSBmb3Igc3VjY2Vzc2Z1bCB2YWxpZGF0aW9uIGFuZCBmYWxzZSBpZiBub3QuCmFzeW5jIGZ1bmN0aW9uIHZhbGlkYXRlKGtleSwgdmFsdWUpIHsKICAgIHJldHVybiB0eXBlb2YgdmFsdWUgPT09ICJzdHJpbmciICYmIC9eaHR0cHM6XC9cL1teXHNcL10rXC5bXlxzXSskLy50ZXN0KHZhbHVlKTsKfQo=	Ly8gRnVuY3Rpb25zIGhhdmUgbm8gbmV0d29yayBhY2Nlc3MsIHNvIHRoZSB1cmwgaXMgdmFsaWRhdGVkIGJ5IGl0cyBzaGFwZS4KLy8gVGhpcyBmdW5jdGlvbiBzaG91bGQgcmV0dXJuIGEgYm9vbGVhbiwgdHJ1ZSBmb3Igc3VjY2Vzc2Z1bCB2YWxpZGF0aW9uIGFuZCBmYWxzZSBpZiBub3QuCmFzeW5jIGZ1bmN0aW9uIHZhbGlkYXRlKGtleSwgdmFsdWUpIHsKICAgIHJldHVybiB0eXBlb2YgdmFsdWUgPT09ICJzdHJpbmciICYmIC9eaHR0cHM6XC9cL1teXHNcL10rXC5bXlxzXSskLy50ZXN0KHZhbHVlKTsKfQo=	An example function that validates URLs	1.0.0	1.0.0	2024-08-22 12:10:48.787527	2024-08-22 12:10:44.416185	user@superposition.io	user@superposition.io	2024-08-22 12:10:44.416187	user@superposition.io
 \.
 
 
@@ -5722,7 +5722,7 @@ per_distance_unit_rate	15.0	2024-08-22 10:52:44.764395+00	user@superposition.io
 distance_unit	"Km"	2024-08-22 10:53:06.368909+00	user@superposition.io	{"enum":["Km","Miles"],"type":"string"}	\N	2024-08-22 10:53:06.368914	user@superposition.io
 hello_message	"Hello World !!!"	2024-08-22 10:53:50.551716+00	user@superposition.io	{"pattern":".*","type":"string"}	\N	2024-08-22 10:53:50.55172	user@superposition.io
 hello_message_color	"black"	2024-08-22 10:54:12.197449+00	user@superposition.io	{"enum":["black","red","blue","green","pink"],"type":"string"}	\N	2024-08-22 10:54:12.197459	user@superposition.io
-logo	"https://cdn1.vectorstock.com/i/1000x1000/03/20/big-city-logo-vector-20480320.jpg"	2024-08-22 10:54:34.169556+00	user@superposition.io	{"pattern":"https://.*","type":"string"}	axios_validator_example	2024-08-22 11:07:03.007146	user@superposition.io
+logo	"https://cdn1.vectorstock.com/i/1000x1000/03/20/big-city-logo-vector-20480320.jpg"	2024-08-22 10:54:34.169556+00	user@superposition.io	{"pattern":"https://.*","type":"string"}	url_validator_example	2024-08-22 11:07:03.007146	user@superposition.io
 \.
 
 
@@ -6100,7 +6100,7 @@ COPY public.experiments (id, created_at, created_by, last_modified, name, overri
 --
 
 COPY public.functions (function_name, published_code, draft_code, function_description, published_runtime_version, draft_runtime_version, published_at, draft_edited_at, published_by, draft_edited_by, last_modified_at, last_modified_by) FROM stdin;
-axios_validator_example	Ly8gVGhpcyBmdW5jdGlvbiBzaG91bGQgcmV0dXJuIGEgYm9vbGVhbiwgdHJ1ZSBmb3Igc3VjY2Vzc2Z1bCB2YWxpZGF0aW9uIGFuZCBmYWxzZSBpZiBub3QuIElmIHRoZSB2YWxpZGF0ZSBmdW5jdGlvbiByZXR1cm5zIGZhbHNlLAovLyBTdXBlcnBvc2l0aW9uIHdpbGwgbm90IGFsbG93IHRoZSBpdGVtIHRvIGJlIHNhdmVkCgphc3luYyBmdW5jdGlvbiB2YWxpZGF0ZShrZXksIHZhbHVlKSB7CiAgICByZXR1cm4gYXhpb3MuZ2V0KHZhbHVlKS50aGVuKHJlc3BvbnNlID0+IHJlc3BvbnNlLnN0YXR1cyA9PSAnMjAwJykKICAgIC5jYXRjaChlcnIgPT4gewogICAgICAgIGNvbnNvbGUubG9nKGVycik7CiAgICAgICAgcmV0dXJuIGZhbHNlOwogICAgfSk7Cn0K	Ly8gVGhpcyBmdW5jdGlvbiBzaG91bGQgcmV0dXJuIGEgYm9vbGVhbiwgdHJ1ZSBmb3Igc3VjY2Vzc2Z1bCB2YWxpZGF0aW9uIGFuZCBmYWxzZSBpZiBub3QuIElmIHRoZSB2YWxpZGF0ZSBmdW5jdGlvbiByZXR1cm5zIGZhbHNlLAovLyBTdXBlcnBvc2l0aW9uIHdpbGwgbm90IGFsbG93IHRoZSBpdGVtIHRvIGJlIHNhdmVkCgphc3luYyBmdW5jdGlvbiB2YWxpZGF0ZShrZXksIHZhbHVlKSB7CiAgICByZXR1cm4gYXhpb3MuZ2V0KHZhbHVlKS50aGVuKHJlc3BvbnNlID0+IHJlc3BvbnNlLnN0YXR1cyA9PSAnMjAwJykKICAgIC5jYXRjaChlcnIgPT4gewogICAgICAgIGNvbnNvbGUubG9nKGVycik7CiAgICAgICAgcmV0dXJuIGZhbHNlOwogICAgfSk7Cn0K	A function that validates URLs using axios	1.0.0	1.0.0	2024-08-22 11:02:09.420582	2024-08-22 11:02:07.801713	user@superposition.io	user@superposition.io	2024-08-22 11:02:07.801715	user@superposition.io
+url_validator_example	Ly8gRnVuY3Rpb25zIGhhdmUgbm8gbmV0d29yayBhY2Nlc3MsIHNvIHRoZSB1cmwgaXMgdmFsaWRhdGVkIGJ5IGl0cyBzaGFwZS4KLy8gVGhpcyBmdW5jdGlvbiBzaG91bGQgcmV0dXJuIGEgYm9vbGVhbiwgdHJ1ZSBmb3Igc3VjY2Vzc2Z1bCB2YWxpZGF0aW9uIGFuZCBmYWxzZSBpZiBub3QuCmFzeW5jIGZ1bmN0aW9uIHZhbGlkYXRlKGtleSwgdmFsdWUpIHsKICAgIHJldHVybiB0eXBlb2YgdmFsdWUgPT09ICJzdHJpbmciICYmIC9eaHR0cHM6XC9cL1teXHNcL10rXC5bXlxzXSskLy50ZXN0KHZhbHVlKTsKfQo=	Ly8gRnVuY3Rpb25zIGhhdmUgbm8gbmV0d29yayBhY2Nlc3MsIHNvIHRoZSB1cmwgaXMgdmFsaWRhdGVkIGJ5IGl0cyBzaGFwZS4KLy8gVGhpcyBmdW5jdGlvbiBzaG91bGQgcmV0dXJuIGEgYm9vbGVhbiwgdHJ1ZSBmb3Igc3VjY2Vzc2Z1bCB2YWxpZGF0aW9uIGFuZCBmYWxzZSBpZiBub3QuCmFzeW5jIGZ1bmN0aW9uIHZhbGlkYXRlKGtleSwgdmFsdWUpIHsKICAgIHJldHVybiB0eXBlb2YgdmFsdWUgPT09ICJzdHJpbmciICYmIC9eaHR0cHM6XC9cL1teXHNcL10rXC5bXlxzXSskLy50ZXN0KHZhbHVlKTsKfQo=	An example function that validates URLs	1.0.0	1.0.0	2024-08-22 11:02:09.420582	2024-08-22 11:02:07.801713	user@superposition.io	user@superposition.io	2024-08-22 11:02:07.801715	user@superposition.io
 \.
 
 
@@ -19271,6 +19271,332 @@ VALUES (
         NOW()
     );
 
+-- Your SQL goes here
+-- Name: function_versions; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.function_versions (
+    function_name text NOT NULL REFERENCES dev_cac.functions(function_name) ON DELETE CASCADE,
+    version integer NOT NULL,
+    code text NOT NULL,
+    runtime_version VARCHAR(16) NOT NULL,
+    description text NOT NULL,
+    published_by text NOT NULL,
+    published_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (function_name, version)
+);
+--
+-- Name: function_versions function_versions_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER function_versions_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.function_versions FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+
+ALTER TABLE dev_cac.functions
+add column published_version integer;
+
+ALTER TABLE dev_cac.contexts
+add column function_versions json DEFAULT '{}'::json NOT NULL;
+
+-- functions published so far become their first version
+INSERT INTO dev_cac.function_versions (function_name, version, code, runtime_version, description, published_by, published_at)
+SELECT function_name, 1, published_code, published_runtime_version, function_description, COALESCE(published_by, draft_edited_by), COALESCE(published_at, CURRENT_TIMESTAMP)
+FROM dev_cac.functions
+WHERE published_code IS NOT NULL AND published_runtime_version IS NOT NULL;
+
+UPDATE dev_cac.functions
+SET published_version = 1
+WHERE published_code IS NOT NULL AND published_runtime_version IS NOT NULL;
+
+-- Your SQL goes here
+-- Name: function_versions; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.function_versions (
+    function_name text NOT NULL REFERENCES test_cac.functions(function_name) ON DELETE CASCADE,
+    version integer NOT NULL,
+    code text NOT NULL,
+    runtime_version VARCHAR(16) NOT NULL,
+    description text NOT NULL,
+    published_by text NOT NULL,
+    published_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (function_name, version)
+);
+--
+-- Name: function_versions function_versions_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER function_versions_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.function_versions FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+
+ALTER TABLE test_cac.functions
+add column published_version integer;
+
+ALTER TABLE test_cac.contexts
+add column function_versions json DEFAULT '{}'::json NOT NULL;
+
+-- functions published so far become their first version
+INSERT INTO test_cac.function_versions (function_name, version, code, runtime_version, description, published_by, published_at)
+SELECT function_name, 1, published_code, published_runtime_version, function_description, COALESCE(published_by, draft_edited_by), COALESCE(published_at, CURRENT_TIMESTAMP)
+FROM test_cac.functions
+WHERE published_code IS NOT NULL AND published_runtime_version IS NOT NULL;
+
+UPDATE test_cac.functions
+SET published_version = 1
+WHERE published_code IS NOT NULL AND published_runtime_version IS NOT NULL;
+
+-- Your SQL goes here
+ALTER TABLE dev_cac.functions
+add column test_cases json DEFAULT '[]'::json NOT NULL;
+
+-- Your SQL goes here
+ALTER TABLE test_cac.functions
+add column test_cases json DEFAULT '[]'::json NOT NULL;
+
+-- Your SQL goes here
+-- Name: dimension_aliases; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.dimension_aliases (
+    alias character varying NOT NULL PRIMARY KEY,
+    dimension character varying NOT NULL REFERENCES dev_cac.dimensions(dimension) ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp without time zone NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: dimension_aliases dimension_aliases_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER dimension_aliases_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.dimension_aliases FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: dimension_aliases; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.dimension_aliases (
+    alias character varying NOT NULL PRIMARY KEY,
+    dimension character varying NOT NULL REFERENCES test_cac.dimensions(dimension) ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp without time zone NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: dimension_aliases dimension_aliases_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER dimension_aliases_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.dimension_aliases FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: default_config_aliases; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.default_config_aliases (
+    alias character varying NOT NULL PRIMARY KEY,
+    key character varying NOT NULL REFERENCES dev_cac.default_configs(key) ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp without time zone NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: default_config_aliases default_config_aliases_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER default_config_aliases_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.default_config_aliases FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: default_config_aliases; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.default_config_aliases (
+    alias character varying NOT NULL PRIMARY KEY,
+    key character varying NOT NULL REFERENCES test_cac.default_configs(key) ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp without time zone NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: default_config_aliases default_config_aliases_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER default_config_aliases_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.default_config_aliases FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: default_config_deprecations; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.default_config_deprecations (
+    key character varying NOT NULL PRIMARY KEY REFERENCES dev_cac.default_configs(key) ON UPDATE CASCADE ON DELETE CASCADE,
+    sunset_at timestamp without time zone NOT NULL,
+    replacement_key character varying REFERENCES dev_cac.default_configs(key) ON UPDATE CASCADE ON DELETE SET NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: default_config_deprecations default_config_deprecations_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER default_config_deprecations_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.default_config_deprecations FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: default_config_deprecations; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.default_config_deprecations (
+    key character varying NOT NULL PRIMARY KEY REFERENCES test_cac.default_configs(key) ON UPDATE CASCADE ON DELETE CASCADE,
+    sunset_at timestamp without time zone NOT NULL,
+    replacement_key character varying REFERENCES test_cac.default_configs(key) ON UPDATE CASCADE ON DELETE SET NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+--
+-- Name: default_config_deprecations default_config_deprecations_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER default_config_deprecations_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.default_config_deprecations FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: change_request_status; Type: TYPE; Schema: dev_cac; Owner: -
+--
+CREATE TYPE dev_cac.change_request_status AS ENUM (
+    'PENDING',
+    'APPLIED',
+    'REJECTED'
+);
+--
+-- Name: change_requests; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.change_requests (
+    id bigint NOT NULL PRIMARY KEY,
+    status dev_cac.change_request_status NOT NULL,
+    change json NOT NULL,
+    diff json NOT NULL,
+    config_tags character varying[],
+    reviewers character varying[] DEFAULT '{}'::character varying[] NOT NULL,
+    author json NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    reviewed_by character varying,
+    reviewed_at timestamp without time zone,
+    config_version bigint
+);
+CREATE INDEX IF NOT EXISTS change_requests_status_index ON dev_cac.change_requests(status);
+--
+-- Name: change_requests change_requests_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER change_requests_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.change_requests FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+--
+-- Name: change_request_comments; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.change_request_comments (
+    id bigint NOT NULL PRIMARY KEY,
+    change_request_id bigint NOT NULL REFERENCES dev_cac.change_requests(id) ON DELETE CASCADE,
+    comment text NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS change_request_comments_change_request_id_index ON dev_cac.change_request_comments(change_request_id);
+--
+-- Name: change_request_comments change_request_comments_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER change_request_comments_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.change_request_comments FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+
+-- Your SQL goes here
+-- Name: change_request_status; Type: TYPE; Schema: test_cac; Owner: -
+--
+CREATE TYPE test_cac.change_request_status AS ENUM (
+    'PENDING',
+    'APPLIED',
+    'REJECTED'
+);
+--
+-- Name: change_requests; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.change_requests (
+    id bigint NOT NULL PRIMARY KEY,
+    status test_cac.change_request_status NOT NULL,
+    change json NOT NULL,
+    diff json NOT NULL,
+    config_tags character varying[],
+    reviewers character varying[] DEFAULT '{}'::character varying[] NOT NULL,
+    author json NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    reviewed_by character varying,
+    reviewed_at timestamp without time zone,
+    config_version bigint
+);
+CREATE INDEX IF NOT EXISTS change_requests_status_index ON test_cac.change_requests(status);
+--
+-- Name: change_requests change_requests_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER change_requests_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.change_requests FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+--
+-- Name: change_request_comments; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.change_request_comments (
+    id bigint NOT NULL PRIMARY KEY,
+    change_request_id bigint NOT NULL REFERENCES test_cac.change_requests(id) ON DELETE CASCADE,
+    comment text NOT NULL,
+    created_by character varying NOT NULL,
+    created_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS change_request_comments_change_request_id_index ON test_cac.change_request_comments(change_request_id);
+--
+-- Name: change_request_comments change_request_comments_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER change_request_comments_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.change_request_comments FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+
+-- Your SQL goes here
+ALTER TABLE dev_cac.contexts
+add column active_from timestamp with time zone,
+add column active_until timestamp with time zone,
+add constraint contexts_active_window_check CHECK (active_from < active_until);
+--
+-- Name: contexts_active_until_index; Type: INDEX; Schema: dev_cac; Owner: -
+--
+CREATE INDEX IF NOT EXISTS contexts_active_until_index ON dev_cac.contexts USING btree (active_until) WHERE active_until IS NOT NULL;
+--
+-- Name: archived_contexts; Type: TABLE; Schema: dev_cac; Owner: -
+--
+CREATE TABLE dev_cac.archived_contexts (
+    id character varying NOT NULL,
+    value json NOT NULL,
+    override_id character varying NOT NULL,
+    created_at timestamp with time zone NOT NULL,
+    created_by character varying NOT NULL,
+    priority integer NOT NULL,
+    override json NOT NULL,
+    last_modified_at timestamp without time zone NOT NULL,
+    last_modified_by character varying(200) NOT NULL,
+    function_versions json NOT NULL,
+    active_from timestamp with time zone,
+    active_until timestamp with time zone,
+    archived_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    archived_by character varying NOT NULL,
+    PRIMARY KEY (id, archived_at)
+);
+--
+-- Name: archived_contexts archived_contexts_audit; Type: TRIGGER; Schema: dev_cac; Owner: -
+--
+CREATE TRIGGER archived_contexts_audit AFTER INSERT OR DELETE OR UPDATE ON dev_cac.archived_contexts FOR EACH ROW EXECUTE FUNCTION dev_cac.event_logger();
+
+-- Your SQL goes here
+ALTER TABLE test_cac.contexts
+add column active_from timestamp with time zone,
+add column active_until timestamp with time zone,
+add constraint contexts_active_window_check CHECK (active_from < active_until);
+--
+-- Name: contexts_active_until_index; Type: INDEX; Schema: test_cac; Owner: -
+--
+CREATE INDEX IF NOT EXISTS contexts_active_until_index ON test_cac.contexts USING btree (active_until) WHERE active_until IS NOT NULL;
+--
+-- Name: archived_contexts; Type: TABLE; Schema: test_cac; Owner: -
+--
+CREATE TABLE test_cac.archived_contexts (
+    id character varying NOT NULL,
+    value json NOT NULL,
+    override_id character varying NOT NULL,
+    created_at timestamp with time zone NOT NULL,
+    created_by character varying NOT NULL,
+    priority integer NOT NULL,
+    override json NOT NULL,
+    last_modified_at timestamp without time zone NOT NULL,
+    last_modified_by character varying(200) NOT NULL,
+    function_versions json NOT NULL,
+    active_from timestamp with time zone,
+    active_until timestamp with time zone,
+    archived_at timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    archived_by character varying NOT NULL,
+    PRIMARY KEY (id, archived_at)
+);
+--
+-- Name: archived_contexts archived_contexts_audit; Type: TRIGGER; Schema: test_cac; Owner: -
+--
+CREATE TRIGGER archived_contexts_audit AFTER INSERT OR DELETE OR UPDATE ON test_cac.archived_contexts FOR EACH ROW EXECUTE FUNCTION test_cac.event_logger();
+
 --
 -- PostgreSQL database dump complete
 --
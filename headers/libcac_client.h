@@ -15,6 +15,8 @@ int cac_new_client(const char *tenant, unsigned long update_frequency, const cha
 
 void cac_start_polling_update(const char *tenant);
 
+void cac_start_streaming_update(const char *tenant);
+
 void cac_free_client(struct Arc_Client *ptr);
 
 struct Arc_Client *cac_get_client(const char *tenant);